@@ -0,0 +1,45 @@
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use ndbc_data::parse_std_met;
+use std::hint::black_box;
+
+const REALTIME_SAMPLE: &str = include_str!("../data-samples/realtimedata.txt");
+
+/// A year of 10-minute observations in the realtime layout (~52k rows).
+fn year_archive() -> String {
+    let header = REALTIME_SAMPLE.lines().take(2).collect::<Vec<_>>().join("\n");
+    let mut text = header + "\n";
+    let days_in_month = [31, 28, 31, 30, 31, 30, 31, 31, 30, 31, 30, 31];
+    for (m, days) in days_in_month.iter().enumerate() {
+        for d in 1..=*days {
+            for h in 0..24 {
+                for mn in (0..60).step_by(10) {
+                    text.push_str(&format!(
+                        "2024 {:02} {:02} {:02} {:02} 190  3.0  5.0   0.4     5   4.5  MM 1024.8  20.1    MM  10.7   MM -0.4    MM\n",
+                        m + 1,
+                        d,
+                        h,
+                        mn
+                    ));
+                }
+            }
+        }
+    }
+    text
+}
+
+fn bench_parse(c: &mut Criterion) {
+    let inputs = [("realtime_45day", REALTIME_SAMPLE.to_string()), ("archive_year", year_archive())];
+    for (name, text) in &inputs {
+        let rows = parse_std_met(text).expect("sample parses").height() as u64;
+
+        let mut group = c.benchmark_group("parse_std_met");
+        group.throughput(Throughput::Bytes(text.len() as u64));
+        group.bench_with_input(BenchmarkId::new("bytes", name), text, |b, t| b.iter(|| parse_std_met(black_box(t))));
+        group.throughput(Throughput::Elements(rows));
+        group.bench_with_input(BenchmarkId::new("rows", name), text, |b, t| b.iter(|| parse_std_met(black_box(t))));
+        group.finish();
+    }
+}
+
+criterion_group!(benches, bench_parse);
+criterion_main!(benches);
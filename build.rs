@@ -0,0 +1,24 @@
+use std::process::Command;
+
+fn main() {
+    // Short git hash of the source tree, or "unknown" outside a git checkout.
+    let git_hash = Command::new("git")
+        .args(["rev-parse", "--short", "HEAD"])
+        .output()
+        .ok()
+        .filter(|o| o.status.success())
+        .map(|o| String::from_utf8_lossy(&o.stdout).trim().to_string())
+        .unwrap_or_else(|| "unknown".to_string());
+    println!("cargo:rustc-env=NDBC_GIT_HASH={}", git_hash);
+
+    // Enabled cargo features, as cargo exposes them to build scripts.
+    let mut features: Vec<String> = std::env::vars()
+        .filter_map(|(k, _)| k.strip_prefix("CARGO_FEATURE_").map(|f| f.to_lowercase().replace('_', "-")))
+        .collect();
+    features.sort();
+    println!("cargo:rustc-env=NDBC_FEATURES={}", features.join(","));
+
+    println!("cargo:rustc-env=NDBC_PROFILE={}", std::env::var("PROFILE").unwrap_or_default());
+    println!("cargo:rerun-if-changed=.git/HEAD");
+    println!("cargo:rerun-if-changed=.git/refs/heads");
+}
@@ -0,0 +1,8 @@
+#YY  MM DD hh mm WVHT  SwH  SwP  WWH  WWP SwD WWD  STEEPNESS  APD MWD
+#yr  mo dy hr mn    m    m  sec    m  sec  -  degT     -      sec degT
+2025 11 13 01 40  1.2  0.9 11.1  0.8  5.3 ESE   E    AVERAGE  6.1 108
+2025 11 13 00 40  1.3  0.9 11.1  0.9  5.6 ESE   E    AVERAGE  6.3 104
+2025 11 12 23 40  1.3  1.0 10.0  0.8  5.0  SE ENE      STEEP  5.9 126
+2025 11 12 22 40  1.1  0.8 10.0  0.7  4.8  SE ENE        N/A  5.6 131
+2025 11 12 21 40   MM   MM   MM   MM   MM  MM  MM         MM   MM  MM
+2025 11 12 20 40  0.9  0.8  9.1  0.4  3.6  SE   E      SWELL  6.6 133
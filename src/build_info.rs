@@ -0,0 +1,26 @@
+//! Build-time information about the running library/binary.
+
+use serde::Serialize;
+
+/// Version, source revision and enabled features of this build.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct BuildInfo {
+    /// Crate version from `Cargo.toml`.
+    pub version: &'static str,
+    /// Short git hash of the source tree, or `unknown`.
+    pub git_hash: &'static str,
+    /// Cargo profile (`debug` or `release`).
+    pub profile: &'static str,
+    /// Enabled cargo features.
+    pub features: Vec<&'static str>,
+}
+
+/// Describe this build, e.g. for logging alongside produced files.
+pub fn build_info() -> BuildInfo {
+    BuildInfo {
+        version: env!("CARGO_PKG_VERSION"),
+        git_hash: env!("NDBC_GIT_HASH"),
+        profile: env!("NDBC_PROFILE"),
+        features: env!("NDBC_FEATURES").split(',').filter(|f| !f.is_empty()).collect(),
+    }
+}
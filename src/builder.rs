@@ -0,0 +1,402 @@
+//! Builder for configuring [`NdbcData`].
+
+use crate::http::{HttpVersion, RequestHeaders, RetryPolicy};
+use crate::{CoordsAs, Delimiter, DuplicateTimes, ExpectedColumns, MetadataFilter, DEFAULT_TREND_THRESHOLD_HPA, NdbcData, OutputFormat, PartitionScheme, ParquetOptions, Result, Revision, StrictLimits, Units, Window, DEFAULT_TIMEOUT, NDBC_BASE_URL};
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use reqwest::Url;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Default `User-Agent` sent with every request.
+pub const DEFAULT_USER_AGENT: &str = "ndbc-data-rust/0.1";
+
+/// Chainable configuration for [`NdbcData`]; obtain one with [`NdbcData::builder`].
+#[derive(Clone, Debug)]
+pub struct NdbcDataBuilder {
+    out_dir: Option<PathBuf>,
+    base_url: String,
+    user_agent: String,
+    timeout: Duration,
+    http_version: HttpVersion,
+    retry: RetryPolicy,
+    concurrency: usize,
+    format: OutputFormat,
+    #[cfg(feature = "sqlite")]
+    sqlite: crate::SqliteTarget,
+    parquet: ParquetOptions,
+    window: Window,
+    precision: Option<u32>,
+    units: Units,
+    derive: bool,
+    station_pressure: bool,
+    columns: Option<Vec<String>>,
+    station_metadata_columns: bool,
+    coords_as: CoordsAs,
+    partition: PartitionScheme,
+    trend_threshold: f64,
+    strict: Option<StrictLimits>,
+    duplicate_times: DuplicateTimes,
+    delimiter: Delimiter,
+    parse_issues: bool,
+    save_raw: bool,
+    expected_columns: ExpectedColumns,
+    metadata_filter: MetadataFilter,
+    revision: Option<Revision>,
+    incremental: Option<Duration>,
+    headers: RequestHeaders,
+    client: Option<reqwest::Client>,
+    #[cfg(feature = "object-store")]
+    store: Option<(Arc<dyn crate::ObjectStore>, crate::StorePath)>,
+}
+
+impl Default for NdbcDataBuilder {
+    fn default() -> Self {
+        Self {
+            out_dir: Some(PathBuf::from("data")),
+            base_url: NDBC_BASE_URL.to_string(),
+            user_agent: DEFAULT_USER_AGENT.to_string(),
+            timeout: DEFAULT_TIMEOUT,
+            http_version: HttpVersion::default(),
+            retry: RetryPolicy::default(),
+            concurrency: 4,
+            format: OutputFormat::default(),
+            #[cfg(feature = "sqlite")]
+            sqlite: crate::SqliteTarget::default(),
+            parquet: ParquetOptions::default(),
+            window: Window::default(),
+            precision: None,
+            units: Units::default(),
+            derive: false,
+            station_pressure: false,
+            columns: None,
+            station_metadata_columns: false,
+            coords_as: CoordsAs::Column,
+            partition: PartitionScheme::Flat,
+            trend_threshold: DEFAULT_TREND_THRESHOLD_HPA,
+            strict: None,
+            duplicate_times: DuplicateTimes::Keep,
+            delimiter: Delimiter::Whitespace,
+            parse_issues: false,
+            save_raw: false,
+            expected_columns: ExpectedColumns::default(),
+            metadata_filter: MetadataFilter::default(),
+            revision: None,
+            incremental: None,
+            headers: RequestHeaders::default(),
+            client: None,
+            #[cfg(feature = "object-store")]
+            store: None,
+        }
+    }
+}
+
+impl NdbcDataBuilder {
+    /// Directory output files are written to (default `data`).
+    pub fn out_dir(mut self, out_dir: impl Into<PathBuf>) -> Self {
+        self.out_dir = Some(out_dir.into());
+        self
+    }
+
+    /// Never touch the filesystem: no output directory is created and `.gitignore` is left
+    /// alone. Methods that write files then fail with [`NdbcError::NoOutputDir`](crate::NdbcError::NoOutputDir).
+    pub fn in_memory(mut self) -> Self {
+        self.out_dir = None;
+        self
+    }
+
+    /// Save fetched station files to `store` under `prefix` instead of the output directory
+    /// (feature `object-store`). Other files (metadata cache, run index) still go to the output
+    /// directory, if any.
+    #[cfg(feature = "object-store")]
+    pub fn store(mut self, store: Arc<dyn crate::ObjectStore>, prefix: crate::StorePath) -> Self {
+        self.store = Some((store, prefix));
+        self
+    }
+
+    /// Server to download from: scheme and host of an NDBC mirror or test server (default
+    /// [`NDBC_BASE_URL`]).
+    pub fn base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = base_url.into();
+        self
+    }
+
+    /// `User-Agent` header sent with every request.
+    pub fn user_agent(mut self, user_agent: impl Into<String>) -> Self {
+        self.user_agent = user_agent.into();
+        self
+    }
+
+    /// Per-request HTTP timeout (default 30 seconds).
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// HTTP protocol to use (default HTTP/1.1); see [`HttpVersion`]. Ignored when a client is
+    /// supplied with [`client`](Self::client).
+    pub fn http_version(mut self, version: HttpVersion) -> Self {
+        self.http_version = version;
+        self
+    }
+
+    /// Retries for transient HTTP failures (default 3; `0` disables retrying).
+    pub fn max_retries(mut self, max_retries: u32) -> Self {
+        self.retry.max_retries = max_retries;
+        self
+    }
+
+    /// Delay before the first retry; doubles on each further retry (default 500 ms).
+    pub fn retry_base_delay(mut self, delay: Duration) -> Self {
+        self.retry.base_delay = delay;
+        self
+    }
+
+    /// Longest wait between retries, capping both the backoff and a `429` response's
+    /// `Retry-After` (default [`DEFAULT_MAX_RETRY_DELAY`](crate::DEFAULT_MAX_RETRY_DELAY), 60 s).
+    pub fn retry_max_delay(mut self, delay: Duration) -> Self {
+        self.retry.max_delay = delay;
+        self
+    }
+
+    /// Number of stations processed at once by batch callers (default 4, minimum 1).
+    pub fn concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+
+    /// Output file format (default Parquet).
+    pub fn format(mut self, format: OutputFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    /// Database file and table for [`OutputFormat::Sqlite`] (feature `sqlite`).
+    #[cfg(feature = "sqlite")]
+    pub fn sqlite_target(mut self, target: crate::SqliteTarget) -> Self {
+        self.sqlite = target;
+        self
+    }
+
+    /// Codec, row group size and statistics of written Parquet files (default zstd, polars' row
+    /// group size, statistics on).
+    pub fn parquet_options(mut self, options: ParquetOptions) -> Self {
+        self.parquet = options;
+        self
+    }
+
+    /// Realtime file downloaded by `fetch_and_save_station` (default 45-day).
+    pub fn window(mut self, window: Window) -> Self {
+        self.window = window;
+        self
+    }
+
+    /// Round float columns to this many decimals in text outputs (JSON Lines and CSV); Parquet
+    /// and Arrow IPC keep full precision. Default: no rounding.
+    pub fn precision(mut self, decimals: u32) -> Self {
+        self.precision = Some(decimals);
+        self
+    }
+
+    /// Unit system for saved and returned values (default [`Units::Native`], as NDBC publishes
+    /// them; [`Units::Metric`] also converts `VIS` from nautical miles to km).
+    pub fn units(mut self, units: Units) -> Self {
+        self.units = units;
+        self
+    }
+
+    /// Append derived `wind_chill`, `heat_index` and `pressure_trend` columns (default off).
+    pub fn derive(mut self, derive: bool) -> Self {
+        self.derive = derive;
+        self
+    }
+
+    /// Append a `station_pressure` column, `PRES` brought from sea level up to the metadata
+    /// elevation using `ATMP` (default off).
+    pub fn station_pressure(mut self, enabled: bool) -> Self {
+        self.station_pressure = enabled;
+        self
+    }
+
+    /// Keep only these met columns (case-insensitive, e.g. `["wvht", "dpd", "mwd"]`, in that
+    /// order) in saved and returned frames, besides the time, station and coordinate columns
+    /// (default: every standard met column). Any numeric column of the file can be named; see
+    /// [`parse_std_met_columns`](crate::parse_std_met_columns).
+    pub fn columns<S: AsRef<str>>(mut self, columns: impl IntoIterator<Item = S>) -> Self {
+        self.columns = Some(columns.into_iter().map(|c| c.as_ref().to_string()).collect());
+        self
+    }
+
+    /// Append `station_type`, `elevation` and `anemometer_height` columns from the station
+    /// metadata (default off).
+    pub fn station_metadata_columns(mut self, enabled: bool) -> Self {
+        self.station_metadata_columns = enabled;
+        self
+    }
+
+    /// Where saved Parquet station files keep latitude/longitude (default: a column per row).
+    pub fn coords_as(mut self, coords_as: CoordsAs) -> Self {
+        self.coords_as = coords_as;
+        self
+    }
+
+    /// Flat station files (default) or `station=/date=` partitions; see [`PartitionScheme`].
+    pub fn partition(mut self, partition: PartitionScheme) -> Self {
+        self.partition = partition;
+        self
+    }
+
+    /// Largest 3-hour pressure change (hPa) classed as a `steady` pressure trend (default 0.5).
+    pub fn trend_threshold(mut self, threshold_hpa: f64) -> Self {
+        self.trend_threshold = threshold_hpa;
+        self
+    }
+
+    /// Fail a station whose file has more parse anomalies than `limits` allow (default: only log
+    /// them).
+    pub fn strict(mut self, limits: StrictLimits) -> Self {
+        self.strict = Some(limits);
+        self
+    }
+
+    /// Write a `<stem>.parse_issues.json` sidecar with the flagged lines of each saved file
+    /// (default off).
+    pub fn parse_issues(mut self, enabled: bool) -> Self {
+        self.parse_issues = enabled;
+        self
+    }
+
+    /// Keep each downloaded realtime file as `<station>.txt` in the output directory (default
+    /// off).
+    pub fn save_raw(mut self, enabled: bool) -> Self {
+        self.save_raw = enabled;
+        self
+    }
+
+    /// Sort parsed rows by time and keep one row per timestamp (default: keep the file's rows
+    /// as they are); see [`DuplicateTimes`].
+    pub fn duplicate_times(mut self, mode: DuplicateTimes) -> Self {
+        self.duplicate_times = mode;
+        self
+    }
+
+    /// Field separator of parsed text (default whitespace); see [`Delimiter`].
+    pub fn delimiter(mut self, delimiter: Delimiter) -> Self {
+        self.delimiter = delimiter;
+        self
+    }
+
+    /// Columns each station class is expected to populate (default
+    /// [`ExpectedColumns::default`]).
+    pub fn expected_columns(mut self, expected: ExpectedColumns) -> Self {
+        self.expected_columns = expected;
+        self
+    }
+
+    /// Which loaded stations the station queries return (default [`MetadataFilter::MetOnly`]).
+    pub fn metadata_filter(mut self, filter: MetadataFilter) -> Self {
+        self.metadata_filter = filter;
+        self
+    }
+
+    /// Merge each fetch into the station's existing Parquet file with this policy instead of
+    /// overwriting it. Default: overwrite.
+    pub fn revision(mut self, revision: Revision) -> Self {
+        self.revision = Some(revision);
+        self
+    }
+
+    /// Skip stations whose saved file is younger than `max_age` instead of fetching them again.
+    /// Default: always fetch.
+    pub fn incremental(mut self, max_age: Duration) -> Self {
+        self.incremental = Some(max_age);
+        self
+    }
+
+    /// Send `name: value` with every request (data, metadata and historical files alike), e.g.
+    /// an API key for a mirror. Credential-like headers are redacted in logs; mark other secret
+    /// values with [`HeaderValue::set_sensitive`] to redact them too.
+    pub fn default_header(mut self, name: HeaderName, value: HeaderValue) -> Self {
+        self.headers.default.insert(name, value);
+        self
+    }
+
+    /// Send all of `headers` with every request; see [`default_header`](Self::default_header).
+    pub fn default_headers(mut self, headers: HeaderMap) -> Self {
+        self.headers.default.extend(headers);
+        self
+    }
+
+    /// Compute extra headers for each request from its URL; they override the default headers.
+    pub fn request_headers(mut self, f: impl Fn(&Url) -> HeaderMap + Send + Sync + 'static) -> Self {
+        self.headers.per_request = Some(Arc::new(f));
+        self
+    }
+
+    /// Use an existing HTTP client (proxy, TLS, pooling already configured) instead of building
+    /// one. [`user_agent`](Self::user_agent), [`timeout`](Self::timeout) and
+    /// [`http_version`](Self::http_version) are then ignored.
+    pub fn client(mut self, client: reqwest::Client) -> Self {
+        self.client = Some(client);
+        self
+    }
+
+    /// Build the HTTP client and ensure the output directory (if any) exists and, when it is a
+    /// relative path, is gitignored.
+    pub fn build(self) -> Result<NdbcData> {
+        if let Some(dir) = &self.out_dir {
+            crate::ensure_data_dir(dir)?;
+        }
+        let client = match self.client {
+            Some(client) => client,
+            None => self
+                .http_version
+                .configure(reqwest::Client::builder())
+                .user_agent(self.user_agent)
+                .timeout(self.timeout)
+                .build()?,
+        };
+        let data = NdbcData::from_parts(client, self.out_dir, self.concurrency, self.window, self.precision)
+            .with_format(self.format)
+            .with_retry(self.retry)
+            .with_base_url(self.base_url)
+            .with_units(self.units)
+            .with_derived(self.derive)
+            .with_station_pressure(self.station_pressure)
+            .with_trend_threshold(self.trend_threshold)
+            .with_station_metadata_columns(self.station_metadata_columns)
+            .with_coords_as(self.coords_as)
+            .with_partition(self.partition)
+            .with_duplicate_times(self.duplicate_times)
+            .with_delimiter(self.delimiter)
+            .with_parse_issues(self.parse_issues)
+            .with_save_raw(self.save_raw)
+            .with_parquet_options(self.parquet)
+            .with_expected_columns(self.expected_columns)
+            .with_metadata_filter(self.metadata_filter)
+            .with_headers(self.headers);
+        let data = match self.strict {
+            Some(limits) => data.with_strict(limits),
+            None => data,
+        };
+        let data = match self.columns {
+            Some(columns) => data.with_columns(columns),
+            None => data,
+        };
+        let data = match self.revision {
+            Some(revision) => data.with_revision(revision),
+            None => data,
+        };
+        #[cfg(feature = "sqlite")]
+        let data = data.with_sqlite_target(self.sqlite);
+        #[cfg(feature = "object-store")]
+        let data = match self.store {
+            Some((store, prefix)) => data.with_store(store, prefix),
+            None => data,
+        };
+        Ok(match self.incremental {
+            Some(max_age) => data.with_incremental(max_age),
+            None => data,
+        })
+    }
+}
@@ -0,0 +1,65 @@
+//! On-disk cache of parsed station metadata, so runs within its max age skip the download.
+
+use crate::{MetadataSource, NdbcError, Result, Station};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::time::Duration;
+use time::OffsetDateTime;
+
+/// Name of the metadata cache file inside the output directory.
+pub const METADATA_CACHE_FILE: &str = "station_meta.json";
+
+/// Cache file contents: the parsed stations and when they were downloaded.
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct MetadataCache {
+    #[serde(with = "time::serde::rfc3339")]
+    pub fetched_at: OffsetDateTime,
+    pub source: MetadataSource,
+    pub stations: Vec<Station>,
+}
+
+impl MetadataCache {
+    /// Snapshot `stations` as fetched now from `source`.
+    pub fn new(source: MetadataSource, stations: &HashMap<String, Station>) -> Self {
+        let mut stations: Vec<Station> = stations.values().cloned().collect();
+        stations.sort_by(|a, b| a.id.cmp(&b.id));
+        Self { fetched_at: OffsetDateTime::now_utc(), source, stations }
+    }
+
+    /// Read the cache at `path`; `Ok(None)` when there is no cache file yet. Unreadable or
+    /// corrupt files are errors so the caller can log and refetch.
+    pub fn read(path: &Path) -> Result<Option<Self>> {
+        let text = match fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+        serde_json::from_str(&text)
+            .map(Some)
+            .map_err(|e| NdbcError::Parse(format!("metadata cache {}: {}", path.display(), e)))
+    }
+
+    /// Write the cache to `path` via a temp file and rename, so readers never see a partial file.
+    pub fn write(&self, path: &Path) -> Result<()> {
+        let tmp_path = path.with_extension("json.part");
+        let json = serde_json::to_vec(self).map_err(|e| NdbcError::Parse(format!("metadata cache: {}", e)))?;
+        let written = fs::write(&tmp_path, json).and_then(|_| fs::rename(&tmp_path, path));
+        if written.is_err() {
+            let _ = fs::remove_file(&tmp_path);
+        }
+        Ok(written?)
+    }
+
+    /// True when the cache came from `source` and is no older than `max_age`.
+    pub fn is_fresh(&self, source: MetadataSource, max_age: Duration) -> bool {
+        let age = OffsetDateTime::now_utc() - self.fetched_at;
+        self.source == source && age >= time::Duration::ZERO && age <= max_age
+    }
+
+    /// The cached stations keyed by id.
+    pub fn into_stations(self) -> HashMap<String, Station> {
+        self.stations.into_iter().map(|s| (s.id.clone(), s)).collect()
+    }
+}
@@ -0,0 +1,66 @@
+//! Per-call cancellation and deadlines for the async fetch methods.
+
+use crate::{NdbcError, Result};
+use std::future::Future;
+use std::time::{Duration, Instant};
+pub use tokio_util::sync::CancellationToken;
+
+/// Options bounding how long a single fetch may run end-to-end (network, parse and write).
+///
+/// Both limits are checked at every await point and between pipeline stages. A fetch that is
+/// stopped returns [`NdbcError::Cancelled`] or [`NdbcError::DeadlineExceeded`] and leaves no
+/// output file behind.
+#[derive(Clone, Debug, Default)]
+pub struct FetchOptions {
+    /// Give up once this instant has passed.
+    pub deadline: Option<Instant>,
+    /// Give up as soon as this token is cancelled.
+    pub cancel: Option<CancellationToken>,
+}
+
+impl FetchOptions {
+    /// Options with a deadline `timeout` from now.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.deadline = Some(Instant::now() + timeout);
+        self
+    }
+
+    /// Options observing `token` for cancellation.
+    pub fn with_cancel(mut self, token: CancellationToken) -> Self {
+        self.cancel = Some(token);
+        self
+    }
+
+    /// Fail if the fetch has been cancelled or its deadline has passed.
+    pub(crate) fn check(&self) -> Result<()> {
+        if self.cancel.as_ref().is_some_and(|t| t.is_cancelled()) {
+            return Err(NdbcError::Cancelled);
+        }
+        if self.deadline.is_some_and(|d| Instant::now() >= d) {
+            return Err(NdbcError::DeadlineExceeded);
+        }
+        Ok(())
+    }
+
+    /// Run `fut`, abandoning it if the fetch is cancelled or the deadline passes first.
+    pub(crate) async fn guard<T>(&self, fut: impl Future<Output = Result<T>>) -> Result<T> {
+        self.check()?;
+        let cancelled = async {
+            match &self.cancel {
+                Some(token) => token.cancelled().await,
+                None => std::future::pending().await,
+            }
+        };
+        let expired = async {
+            match self.deadline {
+                Some(deadline) => tokio::time::sleep_until(deadline.into()).await,
+                None => std::future::pending().await,
+            }
+        };
+        tokio::select! {
+            res = fut => res,
+            _ = cancelled => Err(NdbcError::Cancelled),
+            _ = expired => Err(NdbcError::DeadlineExceeded),
+        }
+    }
+}
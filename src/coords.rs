@@ -0,0 +1,85 @@
+//! Station coordinates stored once per Parquet file (`--coords-as metadata`) instead of on every
+//! row.
+
+use crate::parquet_options::DEFAULT_ROW_GROUP_SIZE;
+use crate::Result;
+use polars::prelude::*;
+use polars_parquet::parquet::metadata::KeyValue;
+use std::fs;
+use std::path::Path;
+use tracing::warn;
+
+/// Where saved station files keep `latitude` and `longitude`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum CoordsAs {
+    /// A `latitude` and a `longitude` column, repeated on every row.
+    #[default]
+    Column,
+    /// Parquet key-value metadata (`latitude`, `longitude`), written once per station file.
+    /// Only used when every row has the same position; other formats and the combined file
+    /// keep the columns.
+    Metadata,
+}
+
+/// Move `latitude`/`longitude` off `df` into key-value metadata when they are the same on every
+/// row. A frame whose position changes (a buoy redeployed within the file) keeps its columns.
+pub(crate) fn coords_to_metadata(mut df: DataFrame) -> Result<(DataFrame, Vec<KeyValue>)> {
+    let constant = |name: &str| -> Result<Option<Option<f64>>> {
+        let values = df.column(name)?.f64()?;
+        let first = values.get(0);
+        Ok(values.into_iter().all(|v| v == first).then_some(first))
+    };
+    let (Some(lat), Some(lon)) = (constant("latitude")?, constant("longitude")?) else {
+        warn!("coordinates change within the file; keeping latitude/longitude columns");
+        return Ok((df, Vec::new()));
+    };
+    let _ = df.drop_in_place("latitude")?;
+    let _ = df.drop_in_place("longitude")?;
+    let key_values = [("latitude", lat), ("longitude", lon)]
+        .into_iter()
+        .filter_map(|(key, value)| Some(KeyValue { key: key.to_string(), value: Some(value?.to_string()) }))
+        .collect();
+    Ok((df, key_values))
+}
+
+/// Write `df` with `writer`, adding `key_values` to the file metadata, in row groups of exactly
+/// `row_group_size` rows (polars' default when `None`) apart from the last.
+pub(crate) fn write_parquet<W: std::io::Write>(
+    writer: ParquetWriter<W>,
+    df: &mut DataFrame,
+    key_values: Vec<KeyValue>,
+    row_group_size: Option<usize>,
+) -> Result<()> {
+    // Always write batches: `ParquetWriter::finish` evens rows out across row groups, so
+    // `row_group_size` would not be the size of each group.
+    let mut writer = writer.batched(&df.schema())?;
+    let chunk = row_group_size.unwrap_or(DEFAULT_ROW_GROUP_SIZE).max(1);
+    for offset in (0..df.height()).step_by(chunk) {
+        let mut part = df.slice(offset as i64, chunk);
+        part.as_single_chunk_par();
+        writer.write_batch(&part)?;
+    }
+    // The batched writer's own `finish` writes the footer without key-value metadata, so end the
+    // underlying file writer directly; that is the only way polars 0.43 lets us add any.
+    let key_values = (!key_values.is_empty()).then_some(key_values);
+    writer.get_writer().lock().unwrap_or_else(|e| e.into_inner()).end(key_values)?;
+    Ok(())
+}
+
+/// Read a Parquet station file. A file written with [`CoordsAs::Metadata`] gets its constant
+/// `latitude`/`longitude` columns back from the key-value metadata (null when none was stored).
+pub(crate) fn read_parquet(path: &Path) -> Result<DataFrame> {
+    let mut reader = ParquetReader::new(fs::File::open(path)?);
+    let key_values = reader.get_metadata()?.key_value_metadata().clone().unwrap_or_default();
+    let mut df = reader.finish()?;
+    let stored = |key: &str| {
+        key_values.iter().find(|kv| kv.key == key).and_then(|kv| kv.value.as_deref()?.parse::<f64>().ok())
+    };
+    for name in ["latitude", "longitude"] {
+        if df.column(name).is_err() && df.column("time_ms").is_ok() {
+            let value = stored(name);
+            df.with_column(Series::new(name.into(), vec![value; df.height()]))?;
+        }
+    }
+    Ok(df)
+}
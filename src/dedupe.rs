@@ -0,0 +1,63 @@
+//! Optional clean-up of a parsed file's rows: time order and repeated timestamps.
+
+use crate::Result;
+use polars::prelude::*;
+
+/// What to do with the rows of a parsed file before they are saved or returned.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum DuplicateTimes {
+    /// Keep every row in file order (newest first for realtime files), repeated timestamps
+    /// included.
+    #[default]
+    Keep,
+    /// Sort by `time_ms` ascending and keep the row that comes first in the file for each
+    /// timestamp.
+    First,
+    /// Sort by `time_ms` ascending and keep the row that comes last in the file for each
+    /// timestamp.
+    Last,
+}
+
+/// Apply `mode` to a parsed frame (see [`DuplicateTimes`]). Rows without a time are dropped by
+/// [`First`](DuplicateTimes::First) and [`Last`](DuplicateTimes::Last) rather than sorted to one
+/// end; the parser never produces them.
+pub fn dedupe_times(df: DataFrame, mode: DuplicateTimes) -> Result<DataFrame> {
+    let keep = match mode {
+        DuplicateTimes::Keep => return Ok(df),
+        DuplicateTimes::First => UniqueKeepStrategy::First,
+        DuplicateTimes::Last => UniqueKeepStrategy::Last,
+    };
+    Ok(df
+        .lazy()
+        .filter(col("time_ms").is_not_null())
+        .unique_stable(Some(vec!["time_ms".into()]), keep)
+        .sort(["time_ms"], Default::default())
+        .collect()?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn duplicated_and_shuffled_rows_are_sorted_and_collapsed() -> Result<()> {
+        let parsed = crate::parse_std_met(include_str!("../data-samples/duplicate_times.txt"))?;
+        let minutes = |df: &DataFrame| -> Result<Vec<i64>> {
+            Ok(crate::time_millis(df)?.into_iter().flatten().map(|ms| (ms / 60_000) % 60).collect())
+        };
+        let wspd = |df: &DataFrame| -> Result<Vec<Option<f64>>> { Ok(df.column("WSPD")?.f64()?.into_iter().collect()) };
+
+        let kept = dedupe_times(parsed.clone(), DuplicateTimes::Keep)?;
+        assert_eq!(minutes(&kept)?, [40, 20, 40, 30, 10, 20]);
+
+        let first = dedupe_times(parsed.clone(), DuplicateTimes::First)?;
+        assert_eq!(minutes(&first)?, [10, 20, 30, 40]);
+        assert_eq!(wspd(&first)?, [Some(2.0), Some(3.0), Some(3.0), Some(3.0)]);
+
+        // 00:40 appears as 3.0 then as a corrected 3.5.
+        let last = dedupe_times(parsed, DuplicateTimes::Last)?;
+        assert_eq!(minutes(&last)?, [10, 20, 30, 40]);
+        assert_eq!(wspd(&last)?, [Some(2.0), Some(3.0), Some(3.0), Some(3.5)]);
+        Ok(())
+    }
+}
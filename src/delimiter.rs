@@ -0,0 +1,22 @@
+//! Field separator of standard met text: NDBC's aligned columns, or comma-separated exports.
+
+/// How the fields of a standard met header and data line are separated.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum Delimiter {
+    /// Runs of spaces or tabs, as in every file NDBC publishes.
+    #[default]
+    Whitespace,
+    /// Commas, e.g. `#YY,MM,DD,hh,mm,WDIR,...`; spaces around fields are ignored and an empty
+    /// field is a missing value.
+    Comma,
+}
+
+impl Delimiter {
+    /// Split `line` into its fields.
+    pub(crate) fn split(self, line: &str) -> Vec<&str> {
+        match self {
+            Delimiter::Whitespace => line.split_whitespace().collect(),
+            Delimiter::Comma => line.split(',').map(str::trim).collect(),
+        }
+    }
+}
@@ -0,0 +1,276 @@
+//! Optional columns derived from the standard met observations.
+
+use crate::Result;
+use polars::prelude::*;
+
+/// Columns appended when derived variables are enabled, in output order.
+pub const DERIVED_COLUMNS: [&str; 3] = ["wind_chill", "heat_index", "pressure_trend"];
+
+/// Default [`pressure_trend`] threshold: a 3-hour change within ±0.5 hPa counts as steady.
+pub const DEFAULT_TREND_THRESHOLD_HPA: f64 = 0.5;
+
+/// Type of each of [`DERIVED_COLUMNS`]: the temperatures are `f64`, `pressure_trend` a string.
+pub(crate) fn derived_dtype(name: &str) -> DataType {
+    match name {
+        "pressure_trend" => DataType::String,
+        _ => DataType::Float64,
+    }
+}
+
+/// NWS wind chill in degrees C from air temperature (degrees C) and wind speed (m/s).
+///
+/// Only defined for temperatures at or below 10 °C and winds of at least 4.8 km/h; `None`
+/// otherwise.
+pub fn wind_chill(temp_c: f64, wind_ms: f64) -> Option<f64> {
+    let v = wind_ms * 3.6;
+    if temp_c > 10.0 || v < 4.8 {
+        return None;
+    }
+    let vp = v.powf(0.16);
+    Some(13.12 + 0.6215 * temp_c - 11.37 * vp + 0.3965 * temp_c * vp)
+}
+
+/// Relative humidity in percent from air and dewpoint temperatures (degrees C), using the
+/// Magnus approximation.
+pub fn relative_humidity(temp_c: f64, dewp_c: f64) -> f64 {
+    let magnus = |t: f64| (17.625 * t / (243.04 + t)).exp();
+    (100.0 * magnus(dewp_c) / magnus(temp_c)).min(100.0)
+}
+
+/// NWS heat index (Rothfusz regression) in degrees C from air and dewpoint temperatures
+/// (degrees C).
+///
+/// Only defined for temperatures of at least 80 °F (26.7 °C) and relative humidity of at least
+/// 40%; `None` otherwise.
+pub fn heat_index(temp_c: f64, dewp_c: f64) -> Option<f64> {
+    let t = temp_c * 9.0 / 5.0 + 32.0;
+    let rh = relative_humidity(temp_c, dewp_c);
+    if t < 80.0 || rh < 40.0 {
+        return None;
+    }
+    let hi = -42.379 + 2.049_015_23 * t + 10.143_331_27 * rh
+        - 0.224_755_41 * t * rh
+        - 0.006_837_83 * t * t
+        - 0.054_817_17 * rh * rh
+        + 0.001_228_74 * t * t * rh
+        + 0.000_852_82 * t * rh * rh
+        - 0.000_001_99 * t * t * rh * rh;
+    Some((hi - 32.0) * 5.0 / 9.0)
+}
+
+/// Station pressure in hPa at `elevation_m` meters from sea-level pressure (hPa) and the air
+/// temperature at the station (degrees C): the inverse of the barometric reduction NDBC applies
+/// to `PRES`, with the standard 6.5 K/km lapse rate,
+/// `p * (1 - 0.0065 h / (T + 0.0065 h + 273.15)) ^ 5.257`. At sea level it returns `slp_hpa`
+/// unchanged. `None` for a non-physical temperature.
+pub fn station_pressure(slp_hpa: f64, elevation_m: f64, temp_c: f64) -> Option<f64> {
+    let lapse = 0.0065 * elevation_m;
+    let t0 = temp_c + lapse + 273.15;
+    if t0 <= 0.0 || temp_c + 273.15 <= 0.0 {
+        return None;
+    }
+    Some(slp_hpa * (1.0 - lapse / t0).powf(5.257))
+}
+
+/// Classify a 3-hour pressure tendency (`PTDY`, hPa) as `rising`, `falling` or `steady`; changes
+/// of at most `threshold_hpa` either way are steady.
+pub fn pressure_trend(ptdy_hpa: f64, threshold_hpa: f64) -> &'static str {
+    if ptdy_hpa > threshold_hpa {
+        "rising"
+    } else if ptdy_hpa < -threshold_hpa {
+        "falling"
+    } else {
+        "steady"
+    }
+}
+
+/// Standard met columns the derived variables are computed from.
+pub(crate) const INPUT_COLUMNS: [&str; 4] = ["ATMP", "WSPD", "DEWP", "PTDY"];
+
+/// Append [`DERIVED_COLUMNS`] to a parsed frame (in NDBC's SI units). Values are null where an
+/// input is missing or outside the formula's valid range. `trend_threshold_hpa` is passed to
+/// [`pressure_trend`].
+pub(crate) fn append_derived(mut df: DataFrame, trend_threshold_hpa: f64) -> Result<DataFrame> {
+    let atmp = df.column("ATMP")?.f64()?.clone();
+    let wspd = df.column("WSPD")?.f64()?.clone();
+    let dewp = df.column("DEWP")?.f64()?.clone();
+    let ptdy = df.column("PTDY")?.f64()?.clone();
+
+    let chill: Vec<Option<f64>> =
+        atmp.into_iter().zip(&wspd).map(|(t, w)| wind_chill(t?, w?)).collect();
+    let heat: Vec<Option<f64>> =
+        atmp.into_iter().zip(&dewp).map(|(t, d)| heat_index(t?, d?)).collect();
+    let trend: Vec<Option<&str>> = ptdy.into_iter().map(|p| Some(pressure_trend(p?, trend_threshold_hpa))).collect();
+
+    df.with_column(Series::new(DERIVED_COLUMNS[0].into(), chill))?;
+    df.with_column(Series::new(DERIVED_COLUMNS[1].into(), heat))?;
+    df.with_column(Series::new(DERIVED_COLUMNS[2].into(), trend))?;
+    Ok(df)
+}
+
+/// Station pressure column appended by
+/// [`NdbcDataBuilder::station_pressure`](crate::NdbcDataBuilder::station_pressure).
+pub const STATION_PRESSURE_COLUMN: &str = "station_pressure";
+
+/// Standard met columns [`STATION_PRESSURE_COLUMN`] is computed from.
+pub(crate) const STATION_PRESSURE_INPUT_COLUMNS: [&str; 2] = ["PRES", "ATMP"];
+
+/// Append [`STATION_PRESSURE_COLUMN`] (hPa, see [`station_pressure`]) to a parsed frame for a
+/// station at `elevation` meters; null throughout when the elevation is unknown, and per row
+/// where `PRES` or `ATMP` is missing.
+pub(crate) fn append_station_pressure(mut df: DataFrame, elevation: Option<f64>) -> Result<DataFrame> {
+    let pres = df.column("PRES")?.f64()?.clone();
+    let atmp = df.column("ATMP")?.f64()?.clone();
+    let values: Vec<Option<f64>> =
+        pres.into_iter().zip(&atmp).map(|(p, t)| station_pressure(p?, elevation?, t?)).collect();
+    df.with_column(Series::new(STATION_PRESSURE_COLUMN.into(), values))?;
+    Ok(df)
+}
+
+/// Standard gravity in m/s².
+const GRAVITY: f64 = 9.80665;
+
+/// Water depth in meters below which [`append_wavelength`] flags waves as `shallow_water` by
+/// default.
+pub const DEFAULT_SHALLOW_WATER_DEPTH_M: f64 = 10.0;
+
+/// Columns appended by [`append_wavelength`].
+pub const WAVELENGTH_COLUMNS: [&str; 2] = ["wavelength", "shallow_water"];
+
+/// Deep-water wavelength in meters for a wave period in seconds, `g T² / 2π`. `None` for a
+/// non-positive period.
+pub fn deep_water_wavelength(period_s: f64) -> Option<f64> {
+    (period_s > 0.0).then(|| GRAVITY * period_s * period_s / (2.0 * std::f64::consts::PI))
+}
+
+/// Wavelength in meters for a wave period in seconds in water `depth_m` meters deep, solving the
+/// linear dispersion relation `ω² = g k tanh(k h)` by Newton iteration. Tends to
+/// [`deep_water_wavelength`] in deep water and to `T √(g h)` in shallow water. `None` for a
+/// non-positive period or depth.
+pub fn wavelength(period_s: f64, depth_m: f64) -> Option<f64> {
+    if period_s <= 0.0 || depth_m <= 0.0 {
+        return None;
+    }
+    let omega2 = (2.0 * std::f64::consts::PI / period_s).powi(2);
+    let deep_k = omega2 / GRAVITY;
+    // Eckart's approximation as the starting point; Newton converges in a few steps from it.
+    let mut k = deep_k / (deep_k * depth_m).tanh().sqrt();
+    for _ in 0..50 {
+        let tanh = (k * depth_m).tanh();
+        let f = GRAVITY * k * tanh - omega2;
+        let df = GRAVITY * tanh + GRAVITY * k * depth_m * (1.0 - tanh * tanh);
+        let step = f / df;
+        k -= step;
+        if step.abs() <= 1e-12 * k {
+            break;
+        }
+    }
+    Some(2.0 * std::f64::consts::PI / k)
+}
+
+/// Whether water `depth_m` meters deep is shallower than `threshold_m`, where the deep-water
+/// assumptions behind wave steepness and energy estimates no longer hold.
+pub fn is_shallow_water(depth_m: f64, threshold_m: f64) -> bool {
+    depth_m < threshold_m
+}
+
+/// Append [`WAVELENGTH_COLUMNS`] to a frame with a dominant wave period column `DPD` (s):
+/// `wavelength` in meters, from [`wavelength`] when the station's `depth_m` is known and
+/// [`deep_water_wavelength`] otherwise, and `shallow_water`, true when `depth_m` is below
+/// `shallow_threshold_m` ([`DEFAULT_SHALLOW_WATER_DEPTH_M`] by default) and null when the depth
+/// is unknown. `wavelength` is null where `DPD` is.
+pub fn append_wavelength(mut df: DataFrame, depth_m: Option<f64>, shallow_threshold_m: f64) -> Result<DataFrame> {
+    let dpd = df.column("DPD")?.f64()?.clone();
+    let lengths: Vec<Option<f64>> = dpd
+        .into_iter()
+        .map(|t| match depth_m {
+            Some(depth) => wavelength(t?, depth),
+            None => deep_water_wavelength(t?),
+        })
+        .collect();
+    let shallow = depth_m.map(|depth| is_shallow_water(depth, shallow_threshold_m));
+    df.with_column(Series::new(WAVELENGTH_COLUMNS[0].into(), lengths))?;
+    df.with_column(Series::new(WAVELENGTH_COLUMNS[1].into(), vec![shallow; df.height()]))?;
+    Ok(df)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wind_chill_matches_nws_table() {
+        let f_to_c = |f: f64| (f - 32.0) * 5.0 / 9.0;
+        let mph_to_ms = |mph: f64| mph * 0.44704;
+        // (°F, mph, °F) from the NWS wind chill chart, which rounds to whole degrees.
+        for (temp_f, wind_mph, chill_f) in [(30.0, 5.0, 25.0), (5.0, 10.0, -10.0), (0.0, 15.0, -19.0), (-20.0, 40.0, -57.0)] {
+            let chill = wind_chill(f_to_c(temp_f), mph_to_ms(wind_mph)).unwrap_or(f64::NAN);
+            let chill_f_computed = chill * 9.0 / 5.0 + 32.0;
+            assert!((chill_f_computed - chill_f).abs() <= 0.5, "{} °F, {} mph: {}", temp_f, wind_mph, chill_f_computed);
+        }
+        assert_eq!(wind_chill(10.5, 10.0), None);
+        assert_eq!(wind_chill(0.0, 1.0), None);
+    }
+
+    #[test]
+    fn relative_humidity_matches_magnus_reference() {
+        // Alduchov & Eskridge (1996) coefficients: 20 °C over a 10 °C dewpoint is 52.5%.
+        assert!((relative_humidity(20.0, 10.0) - 52.54).abs() < 0.01);
+        assert!((relative_humidity(25.0, 0.0) - 19.32).abs() < 0.01);
+        assert_eq!(relative_humidity(-5.0, -5.0), 100.0);
+        // A dewpoint above the air temperature (sensor noise) is capped at saturation.
+        assert_eq!(relative_humidity(15.0, 15.4), 100.0);
+    }
+
+    #[test]
+    fn finite_depth_wavelength_is_shorter_than_deep_water() {
+        // A 10 s wave is 156.1 m long in deep water but 92.4 m in 10 m of water.
+        let deep = deep_water_wavelength(10.0).unwrap_or_default();
+        assert!((deep - 156.078).abs() < 0.01, "{}", deep);
+        let finite = wavelength(10.0, 10.0).unwrap_or_default();
+        assert!((finite - 92.356).abs() < 0.01, "{}", finite);
+        // Deep water converges to the deep-water length, very shallow water to T √(g h).
+        assert!((wavelength(10.0, 1000.0).unwrap_or_default() - deep).abs() < 1e-6);
+        assert!((wavelength(10.0, 0.1).unwrap_or_default() - 10.0 * (GRAVITY * 0.1).sqrt()).abs() < 0.05);
+        assert_eq!(wavelength(0.0, 10.0), None);
+        assert_eq!(wavelength(10.0, 0.0), None);
+    }
+
+    #[test]
+    fn shallow_water_flag_follows_threshold() -> Result<()> {
+        let df = df!("DPD" => [Some(10.0), None])?;
+        let shallow = append_wavelength(df.clone(), Some(8.0), DEFAULT_SHALLOW_WATER_DEPTH_M)?;
+        let flags: Vec<Option<bool>> = shallow.column("shallow_water")?.bool()?.into_iter().collect();
+        assert_eq!(flags, [Some(true), Some(true)]);
+        let lengths: Vec<Option<f64>> = shallow.column("wavelength")?.f64()?.into_iter().collect();
+        assert_eq!(lengths, [wavelength(10.0, 8.0), None]);
+
+        let deeper_threshold = append_wavelength(df.clone(), Some(8.0), 5.0)?;
+        assert_eq!(deeper_threshold.column("shallow_water")?.bool()?.get(0), Some(false));
+        let unknown = append_wavelength(df, None, DEFAULT_SHALLOW_WATER_DEPTH_M)?;
+        assert_eq!(unknown.column("shallow_water")?.null_count(), 2);
+        assert_eq!(unknown.column("wavelength")?.f64()?.get(0), deep_water_wavelength(10.0));
+        Ok(())
+    }
+
+    #[test]
+    fn station_pressure_matches_standard_atmosphere() {
+        // ISA: 1013.25 hPa and 15 °C at sea level give 991.98 hPa and 13.84 °C at 178.6 m.
+        let p = station_pressure(1013.25, 178.6, 13.84).unwrap_or_default();
+        assert!((p - 991.98).abs() < 0.1, "{}", p);
+        assert_eq!(station_pressure(1013.25, 0.0, 20.0), Some(1013.25));
+        assert_eq!(station_pressure(1013.25, 10.0, -300.0), None);
+    }
+
+    #[test]
+    fn station_pressure_column_is_null_without_inputs() -> Result<()> {
+        let df = df!("PRES" => [Some(1013.25), None, Some(1000.0)], "ATMP" => [Some(13.84), Some(10.0), None])?;
+        let with_elevation = append_station_pressure(df.clone(), Some(178.6))?;
+        let values: Vec<Option<f64>> = with_elevation.column(STATION_PRESSURE_COLUMN)?.f64()?.into_iter().collect();
+        assert!(values[0].is_some_and(|p| (p - 991.98).abs() < 0.1));
+        assert_eq!(&values[1..], &[None, None]);
+        let without = append_station_pressure(df, None)?;
+        assert_eq!(without.column(STATION_PRESSURE_COLUMN)?.null_count(), 3);
+        Ok(())
+    }
+}
@@ -0,0 +1,96 @@
+//! Load parsed observations into an in-memory DuckDB database (feature `duckdb`) for ad-hoc SQL.
+
+use crate::{align_columns, standard_columns, NdbcData, Result, STD_MET_COLUMNS};
+use duckdb::types::Value;
+use duckdb::appender_params_from_iter;
+pub use duckdb::Connection as DuckDbConnection;
+use polars::prelude::*;
+
+/// Name of the table created by [`frames_to_duckdb`].
+const TABLE: &str = "observations";
+
+/// Create an in-memory DuckDB database with an `observations` table holding every row of
+/// `frames` (as returned by [`NdbcData::fetch_station_df`]), keyed by `(station_id, time_ms)`.
+///
+/// Columns are `station_id`, `time_ms` (milliseconds since the Unix epoch, UTC), the standard met
+/// columns in lowercase, `latitude` and `longitude`; missing values are `NULL`. A row repeated
+/// for the same station and time keeps the last one.
+pub fn frames_to_duckdb(frames: &[DataFrame]) -> Result<DuckDbConnection> {
+    let conn = DuckDbConnection::open_in_memory()?;
+    let met: Vec<String> = STD_MET_COLUMNS.iter().map(|(name, _)| name.to_lowercase()).collect();
+    conn.execute_batch(&format!(
+        "CREATE TABLE {TABLE} (station_id VARCHAR NOT NULL, time_ms BIGINT NOT NULL, {}, latitude DOUBLE, longitude DOUBLE, \
+         PRIMARY KEY (station_id, time_ms))",
+        met.iter().map(|c| format!("{} DOUBLE", c)).collect::<Vec<_>>().join(", "),
+    ))?;
+
+    let mut all = DataFrame::empty();
+    for df in frames {
+        let df = align_columns(df, &standard_columns())?;
+        if all.width() == 0 {
+            all = df;
+        } else {
+            all.vstack_mut(&df)?;
+        }
+    }
+    if all.height() == 0 {
+        return Ok(conn);
+    }
+    // The appender rejects primary key conflicts, so repeats are collapsed first.
+    let all = all.unique_stable(Some(&["station_id".to_string(), "time_ms".to_string()]), UniqueKeepStrategy::Last, None)?;
+
+    let stations = all.column("station_id")?.str()?;
+    let times = crate::time_millis(&all)?;
+    let mut values: Vec<&Float64Chunked> = Vec::new();
+    for (name, _) in STD_MET_COLUMNS {
+        values.push(all.column(name)?.f64()?);
+    }
+    values.push(all.column("latitude")?.f64()?);
+    values.push(all.column("longitude")?.f64()?);
+
+    {
+        let mut appender = conn.appender(TABLE)?;
+        for i in 0..all.height() {
+            let mut row = vec![
+                stations.get(i).map_or(Value::Null, |s| Value::Text(s.to_string())),
+                times.get(i).map_or(Value::Null, Value::BigInt),
+            ];
+            row.extend(values.iter().map(|col| col.get(i).map_or(Value::Null, Value::Double)));
+            appender.append_row(appender_params_from_iter(row))?;
+        }
+        appender.flush()?;
+    }
+    Ok(conn)
+}
+
+impl NdbcData {
+    /// Fetch realtime data for several stations and load it into an in-memory DuckDB
+    /// `observations` table (see [`frames_to_duckdb`]), returning the connection for queries.
+    ///
+    /// Nothing is written to disk. Stations that fail are logged and left out; the first error is
+    /// returned only when none succeed.
+    pub async fn fetch_into_duckdb(&self, stations: &[String]) -> Result<DuckDbConnection> {
+        let frames = self.fetch_station_frames(stations).await?;
+        frames_to_duckdb(&frames)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn observations_table_is_queryable() -> Result<()> {
+        let data = NdbcData::in_memory()?;
+        let df = data.frame_from_text("41001", include_str!("../data-samples/realtimedata.txt"))?;
+        let rows = df.height();
+        // The same frame twice: repeated (station, time) rows collapse to one.
+        let conn = frames_to_duckdb(&[df.clone(), df])?;
+        let (count, stations): (i64, i64) =
+            conn.query_row(&format!("SELECT count(*), count(DISTINCT station_id) FROM {TABLE}"), [], |r| Ok((r.get(0)?, r.get(1)?)))?;
+        assert_eq!((count as usize, stations), (rows, 1));
+        let missing: i64 = conn.query_row(&format!("SELECT count(*) FROM {TABLE} WHERE wvht IS NULL"), [], |r| r.get(0))?;
+        assert!(missing > 0);
+        Ok(())
+    }
+}
@@ -0,0 +1,95 @@
+//! Error type returned by the library.
+
+use polars::prelude::PolarsError;
+
+/// Failures surfaced by [`NdbcData`](crate::NdbcData) and its helpers.
+///
+/// Variants are coarse enough to `match` on (skip stations without data, retry on network
+/// errors) without inspecting error messages.
+#[derive(Debug, thiserror::Error)]
+pub enum NdbcError {
+    /// The station has no realtime standard met feed (HTTP 404).
+    #[error("data unavailable for {0} (404)")]
+    StationNotFound(String),
+    /// No historical archive exists for the station and year (HTTP 404).
+    #[error("no historical data for {station} in {year} (404)")]
+    HistoricalNotFound { station: String, year: i32 },
+    /// No monthly file exists for the station, year and month (HTTP 404).
+    #[error("no monthly data for {station} in {year}-{month:02} (404)")]
+    HistoricalMonthNotFound { station: String, year: i32, month: u8 },
+    /// The server returned an empty body.
+    #[error("empty data")]
+    EmptyData,
+    /// The file was fetched but contained no standard met rows.
+    #[error("no standard met rows found")]
+    NoMetRows,
+    /// The fetch was cancelled through its [`FetchOptions`](crate::FetchOptions) token.
+    #[error("cancelled")]
+    Cancelled,
+    /// The fetch did not finish before its [`FetchOptions`](crate::FetchOptions) deadline.
+    #[error("deadline exceeded")]
+    DeadlineExceeded,
+    /// The station's saved file is younger than the [`incremental`](crate::NdbcDataBuilder::incremental)
+    /// threshold, so it was not fetched again.
+    #[error("{station} is up to date (saved {}s ago)", .age.as_secs())]
+    UpToDate { station: String, age: std::time::Duration },
+    /// A method that writes files was called on an in-memory instance.
+    #[error("no output directory configured (in-memory instance)")]
+    NoOutputDir,
+    /// Another process holds the output directory's archive lock.
+    #[error("another ndbc-data process holds the archive lock ({})", holder(.pid, .since))]
+    ArchiveLocked { pid: Option<u32>, since: Option<String> },
+    /// Merging into saved files ([`Revision`](crate::Revision), `append_station`) was requested
+    /// for an output format that is rewritten on every save.
+    #[error("cannot merge into saved {0:?} files, which are rewritten on every save; use Parquet or Arrow IPC")]
+    MergeUnsupported(crate::OutputFormat),
+    /// An existing output file could not be merged into (`--revision`): it is unreadable,
+    /// truncated, or its columns do not match a station file.
+    #[error("cannot merge into existing {}: {reason}", .path.display())]
+    ExistingOutput { path: std::path::PathBuf, reason: String },
+    /// Network or HTTP status failure.
+    #[error("http error: {0}")]
+    Http(#[from] reqwest::Error),
+    /// A data file parsed, but with more anomalies than the configured
+    /// [`StrictLimits`](crate::StrictLimits) allow.
+    #[error("parse anomalies over strict limits: {0}")]
+    ParseAnomalies(String),
+    /// Malformed metadata or data file.
+    #[error("parse error: {0}")]
+    Parse(String),
+    /// Filesystem failure while preparing or writing output.
+    #[error("io error: {0}")]
+    Io(#[from] std::io::Error),
+    /// Filesystem failure with what was being done and on which path, e.g.
+    /// `creating data dir data: permission denied`.
+    #[error("{context}: {source}")]
+    IoContext { context: String, source: std::io::Error },
+    /// Failure building or writing a DataFrame.
+    #[error("dataframe error: {0}")]
+    Polars(#[from] PolarsError),
+    /// Failure loading observations into DuckDB.
+    #[cfg(feature = "duckdb")]
+    #[error("duckdb error: {0}")]
+    DuckDb(#[from] ::duckdb::Error),
+    /// Failure loading observations into SQLite.
+    #[cfg(feature = "sqlite")]
+    #[error("sqlite error: {0}")]
+    Sqlite(#[from] rusqlite::Error),
+
+    #[cfg(feature = "object-store")]
+    #[error("object store error for {station}: {source}")]
+    Store { station: String, source: object_store::Error },
+}
+
+/// `pid N, since T` for an [`NdbcError::ArchiveLocked`] message, omitting unknown parts.
+fn holder(pid: &Option<u32>, since: &Option<String>) -> String {
+    match (pid, since) {
+        (Some(pid), Some(since)) => format!("pid {}, since {}", pid, since),
+        (Some(pid), None) => format!("pid {}", pid),
+        (None, Some(since)) => format!("since {}", since),
+        (None, None) => "holder unknown".to_string(),
+    }
+}
+
+/// Result alias using [`NdbcError`].
+pub type Result<T, E = NdbcError> = std::result::Result<T, E>;
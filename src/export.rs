@@ -0,0 +1,164 @@
+//! Streaming CSV export of every station saved in an output directory.
+
+use crate::{align_columns, standard_columns, NdbcError, Result, COMBINED_STEM, INDEX_STEM, STATIONS_STEM, STD_MET_COLUMNS};
+use polars::prelude::*;
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use time::format_description::well_known::Rfc3339;
+use time::OffsetDateTime;
+
+/// Format written by [`NdbcData::export`](crate::NdbcData::export).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum ExportFormat {
+    /// Comma-separated values with a header row.
+    #[default]
+    Csv,
+}
+
+/// Header of the exported CSV: `time`, the standard met columns in [`STD_MET_COLUMNS`] order,
+/// then `station_id`, `latitude` and `longitude`, matching the JSON output schema.
+pub fn export_columns() -> Vec<String> {
+    let mut cols = vec!["time".to_string()];
+    cols.extend(STD_MET_COLUMNS.iter().map(|(name, _)| name.to_lowercase()));
+    cols.extend(["station_id", "latitude", "longitude"].map(String::from));
+    cols
+}
+
+/// Write every Parquet station file in `dir` to `out` as one CSV, station by station (sorted by
+/// id) and time-ordered within each station. Returns the number of data rows written.
+///
+/// Only one station's files are held in memory at a time. Rows repeated across a station's
+/// realtime, monthly and yearly files are written once.
+pub(crate) fn export_csv(dir: &Path, precision: Option<u32>, mut out: impl Write) -> Result<usize> {
+    writeln!(out, "{}", export_columns().join(","))?;
+    let mut rows = 0;
+    for (station, files) in station_files(dir)? {
+        let mut df = DataFrame::empty();
+        for path in &files {
+            // Aligning also drops `revision`, derived and metadata columns and fills in met columns
+            // left out by a column selection, so files saved with different options stack.
+            let frame = align_columns(&crate::coords::read_parquet(path)?, &standard_columns())?;
+            if df.width() == 0 {
+                df = frame;
+            } else {
+                df.vstack_mut(&frame)?;
+            }
+        }
+        let df = df
+            .unique_stable(Some(&["time_ms".to_string()]), UniqueKeepStrategy::Last, None)?
+            .sort(["time_ms"], SortMultipleOptions::default())?;
+        rows += write_rows(&df, &station, precision, &mut out)?;
+    }
+    out.flush()?;
+    Ok(rows)
+}
+
+/// Parquet files in `dir` grouped by station id: `<station>.parquet`, `<station>_<year>.parquet`
+/// and `<station>_<year>_<mm>.parquet` all belong to `<station>`. The `--combine` output is
+/// skipped since its rows are already in the per-station files or were never split out, and so
+/// are the station metadata table and the run index. Files of a `station=<station>/date=<day>/`
+/// partitioned layout belong to `<station>` too.
+fn station_files(dir: &Path) -> Result<BTreeMap<String, Vec<PathBuf>>> {
+    let mut groups: BTreeMap<String, Vec<PathBuf>> = BTreeMap::new();
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if let Some(station) = path.file_name().and_then(|n| n.to_str()).and_then(|n| n.strip_prefix("station=")) {
+            let files = groups.entry(station.to_string()).or_default();
+            for day in fs::read_dir(&path)? {
+                let day = day?.path();
+                if !day.is_dir() {
+                    continue;
+                }
+                for file in fs::read_dir(day)? {
+                    let file = file?.path();
+                    if file.extension().is_some_and(|ext| ext == "parquet") {
+                        files.push(file);
+                    }
+                }
+            }
+            continue;
+        }
+        if path.extension().is_none_or(|ext| ext != "parquet") {
+            continue;
+        }
+        let Some(stem) = path.file_stem().and_then(|s| s.to_str()).filter(|s| ![COMBINED_STEM, STATIONS_STEM, INDEX_STEM].contains(s)) else {
+            continue;
+        };
+        let station = stem.split('_').next().unwrap_or(stem).to_string();
+        groups.entry(station).or_default().push(path);
+    }
+    for files in groups.values_mut() {
+        files.sort();
+    }
+    Ok(groups)
+}
+
+/// Write the rows of one station's frame in [`export_columns`] order.
+fn write_rows<W: Write>(df: &DataFrame, station: &str, precision: Option<u32>, out: &mut W) -> Result<usize> {
+    let times = crate::time_millis(df)?;
+    let mut values: Vec<&Float64Chunked> = Vec::new();
+    for (name, _) in STD_MET_COLUMNS {
+        values.push(df.column(name)?.f64()?);
+    }
+    values.push(df.column("latitude")?.f64()?);
+    values.push(df.column("longitude")?.f64()?);
+    let (met, coords) = values.split_at(STD_MET_COLUMNS.len());
+
+    let factor = precision.map(|decimals| 10f64.powi(decimals as i32));
+    let number = |out: &mut W, v: Option<f64>| match (v, factor) {
+        (None, _) => Ok(()),
+        (Some(v), Some(factor)) => write!(out, "{}", (v * factor).round() / factor),
+        (Some(v), None) => write!(out, "{}", v),
+    };
+    let station = csv_field(station);
+    // Fields go straight to the writer, so a row costs no allocations of its own.
+    for i in 0..df.height() {
+        times
+            .get(i)
+            .and_then(|ms| OffsetDateTime::from_unix_timestamp_nanos(ms as i128 * 1_000_000).ok())
+            .and_then(|dt| dt.format_into(out, &Rfc3339).ok())
+            .ok_or_else(|| NdbcError::Parse(format!("invalid time in row {} of {}", i, station)))?;
+        for col in met {
+            out.write_all(b",")?;
+            number(out, col.get(i))?;
+        }
+        write!(out, ",{}", station)?;
+        for col in coords {
+            out.write_all(b",")?;
+            number(out, col.get(i))?;
+        }
+        out.write_all(b"\n")?;
+    }
+    Ok(df.height())
+}
+
+/// Quote a CSV field when it contains a delimiter, quote or newline.
+pub(crate) fn csv_field(s: &str) -> String {
+    if s.contains([',', '"', '\n']) {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::NdbcData;
+
+    #[test]
+    fn rows_are_written_field_by_field() -> Result<()> {
+        let data = NdbcData::in_memory()?;
+        let text = "#YY  MM DD hh mm WSPD WVHT\n#yr  mo dy hr mn m/s  m\n2025 11 13 00 30  3.14159   MM\n";
+        let df = align_columns(&data.frame_from_text("41001", text)?, &standard_columns())?;
+        let mut out = Vec::new();
+        assert_eq!(write_rows(&df, "Point, Sur", Some(2), &mut out)?, 1);
+        let mut expected = vec!["2025-11-13T00:30:00Z".to_string()];
+        expected.extend(STD_MET_COLUMNS.iter().map(|(name, _)| if *name == "WSPD" { "3.14".to_string() } else { String::new() }));
+        expected.extend(["\"Point, Sur\"", "", ""].map(String::from));
+        assert_eq!(String::from_utf8_lossy(&out), format!("{}\n", expected.join(",")));
+        Ok(())
+    }
+}
@@ -0,0 +1,84 @@
+//! Golden-file regression check: run the parsing pipeline over fixed fixtures and compare the CSV
+//! it produces with committed copies, so parser refactors cannot silently change schema or values.
+
+use crate::{to_text_frame, NdbcData, NdbcError, Result};
+use polars::prelude::*;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// How one fixture compared with its golden file.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GoldenOutcome {
+    /// Output is byte-for-byte identical to the golden file.
+    Match,
+    /// Output differs from the golden file.
+    Differs,
+    /// There is no golden file for the fixture yet.
+    Missing,
+    /// The golden file was (re)written from the output.
+    Blessed,
+}
+
+/// Result of checking one fixture with [`check_golden`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct GoldenResult {
+    pub fixture: PathBuf,
+    pub golden: PathBuf,
+    pub outcome: GoldenOutcome,
+    /// First line (1-based) where output and golden file disagree, for [`GoldenOutcome::Differs`].
+    pub first_difference: Option<usize>,
+}
+
+/// Parse every `*.txt` fixture in `fixtures` (sorted by name) as a standard met file for a
+/// station named after the file stem, with default settings and no station metadata, and compare
+/// its CSV output (as written by `--format csv`) with `<golden>/<stem>.csv`.
+///
+/// The output holds nothing that depends on when or where it ran, so the comparison is exact.
+/// With `bless`, golden files are written from the output instead of compared. A fixture that
+/// fails to parse, or whose frame does not have exactly [`std_met_schema`](crate::std_met_schema),
+/// is an error, so every fixture is also checked to produce the same schema.
+pub fn check_golden(fixtures: &Path, golden: &Path, bless: bool) -> Result<Vec<GoldenResult>> {
+    let mut paths: Vec<PathBuf> = fs::read_dir(fixtures)?
+        .map(|entry| entry.map(|e| e.path()))
+        .collect::<std::io::Result<_>>()?;
+    paths.retain(|p| p.extension().is_some_and(|ext| ext == "txt"));
+    paths.sort();
+
+    let data = NdbcData::in_memory()?;
+    if bless {
+        fs::create_dir_all(golden)?;
+    }
+    let mut results = Vec::new();
+    for fixture in paths {
+        let stem = fixture.file_stem().and_then(|s| s.to_str()).unwrap_or_default().to_string();
+        let df = data.frame_from_text(&stem, &fs::read_to_string(&fixture)?)?;
+        let schema = df.schema();
+        if schema != data.output_schema() {
+            return Err(NdbcError::Parse(format!(
+                "{}: frame schema {:?} differs from the standard output schema",
+                fixture.display(),
+                schema
+            )));
+        }
+        let mut csv = Vec::new();
+        CsvWriter::new(&mut csv).include_header(true).finish(&mut to_text_frame(&df, None)?)?;
+
+        let golden_path = golden.join(format!("{}.csv", stem));
+        let (outcome, first_difference) = if bless {
+            fs::write(&golden_path, &csv)?;
+            (GoldenOutcome::Blessed, None)
+        } else {
+            match fs::read(&golden_path) {
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => (GoldenOutcome::Missing, None),
+                Err(e) => return Err(e.into()),
+                Ok(expected) if expected == csv => (GoldenOutcome::Match, None),
+                Ok(expected) => {
+                    let line = expected.split(|&b| b == b'\n').zip(csv.split(|&b| b == b'\n')).take_while(|(a, b)| a == b).count();
+                    (GoldenOutcome::Differs, Some(line + 1))
+                }
+            }
+        };
+        results.push(GoldenResult { fixture, golden: golden_path, outcome, first_difference });
+    }
+    Ok(results)
+}
@@ -0,0 +1,57 @@
+//! Built-in named station groups (`--group gulf`), so common regions can be fetched without
+//! looking up ids.
+
+/// A named, curated list of station ids.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct StationGroup {
+    /// Name used on the command line, e.g. `gulf`.
+    pub name: &'static str,
+    /// Region covered.
+    pub description: &'static str,
+    pub stations: &'static [&'static str],
+}
+
+/// Every built-in group, by name. The lists hold long-running met buoys and C-MAN stations of
+/// each region; stations NDBC has since retired simply fail as not found.
+pub const STATION_GROUPS: [StationGroup; 6] = [
+    StationGroup {
+        name: "gulf",
+        description: "Gulf of Mexico",
+        stations: &["42001", "42002", "42003", "42012", "42019", "42020", "42035", "42036", "42039", "42040", "42055", "42056"],
+    },
+    StationGroup {
+        name: "east-coast",
+        description: "US Atlantic coast, Florida to Maine",
+        stations: &[
+            "41004", "41008", "41009", "41010", "41013", "41025", "44008", "44009", "44013", "44017", "44025", "44027",
+        ],
+    },
+    StationGroup {
+        name: "west-coast",
+        description: "US Pacific coast, California to Washington",
+        stations: &[
+            "46011", "46013", "46014", "46022", "46025", "46026", "46027", "46028", "46041", "46042", "46047", "46050",
+            "46053", "46054", "46086", "46089",
+        ],
+    },
+    StationGroup {
+        name: "great-lakes",
+        description: "Great Lakes (seasonal buoys, usually April to November)",
+        stations: &["45001", "45002", "45003", "45004", "45005", "45006", "45007", "45008", "45012"],
+    },
+    StationGroup {
+        name: "hawaii",
+        description: "Hawaiian Islands",
+        stations: &["51000", "51001", "51002", "51003", "51004", "51101"],
+    },
+    StationGroup {
+        name: "alaska",
+        description: "Gulf of Alaska and Bering Sea",
+        stations: &["46001", "46060", "46061", "46075", "46076", "46078", "46080", "46082", "46083", "46085"],
+    },
+];
+
+/// Look up a built-in group by name (case-insensitive).
+pub fn station_group(name: &str) -> Option<&'static StationGroup> {
+    STATION_GROUPS.iter().find(|g| g.name.eq_ignore_ascii_case(name))
+}
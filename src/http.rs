@@ -0,0 +1,222 @@
+//! HTTP helpers shared by the fetch methods.
+
+use crate::Result;
+use reqwest::header::{HeaderMap, HeaderName, RETRY_AFTER};
+use reqwest::{Response, StatusCode, Url};
+use std::fmt;
+use std::sync::Arc;
+use std::time::Duration;
+use time::format_description::well_known::Rfc2822;
+use time::OffsetDateTime;
+use tracing::{debug, warn};
+
+/// HTTP protocol used to talk to NDBC.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum HttpVersion {
+    /// HTTP/1.1 only, one request per connection at a time.
+    #[default]
+    #[value(name = "1.1")]
+    Http1,
+    /// HTTP/2 when the server offers it during the TLS handshake, otherwise HTTP/1.1.
+    Auto,
+    /// HTTP/2 only, multiplexing concurrent requests over one connection; fails against a
+    /// server without HTTP/2.
+    #[value(name = "2")]
+    Http2,
+}
+
+impl HttpVersion {
+    /// Apply this preference to a client being built.
+    pub(crate) fn configure(self, builder: reqwest::ClientBuilder) -> reqwest::ClientBuilder {
+        match self {
+            HttpVersion::Http1 => builder.http1_only(),
+            HttpVersion::Auto => builder,
+            HttpVersion::Http2 => builder.http2_prior_knowledge(),
+        }
+    }
+}
+
+/// Callback computing extra headers for each request from its URL.
+pub type HeaderFn = dyn Fn(&Url) -> HeaderMap + Send + Sync;
+
+/// Extra headers added to every request: fixed defaults plus an optional per-request callback
+/// whose headers override the defaults.
+#[derive(Clone, Default)]
+pub(crate) struct RequestHeaders {
+    pub default: HeaderMap,
+    pub per_request: Option<Arc<HeaderFn>>,
+}
+
+impl RequestHeaders {
+    /// Headers for a request to `url`.
+    fn for_url(&self, url: &str) -> HeaderMap {
+        let mut headers = self.default.clone();
+        if let (Some(f), Ok(url)) = (&self.per_request, Url::parse(url)) {
+            for (name, value) in f(&url).iter() {
+                headers.insert(name.clone(), value.clone());
+            }
+        }
+        headers
+    }
+}
+
+impl fmt::Debug for RequestHeaders {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RequestHeaders")
+            .field("default", &Redacted(&self.default))
+            .field("per_request", &self.per_request.is_some())
+            .finish()
+    }
+}
+
+/// Header names whose values are never logged, in addition to values marked sensitive.
+const SENSITIVE_HEADERS: [&str; 6] = ["authorization", "proxy-authorization", "cookie", "set-cookie", "x-api-key", "api-key"];
+
+/// Whether a header's value must be hidden in logs: known credential headers, names mentioning
+/// a key, token, secret or password, and values marked with `HeaderValue::set_sensitive`.
+pub(crate) fn is_sensitive(name: &HeaderName, value: &reqwest::header::HeaderValue) -> bool {
+    let name = name.as_str();
+    value.is_sensitive()
+        || SENSITIVE_HEADERS.contains(&name)
+        || ["key", "token", "secret", "password"].iter().any(|word| name.contains(word))
+}
+
+/// Debug view of headers with sensitive values replaced by `<redacted>`.
+pub(crate) struct Redacted<'a>(pub &'a HeaderMap);
+
+impl fmt::Debug for Redacted<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map()
+            .entries(self.0.iter().map(|(name, value)| {
+                let shown = if is_sensitive(name, value) { "<redacted>" } else { value.to_str().unwrap_or("<binary>") };
+                (name.as_str(), shown)
+            }))
+            .finish()
+    }
+}
+
+/// How transient HTTP failures (5xx, 429, connection errors) are retried.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Retries after the first attempt; `0` disables retrying.
+    pub max_retries: u32,
+    /// Delay before the first retry; doubles on each further attempt, plus up to 50% jitter.
+    pub base_delay: Duration,
+    /// Longest single wait between attempts, for the backoff and for a server's `Retry-After`
+    /// alike, so a misbehaving server cannot stall a fetch for hours.
+    pub max_delay: Duration,
+}
+
+/// Default [`RetryPolicy::max_delay`].
+pub const DEFAULT_MAX_RETRY_DELAY: Duration = Duration::from_secs(60);
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self { max_retries: 3, base_delay: Duration::from_millis(500), max_delay: DEFAULT_MAX_RETRY_DELAY }
+    }
+}
+
+impl RetryPolicy {
+    /// Exponential backoff with jitter for the given retry (1-based), at most `max_delay`.
+    fn backoff(&self, retry: u32) -> Duration {
+        let delay = self.base_delay.saturating_mul(2u32.saturating_pow(retry - 1));
+        (delay + delay.mul_f64(fastrand::f64() * 0.5)).min(self.max_delay)
+    }
+}
+
+/// GET `url`, retrying transient failures according to `policy`.
+///
+/// Server errors (5xx) and transport errors (connect, timeout) are retried with exponential
+/// backoff. `429 Too Many Requests` waits for the response's `Retry-After` header when present.
+/// Every wait is capped at the policy's `max_delay`.
+/// Any other status, including 404, is returned to the caller as-is, as is the last response
+/// once retries are exhausted.
+///
+/// `headers` are added to every attempt; they are logged at debug level with sensitive values
+/// redacted.
+pub(crate) async fn get(
+    client: &reqwest::Client,
+    url: &str,
+    policy: &RetryPolicy,
+    headers: &RequestHeaders,
+) -> Result<Response> {
+    let headers = headers.for_url(url);
+    if !headers.is_empty() {
+        debug!(%url, headers = ?Redacted(&headers), "adding request headers");
+    }
+    let mut retry = 0;
+    loop {
+        let delay = match client.get(url).headers(headers.clone()).send().await {
+            Ok(resp) => {
+                let status = resp.status();
+                if retry >= policy.max_retries || !(status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error()) {
+                    return Ok(resp);
+                }
+                retry += 1;
+                let delay = match status {
+                    StatusCode::TOO_MANY_REQUESTS => retry_after(&resp).map_or_else(|| policy.backoff(retry), |d| d.min(policy.max_delay)),
+                    _ => policy.backoff(retry),
+                };
+                warn!(%url, %status, retry, delay_ms = delay.as_millis() as u64, "transient HTTP status; retrying");
+                delay
+            }
+            Err(e) => {
+                if retry >= policy.max_retries || !is_transient(&e) {
+                    return Err(e.into());
+                }
+                retry += 1;
+                let delay = policy.backoff(retry);
+                warn!(%url, error = %e, retry, delay_ms = delay.as_millis() as u64, "request failed; retrying");
+                delay
+            }
+        };
+        tokio::time::sleep(delay).await;
+    }
+}
+
+/// Transport-level failures worth retrying (connection resets, timeouts).
+fn is_transient(e: &reqwest::Error) -> bool {
+    e.is_connect() || e.is_timeout() || e.is_request()
+}
+
+/// Delay requested by a response's `Retry-After` header, if any.
+fn retry_after(resp: &Response) -> Option<Duration> {
+    let value = resp.headers().get(RETRY_AFTER)?.to_str().ok()?;
+    parse_retry_after(value, OffsetDateTime::now_utc())
+}
+
+/// Parse `Retry-After` in either delay-seconds (`120`) or HTTP-date
+/// (`Wed, 21 Oct 2015 07:28:00 GMT`) form. Dates in the past yield a zero delay.
+fn parse_retry_after(value: &str, now: OffsetDateTime) -> Option<Duration> {
+    let value = value.trim();
+    if let Ok(secs) = value.parse::<u64>() {
+        return Some(Duration::from_secs(secs));
+    }
+    let at = OffsetDateTime::parse(value, &Rfc2822).ok()?;
+    Some(Duration::try_from(at - now).unwrap_or(Duration::ZERO))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backoff_is_capped_at_max_delay() {
+        let policy = RetryPolicy { max_retries: 40, base_delay: Duration::from_millis(500), max_delay: Duration::from_secs(10) };
+        let first = policy.backoff(1);
+        assert!(first >= Duration::from_millis(500) && first <= Duration::from_millis(750), "{:?}", first);
+        // 500 ms * 2^5 is already past the cap; far later retries would overflow uncapped.
+        for retry in [6, 20, 40] {
+            assert_eq!(policy.backoff(retry), Duration::from_secs(10));
+        }
+    }
+
+    #[test]
+    fn retry_after_parses_both_forms() {
+        let now = OffsetDateTime::parse("Wed, 21 Oct 2015 07:28:00 GMT", &Rfc2822).unwrap_or(OffsetDateTime::UNIX_EPOCH);
+        assert_eq!(parse_retry_after("120", now), Some(Duration::from_secs(120)));
+        assert_eq!(parse_retry_after("Wed, 21 Oct 2015 07:30:30 GMT", now), Some(Duration::from_secs(150)));
+        assert_eq!(parse_retry_after("Wed, 21 Oct 2015 07:00:00 GMT", now), Some(Duration::ZERO));
+        assert_eq!(parse_retry_after("soon", now), None);
+    }
+}
@@ -1,13 +1,251 @@
-use anyhow::{anyhow, Context, Result};
+use futures::StreamExt;
 use polars::prelude::*;
-use quick_xml::events::Event;
-use quick_xml::Reader as XmlReader;
 use reqwest::StatusCode;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use flate2::read::MultiGzDecoder;
+use polars_parquet::parquet::metadata::KeyValue;
 use std::fs;
+use std::io::Read;
 use std::path::{Path, PathBuf};
-use time::{Date, Time as Tm};
-use tracing::info;
+use std::time::Duration;
+use time::format_description::well_known::Rfc3339;
+use time::{Date, Month, OffsetDateTime, Time as Tm};
+use tracing::{info, warn};
+
+mod build_info;
+mod builder;
+mod cache;
+mod cancel;
+mod coords;
+mod dedupe;
+mod delimiter;
+mod derive;
+#[cfg(feature = "duckdb")]
+mod duckdb;
+mod error;
+mod export;
+mod golden;
+mod groups;
+mod http;
+mod lock;
+mod parquet_options;
+mod partition;
+mod provenance;
+mod quality;
+mod range;
+mod record;
+mod report;
+mod revision;
+mod run_index;
+mod schema;
+mod site_report;
+#[cfg(feature = "sqlite")]
+mod sqlite;
+mod station;
+#[cfg(feature = "object-store")]
+mod store;
+mod strict;
+mod wave_summary;
+
+pub use build_info::{build_info, BuildInfo};
+pub use builder::{NdbcDataBuilder, DEFAULT_USER_AGENT};
+pub use cache::METADATA_CACHE_FILE;
+use cache::MetadataCache;
+pub use cancel::{CancellationToken, FetchOptions};
+pub use coords::CoordsAs;
+pub use dedupe::{dedupe_times, DuplicateTimes};
+pub use delimiter::Delimiter;
+pub use derive::{
+    append_wavelength, deep_water_wavelength, heat_index, is_shallow_water, pressure_trend, relative_humidity, station_pressure,
+    wavelength, wind_chill, DEFAULT_SHALLOW_WATER_DEPTH_M, DEFAULT_TREND_THRESHOLD_HPA, DERIVED_COLUMNS, STATION_PRESSURE_COLUMN,
+    WAVELENGTH_COLUMNS,
+};
+#[cfg(feature = "duckdb")]
+pub use crate::duckdb::{frames_to_duckdb, DuckDbConnection};
+pub use error::{NdbcError, Result};
+pub use export::{export_columns, ExportFormat};
+pub use provenance::{read_file_provenance, Provenance};
+pub use golden::{check_golden, GoldenOutcome, GoldenResult};
+pub use groups::{station_group, StationGroup, STATION_GROUPS};
+pub use http::{HeaderFn, HttpVersion, RetryPolicy, DEFAULT_MAX_RETRY_DELAY};
+use http::RequestHeaders;
+pub use lock::{ArchiveLock, LockMode, LOCK_FILE_NAME};
+pub use parquet_options::{Compression, ParquetOptions};
+pub use partition::PartitionScheme;
+pub use quality::{station_summary, StationSummary};
+pub use report::{BatchReport, FailureCategory, SelectionMethod, StationFailure, StationSelection};
+pub use revision::{Revision, REVISION_COLUMN};
+pub use run_index::{IndexEntry, INDEX_STEM};
+pub use schema::{
+    output_json_schema, std_met_schema, ColumnCheck, ColumnExpectation, ExpectedColumns, StationClass, STD_MET_COLUMNS,
+};
+pub use site_report::{Artifact, SiteReportIndex, SiteReportOptions, EXCEEDANCE_THRESHOLDS, INDEX_FILE};
+#[cfg(feature = "sqlite")]
+pub use sqlite::{frames_to_sqlite, Connection, SqliteTarget, SQLITE_FILE};
+#[cfg(feature = "object-store")]
+pub use store::{ObjectStore, StorePath};
+pub use station::{Capabilities, Deployment, HistoryEntry, CoordsFormat, MetadataFilter, MetadataFormat, MetadataSource, Station, StationFilter, COORD_CONFLICT_KM};
+pub use strict::{ParseIssue, ParseIssueKind, ParseStats, StrictLimits, PARSE_ISSUES_SUFFIX};
+pub use wave_summary::parse_wave_summary_to_df;
+pub use record::{parse_std_met_records, records_from_frame, StdMetRecord};
+use range::{range_bounds_ms, range_pieces, RangePiece};
+use station::{document_created, haversine_km, in_bbox, stations_frame, stations_geojson, merge_sources, parse_active_stations, parse_station_metadata, parse_station_table};
+
+/// Output file format for saved station data.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum OutputFormat {
+    /// Apache Parquet (`<station>.parquet`)
+    #[default]
+    Parquet,
+    /// JSON Lines (NDJSON), one observation per line (`<station>.jsonl`)
+    #[value(alias = "ndjson")]
+    Jsonl,
+    /// Comma-separated values with a header row (`<station>.csv`)
+    Csv,
+    /// Arrow IPC file, also known as Feather v2 (`<station>.arrow`), with the Parquet schema
+    #[value(alias = "feather")]
+    ArrowIpc,
+    /// Rows upserted into a SQLite database (see [`SqliteTarget`]), keyed by station and time
+    #[cfg(feature = "sqlite")]
+    Sqlite,
+}
+
+impl OutputFormat {
+    /// File extension used for this format.
+    pub fn extension(&self) -> &'static str {
+        match self {
+            OutputFormat::Parquet => "parquet",
+            OutputFormat::Jsonl => "jsonl",
+            OutputFormat::Csv => "csv",
+            OutputFormat::ArrowIpc => "arrow",
+            #[cfg(feature = "sqlite")]
+            OutputFormat::Sqlite => "sqlite",
+        }
+    }
+
+    /// Whether saved files of this format can be merged into ([`Revision`],
+    /// [`NdbcData::append_station`]); SQLite rows are upserted by time instead.
+    pub fn supports_merge(&self) -> bool {
+        !matches!(self, OutputFormat::Jsonl | OutputFormat::Csv)
+    }
+}
+
+/// Result of [`NdbcData::fetch_and_save_station_checked`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct SavedStation {
+    /// How the fetched columns compare with what the station should report (see
+    /// [`NdbcData::check_columns`]).
+    pub check: ColumnCheck,
+    /// Completeness of the fetched rows, before any merge with rows already saved.
+    pub summary: StationSummary,
+    /// The file written; with a [`Revision`] policy it covers the whole merged file.
+    pub entry: IndexEntry,
+}
+
+/// Which realtime standard met file to download for a station.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum Window {
+    /// `data/realtime2/<station>.txt`, the last ~45 days.
+    #[default]
+    #[value(name = "45day")]
+    FortyFiveDay,
+    /// `data/5day2/<station>_5day.txt`, the last 5 days; much smaller for frequent polling.
+    #[value(name = "5day")]
+    FiveDay,
+}
+
+impl Window {
+    /// URL of the realtime file for `station` in this window on the server at `base_url`.
+    fn url(&self, base_url: &str, station: &str) -> String {
+        match self {
+            Window::FortyFiveDay => format!("{}/data/realtime2/{}.txt", base_url, station),
+            Window::FiveDay => format!("{}/data/5day2/{}_5day.txt", base_url, station),
+        }
+    }
+}
+
+/// Conversion of a single value from the unit NDBC publishes.
+type Conversion = fn(f64) -> f64;
+
+/// Unit of a column under one unit system, with the conversion from the published value
+/// (`None` when the published value is already in that unit).
+type TargetUnit = (&'static str, Option<Conversion>);
+
+/// Per-column units: the column, its unit as published by NDBC, and its unit under
+/// [`Units::Metric`] and [`Units::Imperial`]. Columns not listed (directions, periods) are the
+/// same in every system.
+const COLUMN_UNITS: [(&str, &str, TargetUnit, TargetUnit); 13] = [
+    ("WSPD", "m/s", ("m/s", None), ("kn", Some(|v| v * 1.943_844))),
+    ("GST", "m/s", ("m/s", None), ("kn", Some(|v| v * 1.943_844))),
+    ("WVHT", "m", ("m", None), ("ft", Some(|v| v * 3.280_84))),
+    ("PRES", "hPa", ("hPa", None), ("inHg", Some(|v| v * 0.029_53))),
+    ("ATMP", "degC", ("degC", None), ("degF", Some(|c| c * 9.0 / 5.0 + 32.0))),
+    ("WTMP", "degC", ("degC", None), ("degF", Some(|c| c * 9.0 / 5.0 + 32.0))),
+    ("DEWP", "degC", ("degC", None), ("degF", Some(|c| c * 9.0 / 5.0 + 32.0))),
+    ("VIS", "nmi", ("km", Some(|v| v * 1.852)), ("nmi", None)),
+    ("PTDY", "hPa", ("hPa", None), ("inHg", Some(|v| v * 0.029_53))),
+    ("TIDE", "ft", ("ft", None), ("ft", None)),
+    ("wind_chill", "degC", ("degC", None), ("degF", Some(|c| c * 9.0 / 5.0 + 32.0))),
+    ("heat_index", "degC", ("degC", None), ("degF", Some(|c| c * 9.0 / 5.0 + 32.0))),
+    ("station_pressure", "hPa", ("hPa", None), ("inHg", Some(|v| v * 0.029_53))),
+];
+
+/// Unit system for the saved and returned values.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum Units {
+    /// Values exactly as NDBC publishes them: SI except `VIS` in nautical miles and `TIDE` in
+    /// feet.
+    #[default]
+    Native,
+    /// Native units with `VIS` in km; this is the only column it changes.
+    Metric,
+    /// US customary units: WSPD/GST in knots, WVHT in feet, PRES and PTDY in inHg and
+    /// ATMP/WTMP/DEWP (and any derived temperatures) in degrees F. `VIS` stays in nautical miles.
+    Imperial,
+}
+
+impl Units {
+    /// Convert the standard met columns of a parsed frame (in NDBC's published units) to these
+    /// units, keeping column names. Nulls stay null.
+    pub fn convert(&self, df: DataFrame) -> Result<DataFrame> {
+        let mut df = df;
+        for (name, ..) in COLUMN_UNITS {
+            let (Some(f), Ok(s)) = (self.conversion(name), df.column(name)) else {
+                continue;
+            };
+            let converted = s.f64()?.apply_values(f).into_series();
+            df.replace(name, converted)?;
+        }
+        Ok(df)
+    }
+
+    /// Convert one value of `column` from NDBC's published unit to these units, e.g. a threshold
+    /// to compare with converted frames. Columns without a unit table entry are unchanged.
+    pub(crate) fn convert_value(&self, column: &str, v: f64) -> f64 {
+        self.conversion(column).map_or(v, |f| f(v))
+    }
+
+    fn conversion(&self, column: &str) -> Option<Conversion> {
+        let (_, _, metric, imperial) = COLUMN_UNITS.iter().find(|(name, ..)| *name == column)?;
+        match self {
+            Units::Native => None,
+            Units::Metric => metric.1,
+            Units::Imperial => imperial.1,
+        }
+    }
+
+    /// Unit of `column` in frames converted to these units, e.g. `km` for `VIS` under
+    /// [`Units::Metric`]; `None` for columns without a unit table entry (directions in degrees
+    /// and periods in seconds, which never change).
+    pub fn unit(&self, column: &str) -> Option<&'static str> {
+        let (_, native, metric, imperial) = COLUMN_UNITS.iter().find(|(name, ..)| *name == column)?;
+        Some(match self {
+            Units::Native => native,
+            Units::Metric => metric.0,
+            Units::Imperial => imperial.0,
+        })
+    }
+}
 
 /// Core library for downloading, parsing, and saving NOAA NDBC standard met data.
 ///
@@ -15,162 +253,1582 @@ use tracing::info;
 /// async methods to fetch metadata and process stations.
 pub struct NdbcData {
     client: reqwest::Client,
-    out_dir: PathBuf,
-    // Map of station id -> (latitude, longitude) for stations with met data
-    station_meta: HashMap<String, (f64, f64)>,
+    // Scheme and host files are downloaded from, without a trailing slash
+    base_url: String,
+    // None for in-memory instances that never write files
+    out_dir: Option<PathBuf>,
+    format: OutputFormat,
+    // Database and table for `OutputFormat::Sqlite`
+    #[cfg(feature = "sqlite")]
+    sqlite: SqliteTarget,
+    // Codec, row groups and statistics of every Parquet file written
+    parquet: ParquetOptions,
+    retry: RetryPolicy,
+    // Extra headers added to every request
+    headers: RequestHeaders,
+    concurrency: usize,
+    window: Window,
+    precision: Option<u32>,
+    units: Units,
+    // Append wind chill / heat index / pressure trend columns
+    derive: bool,
+    // Append a station pressure column from PRES, ATMP and the station elevation
+    station_pressure: bool,
+    // Met columns to keep (frame names, e.g. `WVHT`); all standard ones when `None`
+    columns: Option<Vec<String>>,
+    // Append station_type / elevation / anemometer_height columns from the metadata
+    station_metadata_columns: bool,
+    // Where saved Parquet station files keep latitude/longitude
+    coords_as: CoordsAs,
+    // Flat files or station=/date= partitions
+    partition: PartitionScheme,
+    // PTDY change (hPa) within which the pressure trend is steady
+    trend_threshold: f64,
+    // Parse anomaly limits; None only logs anomalies
+    strict: Option<StrictLimits>,
+    // Time order and repeated timestamps of parsed rows
+    duplicate_times: DuplicateTimes,
+    // Field separator of parsed text
+    delimiter: Delimiter,
+    // Write a `<stem>.parse_issues.json` sidecar next to each saved file
+    parse_issues: bool,
+    // Keep the downloaded realtime text as `<station>.txt`
+    save_raw: bool,
+    // Columns each station class should populate, checked after parsing
+    expected_columns: ExpectedColumns,
+    // How a fetch merges into an existing station file; None overwrites it
+    revision: Option<Revision>,
+    // Skip fetching a station whose saved file is younger than this
+    incremental: Option<Duration>,
+    // Object store station files are put to in place of `out_dir`
+    #[cfg(feature = "object-store")]
+    store: Option<store::OutputStore>,
+    // Map of station id -> metadata for every station in the metadata XML
+    stations: HashMap<String, Station>,
+    // Source of `stations`, once loaded
+    metadata_source: Option<MetadataSource>,
+    // Which loaded stations the station queries return
+    metadata_filter: MetadataFilter,
+    // Station id -> current (lat, lon), derived from `stations` for stations with both known
+    locations: HashMap<String, (f64, f64)>,
+}
+
+impl NdbcData {
+    /// Create a new instance with default settings and ensure the output directory exists and,
+    /// when relative, is gitignored. Use [`NdbcData::builder`] for more options.
+    pub fn new(out_dir: impl Into<PathBuf>) -> Result<Self> {
+        Self::builder().out_dir(out_dir).build()
+    }
+
+    /// Create an instance for pure in-memory use that performs no filesystem setup (no output
+    /// directory, no `.gitignore` changes). Use the DataFrame-returning methods such as
+    /// [`fetch_station_df`](Self::fetch_station_df) and [`fetch_range`](Self::fetch_range).
+    pub fn in_memory() -> Result<Self> {
+        Self::builder().in_memory().build()
+    }
+
+    /// Start configuring a new instance.
+    pub fn builder() -> NdbcDataBuilder {
+        NdbcDataBuilder::default()
+    }
+
+    fn from_parts(client: reqwest::Client, out_dir: Option<PathBuf>, concurrency: usize, window: Window, precision: Option<u32>) -> Self {
+        Self {
+            client,
+            base_url: NDBC_BASE_URL.to_string(),
+            out_dir,
+            format: OutputFormat::default(),
+            #[cfg(feature = "sqlite")]
+            sqlite: SqliteTarget::default(),
+            parquet: ParquetOptions::default(),
+            retry: RetryPolicy::default(),
+            headers: RequestHeaders::default(),
+            concurrency,
+            window,
+            precision,
+            units: Units::Native,
+            derive: false,
+            station_pressure: false,
+            columns: None,
+            station_metadata_columns: false,
+            coords_as: CoordsAs::Column,
+            partition: PartitionScheme::Flat,
+            trend_threshold: DEFAULT_TREND_THRESHOLD_HPA,
+            strict: None,
+            duplicate_times: DuplicateTimes::Keep,
+            delimiter: Delimiter::Whitespace,
+            parse_issues: false,
+            save_raw: false,
+            expected_columns: ExpectedColumns::default(),
+            revision: None,
+            incremental: None,
+            #[cfg(feature = "object-store")]
+            store: None,
+            stations: HashMap::new(),
+            metadata_source: None,
+            metadata_filter: MetadataFilter::default(),
+            locations: HashMap::new(),
+        }
+    }
+
+    /// Number of stations batch callers should process at once.
+    pub fn concurrency(&self) -> usize {
+        self.concurrency
+    }
+
+    /// Select the output format used when saving stations.
+    pub(crate) fn with_format(mut self, format: OutputFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    /// Database file and table used by [`OutputFormat::Sqlite`] (feature `sqlite`).
+    #[cfg(feature = "sqlite")]
+    pub(crate) fn with_sqlite_target(mut self, target: SqliteTarget) -> Self {
+        self.sqlite = target;
+        self
+    }
+
+    pub(crate) fn with_headers(mut self, headers: RequestHeaders) -> Self {
+        self.headers = headers;
+        self
+    }
+
+    /// Convert values to `units` before they are saved or returned.
+    pub(crate) fn with_units(mut self, units: Units) -> Self {
+        self.units = units;
+        self
+    }
+
+    /// Append [`DERIVED_COLUMNS`] (wind chill, heat index and pressure trend) to saved and
+    /// returned frames.
+    pub(crate) fn with_derived(mut self, derive: bool) -> Self {
+        self.derive = derive;
+        self
+    }
+
+    /// Append [`STATION_PRESSURE_COLUMN`]: `PRES`, which NDBC publishes reduced to sea level,
+    /// brought back up to the station's metadata elevation (see [`station_pressure`]). It equals
+    /// `PRES` for buoys at sea level and differs for elevated C-MAN and Great Lakes stations. The
+    /// column is null for stations without a known elevation, and per row where `PRES` or `ATMP`
+    /// is missing.
+    pub(crate) fn with_station_pressure(mut self, enabled: bool) -> Self {
+        self.station_pressure = enabled;
+        self
+    }
+
+    /// Keep only these met columns (case-insensitive, e.g. `["wvht", "dpd", "mwd"]`, in that
+    /// order) in saved and returned frames, besides the time, station and coordinate columns.
+    /// Any numeric column of the file can be named; see [`parse_std_met_columns`].
+    pub(crate) fn with_columns<S: AsRef<str>>(mut self, columns: impl IntoIterator<Item = S>) -> Self {
+        let mut selected: Vec<String> = Vec::new();
+        for name in columns.into_iter().map(|c| column_key(c.as_ref())) {
+            if !selected.contains(&name) {
+                selected.push(name);
+            }
+        }
+        self.columns = Some(selected);
+        self
+    }
+
+    /// Lay saved station files out as `station=<id>/date=<YYYY-MM-DD>/part-0.<ext>` partitions
+    /// instead of one file per station (see [`PartitionScheme`]). The combined file is not
+    /// partitioned.
+    pub(crate) fn with_partition(mut self, partition: PartitionScheme) -> Self {
+        self.partition = partition;
+        self
+    }
+
+    /// Store `latitude`/`longitude` once in each Parquet station file's key-value metadata
+    /// instead of on every row (see [`CoordsAs`]). The combined file always keeps the columns.
+    pub(crate) fn with_coords_as(mut self, coords_as: CoordsAs) -> Self {
+        self.coords_as = coords_as;
+        self
+    }
+
+    /// Append the station's `station_type` (e.g. `buoy`, `fixed`), `elevation` (m) and
+    /// `anemometer_height` (m above the site, from the deployment covering each row's date; see
+    /// [`station_anemometer_height_at`](Self::station_anemometer_height_at)) from the loaded
+    /// metadata to every row of saved and returned frames; null when unknown.
+    pub(crate) fn with_station_metadata_columns(mut self, enabled: bool) -> Self {
+        self.station_metadata_columns = enabled;
+        self
+    }
+
+    /// Largest 3-hour pressure change (hPa, either way) classed as `steady` in the derived
+    /// `pressure_trend` column (default [`DEFAULT_TREND_THRESHOLD_HPA`]).
+    pub(crate) fn with_trend_threshold(mut self, threshold_hpa: f64) -> Self {
+        self.trend_threshold = threshold_hpa;
+        self
+    }
+
+    /// Fail a station when parsing its file hits more anomalies than `limits` allow, instead of
+    /// only logging them.
+    pub(crate) fn with_strict(mut self, limits: StrictLimits) -> Self {
+        self.strict = Some(limits);
+        self
+    }
+
+    /// Sort each parsed file by time and drop repeated timestamps, keeping the first or last row
+    /// of each (see [`DuplicateTimes`]); the default keeps the file's rows as they are. Strict
+    /// limits still count the duplicates found before they are dropped.
+    pub(crate) fn with_duplicate_times(mut self, mode: DuplicateTimes) -> Self {
+        self.duplicate_times = mode;
+        self
+    }
+
+    /// Parse text with fields separated by `delimiter` (default whitespace, as NDBC publishes).
+    /// Meant for [`frame_from_text`](Self::frame_from_text) on files converted to CSV; downloads
+    /// from NDBC need the default.
+    pub(crate) fn with_delimiter(mut self, delimiter: Delimiter) -> Self {
+        self.delimiter = delimiter;
+        self
+    }
+
+    /// Next to each file saved from a download, write `<stem>.parse_issues.json` listing the
+    /// lines the parser flagged (see [`save_parse_issues`](Self::save_parse_issues)). A file
+    /// that fails strict limits is not saved and gets no sidecar.
+    pub(crate) fn with_parse_issues(mut self, enabled: bool) -> Self {
+        self.parse_issues = enabled;
+        self
+    }
+
+    /// Keep the realtime text [`fetch_and_save_station`](Self::fetch_and_save_station) downloads
+    /// as `<station>.txt` in the output directory, written before parsing so a file that fails to
+    /// parse is kept too. Handy for bug reports and as parser fixtures.
+    pub(crate) fn with_save_raw(mut self, enabled: bool) -> Self {
+        self.save_raw = enabled;
+        self
+    }
+
+    /// Replace the per-class column expectations used by [`check_columns`](Self::check_columns).
+    pub(crate) fn with_expected_columns(mut self, expected: ExpectedColumns) -> Self {
+        self.expected_columns = expected;
+        self
+    }
+
+    /// Merge each saved station into its existing Parquet or Arrow IPC file using `revision`
+    /// instead of overwriting the file. Saving in another format then fails with
+    /// [`NdbcError::MergeUnsupported`] rather than overwriting what is there.
+    pub(crate) fn with_revision(mut self, revision: Revision) -> Self {
+        self.revision = Some(revision);
+        self
+    }
+
+    /// Encode every Parquet file written (station files, the combined file, the run index and
+    /// station metadata) with `options` instead of the defaults.
+    pub(crate) fn with_parquet_options(mut self, options: ParquetOptions) -> Self {
+        self.parquet = options;
+        self
+    }
+
+    /// Skip stations whose saved file was written less than `max_age` ago: saving them fails with
+    /// [`NdbcError::UpToDate`] before anything is downloaded. Realtime feeds update about hourly,
+    /// so this spares scheduled runs from re-fetching unchanged data.
+    pub(crate) fn with_incremental(mut self, max_age: Duration) -> Self {
+        self.incremental = Some(max_age);
+        self
+    }
+
+    /// Save station files fetched by [`fetch_and_save_station`](Self::fetch_and_save_station) to
+    /// `store` as `<prefix>/<station>.<ext>` instead of the output directory (feature
+    /// `object-store`).
+    #[cfg(feature = "object-store")]
+    pub(crate) fn with_store(mut self, store: std::sync::Arc<dyn ObjectStore>, prefix: StorePath) -> Self {
+        self.store = Some(store::OutputStore { store, prefix });
+        self
+    }
+
+    /// Choose which loaded stations [`all_station_ids`](Self::all_station_ids) and the bounding
+    /// box and distance queries return (default [`MetadataFilter::MetOnly`]).
+    pub(crate) fn with_metadata_filter(mut self, filter: MetadataFilter) -> Self {
+        self.metadata_filter = filter;
+        self
+    }
+
+    /// Download from the server at `base_url` (scheme and host, e.g. a mirror or a test server)
+    /// instead of [`NDBC_BASE_URL`]; paths below it are the same as on NDBC.
+    pub(crate) fn with_base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = base_url.into().trim_end_matches('/').to_string();
+        self
+    }
+
+    /// URL of `path` (starting with `/`) on the configured server.
+    pub(crate) fn ndbc_url(&self, path: &str) -> String {
+        format!("{}{}", self.base_url, path)
+    }
+
+    /// Set how transient HTTP failures are retried for metadata and station downloads.
+    pub(crate) fn with_retry(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    /// Take an exclusive advisory lock on the output directory for the duration of a writing
+    /// run, so overlapping processes cannot interleave writes. Held until the guard is dropped.
+    ///
+    /// Fails fast with [`NdbcError::ArchiveLocked`] when another process holds the lock, unless
+    /// `wait` is given, in which case it keeps retrying for up to that long.
+    pub async fn lock_archive(&self, wait: Option<Duration>) -> Result<ArchiveLock> {
+        let out_dir = self.out_dir.as_ref().ok_or(NdbcError::NoOutputDir)?;
+        ArchiveLock::acquire(out_dir, LockMode::Exclusive, wait).await
+    }
+
+    /// Take a shared lock on the output directory for read-only use; it only conflicts with an
+    /// exclusive [`lock_archive`](Self::lock_archive) holder.
+    pub async fn lock_archive_shared(&self, wait: Option<Duration>) -> Result<ArchiveLock> {
+        let out_dir = self.out_dir.as_ref().ok_or(NdbcError::NoOutputDir)?;
+        ArchiveLock::acquire(out_dir, LockMode::Shared, wait).await
+    }
+
+    /// Download and lightly-validate the station metadata XML.
+    pub async fn fetch_station_metadata(&mut self) -> Result<()> {
+        self.fetch_station_metadata_with(&FetchOptions::default()).await
+    }
+
+    /// [`fetch_station_metadata`](Self::fetch_station_metadata) bounded by `opts`.
+    pub async fn fetch_station_metadata_with(&mut self, opts: &FetchOptions) -> Result<()> {
+        let url = self.ndbc_url(STATION_METADATA_PATH);
+        info!(%url, "downloading station metadata");
+        let xml = opts.guard(self.download(&url)).await?;
+
+        opts.check()?;
+        self.load_stations(parse_station_metadata(xml.as_bytes())?, MetadataSource::StationMetadata)
+    }
+
+    /// Load station metadata from `out_dir/station_meta.json` when it is no older than `max_age`,
+    /// otherwise download it as [`fetch_station_metadata`](Self::fetch_station_metadata) does
+    /// and rewrite the cache.
+    ///
+    /// A missing, stale or corrupt cache falls back to the network. In-memory instances have no
+    /// cache and always download.
+    pub async fn fetch_station_metadata_cached(&mut self, max_age: Duration) -> Result<()> {
+        if let Some(path) = self.metadata_cache_path() {
+            match MetadataCache::read(&path) {
+                Ok(Some(cache)) if cache.is_fresh(MetadataSource::StationMetadata, max_age) => {
+                    info!(file = %path.display(), fetched_at = %cache.fetched_at, "using cached station metadata");
+                    return self.load_stations(cache.into_stations(), MetadataSource::StationMetadata);
+                }
+                Ok(_) => {}
+                Err(e) => warn!(file = %path.display(), error = %e, "ignoring unreadable metadata cache"),
+            }
+        }
+        self.force_refresh().await
+    }
+
+    /// Write every station saved as Parquet in the output directory to `out` as a single file,
+    /// one station at a time (sorted by id) and time-ordered within each station, so archives
+    /// larger than memory can be piped elsewhere. Returns the number of rows written.
+    ///
+    /// Columns follow [`export_columns`]; `--precision` style rounding applies as for JSON.
+    /// Fields are written to `out` one at a time, so pass a buffered writer.
+    pub fn export(&self, format: ExportFormat, out: impl std::io::Write) -> Result<usize> {
+        let out_dir = self.out_dir.as_ref().ok_or(NdbcError::NoOutputDir)?;
+        match format {
+            ExportFormat::Csv => export::export_csv(out_dir, self.precision, out),
+        }
+    }
+
+    /// Download station metadata regardless of the cache's age and rewrite the cache.
+    pub async fn force_refresh(&mut self) -> Result<()> {
+        self.fetch_station_metadata().await?;
+        if let Some(path) = self.metadata_cache_path() {
+            let cache = MetadataCache::new(MetadataSource::StationMetadata, &self.stations);
+            if let Err(e) = cache.write(&path) {
+                warn!(file = %path.display(), error = %e, "failed to write metadata cache");
+            }
+        }
+        Ok(())
+    }
+
+    fn metadata_cache_path(&self) -> Option<PathBuf> {
+        self.out_dir.as_ref().map(|dir| dir.join(METADATA_CACHE_FILE))
+    }
+
+    /// Download the compact `activestations.xml` list and use it as the station metadata.
+    ///
+    /// A lighter-weight alternative to [`fetch_station_metadata`](Self::fetch_station_metadata)
+    /// when only currently active stations matter: it populates the same station map (without
+    /// deployment history), so the rest of the API works unchanged.
+    pub async fn fetch_active_stations(&mut self) -> Result<()> {
+        self.fetch_active_stations_with(&FetchOptions::default()).await
+    }
+
+    /// [`fetch_active_stations`](Self::fetch_active_stations) bounded by `opts`.
+    pub async fn fetch_active_stations_with(&mut self, opts: &FetchOptions) -> Result<()> {
+        let url = self.ndbc_url(ACTIVE_STATIONS_PATH);
+        info!(%url, "downloading active stations");
+        let xml = opts.guard(self.download(&url)).await?;
+
+        opts.check()?;
+        self.load_stations(parse_active_stations(xml.as_bytes())?, MetadataSource::ActiveStations)
+    }
+
+    /// Download both `stationmetadata.xml` and `activestations.xml` and merge them per station.
+    ///
+    /// For a station listed in both, the file with the later `created` timestamp wins and the
+    /// other only fills missing fields (the deployment history always comes from
+    /// `stationmetadata.xml`); ties go to the active list. Positions that disagree by more than
+    /// [`COORD_CONFLICT_KM`] are logged as warnings.
+    pub async fn fetch_merged_metadata(&mut self) -> Result<()> {
+        self.fetch_merged_metadata_with(&FetchOptions::default()).await
+    }
+
+    /// [`fetch_merged_metadata`](Self::fetch_merged_metadata) bounded by `opts`.
+    pub async fn fetch_merged_metadata_with(&mut self, opts: &FetchOptions) -> Result<()> {
+        let (full_url, active_url) = (self.ndbc_url(STATION_METADATA_PATH), self.ndbc_url(ACTIVE_STATIONS_PATH));
+        info!(full = %full_url, active = %active_url, "downloading station metadata from both sources");
+        let (full, active) = opts
+            .guard(async { futures::try_join!(self.download(&full_url), self.download(&active_url)) })
+            .await?;
+
+        opts.check()?;
+        let stations = merge_sources(
+            parse_station_metadata(full.as_bytes())?,
+            document_created(full.as_bytes()),
+            parse_active_stations(active.as_bytes())?,
+            document_created(active.as_bytes()),
+            COORD_CONFLICT_KM,
+        );
+        self.load_stations(stations, MetadataSource::Merged)
+    }
+
+    /// Download NDBC's pipe-delimited `station_table.txt` and merge it into the loaded station
+    /// metadata, filling fields the XML left empty: owner code, type, hull, payload, time zone,
+    /// notes and, for stations without one, the position.
+    ///
+    /// Values already present are kept. Stations only listed in the table are added without
+    /// capability flags, so they do not appear in [`all_station_ids`](Self::all_station_ids).
+    pub async fn fetch_station_table(&mut self) -> Result<()> {
+        self.fetch_station_table_with(&FetchOptions::default()).await
+    }
+
+    /// [`fetch_station_table`](Self::fetch_station_table) bounded by `opts`.
+    pub async fn fetch_station_table_with(&mut self, opts: &FetchOptions) -> Result<()> {
+        let url = self.ndbc_url("/data/stations/station_table.txt");
+        info!(%url, "downloading station table");
+        let text = opts.guard(self.download(&url)).await?;
+
+        opts.check()?;
+        let table = parse_station_table(&text)?;
+        let count = table.len();
+        for (id, row) in table {
+            match self.stations.get_mut(&id) {
+                Some(station) => station.merge_missing(row),
+                None => {
+                    self.stations.insert(id, row);
+                }
+            }
+        }
+        self.refresh_locations();
+        info!(count, "station table merged");
+        Ok(())
+    }
+
+    /// Load station metadata from the given source.
+    pub async fn fetch_metadata_from(&mut self, source: MetadataSource) -> Result<()> {
+        match source {
+            MetadataSource::StationMetadata => self.fetch_station_metadata().await,
+            MetadataSource::ActiveStations => self.fetch_active_stations().await,
+            MetadataSource::Merged => self.fetch_merged_metadata().await,
+        }
+    }
+
+    /// Which source backs the loaded station metadata (and so [`all_station_ids`](Self::all_station_ids)).
+    pub fn metadata_source(&self) -> Option<MetadataSource> {
+        self.metadata_source
+    }
+
+    /// GET `url` with retries and return the body of a successful response as text, decompressed
+    /// when it is gzip (see [`decode_text`]).
+    async fn download(&self, url: &str) -> Result<String> {
+        let bytes = http::get(&self.client, url, &self.retry, &self.headers).await?.error_for_status()?.bytes().await?;
+        decode_text(&bytes)
+    }
+
+    fn refresh_locations(&mut self) {
+        self.locations = self.stations.iter().filter_map(|(id, s)| Some((id.clone(), s.coords()?))).collect();
+    }
+
+    fn load_stations(&mut self, stations: HashMap<String, Station>, source: MetadataSource) -> Result<()> {
+        self.stations = stations;
+        self.metadata_source = Some(source);
+        self.refresh_locations();
+
+        let count = self.stations.values().filter(|s| self.metadata_filter.includes(s)).count();
+        if count == 0 {
+            return Err(NdbcError::Parse(format!("no stations matching {:?} found in metadata", self.metadata_filter)));
+        }
+        info!(count, total = self.stations.len(), source = ?self.metadata_source, "station metadata retrieved");
+        Ok(())
+    }
+
+    /// Fetch realtime data for a station, parse, and save in the configured format into the output directory.
+    ///
+    /// The configured [`Window`] picks the 45-day or 5-day file; both produce the same columns.
+    /// [`fetch_station_df`](Self::fetch_station_df) runs the same download and parsing and returns
+    /// the frame instead, without touching the filesystem (see also [`NdbcData::in_memory`]).
+    pub async fn fetch_and_save_station(&self, station: &str) -> Result<()> {
+        self.fetch_and_save_station_with(station, &FetchOptions::default()).await
+    }
+
+    /// [`fetch_and_save_station`](Self::fetch_and_save_station) bounded by `opts`.
+    pub async fn fetch_and_save_station_with(&self, station: &str, opts: &FetchOptions) -> Result<()> {
+        self.fetch_and_save_station_checked(station, opts).await.map(|_| ())
+    }
+
+    /// [`fetch_and_save_station_with`](Self::fetch_and_save_station_with), also returning what
+    /// was fetched and written (see [`SavedStation`]).
+    pub async fn fetch_and_save_station_checked(&self, station: &str, opts: &FetchOptions) -> Result<SavedStation> {
+        if let (Some(max_age), Some(age)) = (self.incremental, self.saved_age(station))
+            && age < max_age
+        {
+            return Err(NdbcError::UpToDate { station: station.to_string(), age });
+        }
+        let text = opts.guard(self.realtime_text(station, self.window)).await?;
+        if self.save_raw {
+            self.save_raw_text(station, &text)?;
+        }
+
+        opts.check()?;
+        let df = self.parse_archive_for_save(station, &text, station)?;
+        let check = self.check_columns(station, &df);
+        let summary = station_summary(&df);
+
+        opts.check()?;
+        #[cfg(feature = "object-store")]
+        if let Some(output) = &self.store {
+            let entry = opts.guard(self.put_station_frame(output, station, df, &self.window.url(&self.base_url, station))).await?;
+            return Ok(SavedStation { check, summary, entry });
+        }
+        let entry = self.save_station_frame(station, df, station, &self.window.url(&self.base_url, station))?;
+        Ok(SavedStation { check, summary, entry })
+    }
+
+    /// Write downloaded text for `station` to `<station>.txt` in the output directory, atomically.
+    fn save_raw_text(&self, station: &str, text: &str) -> Result<()> {
+        let out_dir = self.out_dir.as_ref().ok_or(NdbcError::NoOutputDir)?;
+        let out_path = out_dir.join(format!("{}.txt", station));
+        let tmp_path = out_path.with_extension("txt.part");
+        info!(file = %out_path.display(), bytes = text.len(), "writing raw text");
+        let written = fs::write(&tmp_path, text).and_then(|_| fs::rename(&tmp_path, &out_path));
+        if written.is_err() {
+            let _ = fs::remove_file(&tmp_path);
+        }
+        Ok(written?)
+    }
+
+    /// Time since the station's realtime file was last written, from its modification time; with
+    /// partitioned output, the newest partition counts. `None` when nothing is saved yet.
+    fn saved_age(&self, station: &str) -> Option<Duration> {
+        let out_dir = self.out_dir.as_ref()?;
+        let modified = match self.partition {
+            PartitionScheme::Flat => fs::metadata(out_dir.join(format!("{}.{}", station, self.format.extension()))).ok()?.modified().ok()?,
+            PartitionScheme::StationDate => fs::read_dir(out_dir.join(partition::station_dir(station)))
+                .ok()?
+                .flatten()
+                .filter_map(|date| fs::read_dir(date.path()).ok())
+                .flatten()
+                .flatten()
+                .filter_map(|part| part.metadata().ok()?.modified().ok())
+                .max()?,
+        };
+        // A clock step backwards can put the file in the future; treat it as just written.
+        Some(modified.elapsed().unwrap_or_default())
+    }
+
+    /// Fetch realtime data for a station and append it to its saved Parquet or Arrow IPC file:
+    /// the new rows are merged with those already saved, a repeated `time_ms` keeps the newest
+    /// values, and the file is rewritten sorted by time. This is saving with
+    /// [`Revision::KeepLatest`] whatever policy is configured. Other formats cannot be merged
+    /// into and fail with [`NdbcError::MergeUnsupported`] before anything is downloaded.
+    pub async fn append_station(&self, station: &str) -> Result<()> {
+        if !self.format.supports_merge() {
+            return Err(NdbcError::MergeUnsupported(self.format));
+        }
+        let text = self.realtime_text(station, self.window).await?;
+        let df = self.parse_archive_for_save(station, &text, station)?;
+        self.save_station_frame_with(station, df, station, &self.window.url(&self.base_url, station), Some(Revision::KeepLatest)).map(|_| ())
+    }
+
+    /// Compare the columns populated in a station's frame with the [`ExpectedColumns`] for its
+    /// [`StationClass`], taken from the loaded metadata. Without metadata for the station only
+    /// [`ColumnCheck::missing`] is filled.
+    pub fn check_columns(&self, station: &str, df: &DataFrame) -> ColumnCheck {
+        let class = self
+            .station(station)
+            .and_then(|s| s.station_type.as_deref())
+            .and_then(StationClass::from_station_type);
+        self.expected_columns.check(class, df)
+    }
+
+    /// Fetch realtime data for several stations and save them as a single `combined.<ext>` file
+    /// in the configured format, one row per observation with its `station_id`.
+    ///
+    /// Stations are fetched [`concurrency`](Self::concurrency) at a time. Stations that fail are
+    /// logged and left out; the first error is returned only when none succeed.
+    pub async fn fetch_and_save_combined(&self, stations: &[String]) -> Result<()> {
+        let frames = self.fetch_station_frames(stations).await?;
+        self.save_combined(frames).map(|_| ())
+    }
+
+    /// [`fetch_and_save_combined`](Self::fetch_and_save_combined) writing to `path` (in the
+    /// configured format, whatever its extension) instead of `combined.<ext>` in the output
+    /// directory. Works on in-memory instances too.
+    pub async fn fetch_and_save_combined_to(&self, stations: &[String], path: &Path) -> Result<()> {
+        let frames = self.fetch_station_frames(stations).await?;
+        self.save_combined_to(frames, path).map(|_| ())
+    }
+
+    /// Fetch [`fetch_station_df`](Self::fetch_station_df) frames for several stations,
+    /// [`concurrency`](Self::concurrency) at a time. Failing stations are logged and skipped; the
+    /// first error is returned only when none succeed.
+    async fn fetch_station_frames(&self, stations: &[String]) -> Result<Vec<DataFrame>> {
+        let results: Vec<_> = futures::stream::iter(stations)
+            .map(|station| async move { (station, self.fetch_station_df(station).await) })
+            .buffer_unordered(self.concurrency)
+            .collect()
+            .await;
+
+        let mut frames: Vec<DataFrame> = Vec::new();
+        let mut first_err: Option<NdbcError> = None;
+        for (station, result) in results {
+            match result {
+                Ok(df) => frames.push(df),
+                Err(e) => {
+                    warn!(station = %station, error = %e, "station unavailable; leaving it out");
+                    first_err.get_or_insert(e);
+                }
+            }
+        }
+        if frames.is_empty() {
+            return Err(first_err.unwrap_or(NdbcError::NoMetRows));
+        }
+        Ok(frames)
+    }
+
+    /// Stack station frames (as returned by [`fetch_station_df`](Self::fetch_station_df)) and
+    /// write them as `combined.<ext>`, sorted by `station_id` and time. Returns the path written.
+    ///
+    /// Every frame is first aligned to the standard column set and types, so a frame missing a
+    /// column gets it filled with nulls instead of breaking the concatenation.
+    pub fn save_combined(&self, frames: Vec<DataFrame>) -> Result<PathBuf> {
+        let out_dir = self.out_dir.as_ref().ok_or(NdbcError::NoOutputDir)?;
+        self.save_combined_to(frames, &out_dir.join(format!("{}.{}", COMBINED_STEM, self.format.extension())))
+    }
+
+    /// [`save_combined`](Self::save_combined) writing to `path` instead, creating its parent
+    /// directories. With [`OutputFormat::Sqlite`] the rows go to the configured database and
+    /// `path` is not used.
+    pub fn save_combined_to(&self, frames: Vec<DataFrame>, path: &Path) -> Result<PathBuf> {
+        #[cfg(feature = "sqlite")]
+        if self.format == OutputFormat::Sqlite {
+            return self.save_to_sqlite(&frames);
+        }
+        let mut combined = DataFrame::empty();
+        for df in frames {
+            let df = align_columns(&df, &self.output_columns())?;
+            if combined.width() == 0 {
+                combined = df;
+            } else {
+                combined.vstack_mut(&df)?;
+            }
+        }
+        let combined = combined.sort(["station_id", "time_ms"], SortMultipleOptions::default())?;
+        self.write_frame_to(combined, path, self.provenance_key_values(None, None))?;
+        Ok(path.to_path_buf())
+    }
+
+    /// Download the realtime text for a station (the file the configured [`Window`] selects),
+    /// exactly as NDBC returned it apart from gzip decoding, without parsing it. Useful to see
+    /// why a station fails to parse and to save fixtures for offline parser tests.
+    pub async fn fetch_station_raw(&self, station: &str) -> Result<String> {
+        self.fetch_station_raw_with(station, &FetchOptions::default()).await
+    }
+
+    /// [`fetch_station_raw`](Self::fetch_station_raw) bounded by `opts`.
+    pub async fn fetch_station_raw_with(&self, station: &str, opts: &FetchOptions) -> Result<String> {
+        opts.guard(self.realtime_text(station, self.window)).await
+    }
+
+    /// Fetch realtime data for a station and return it as a DataFrame (with `station_id`,
+    /// `latitude` and `longitude` columns) without writing anything to disk.
+    pub async fn fetch_station_df(&self, station: &str) -> Result<DataFrame> {
+        self.fetch_station_df_with(station, &FetchOptions::default()).await
+    }
+
+    /// [`fetch_station_df`](Self::fetch_station_df) bounded by `opts`.
+    pub async fn fetch_station_df_with(&self, station: &str, opts: &FetchOptions) -> Result<DataFrame> {
+        let text = opts.guard(self.realtime_text(station, self.window)).await?;
+
+        opts.check()?;
+        let df = self.parse_archive(&text)?;
+        self.with_station_columns(station, df)
+    }
+
+    /// Parse standard met `text` already at hand (e.g. a saved NDBC file) for `station` into the
+    /// frame [`fetch_station_df`](Self::fetch_station_df) would return: same parsing, strict
+    /// limits, units, optional columns and coordinates from the loaded metadata.
+    pub fn frame_from_text(&self, station: &str, text: &str) -> Result<DataFrame> {
+        let df = self.parse_archive(text)?;
+        self.with_station_columns(station, df)
+    }
+
+    /// Fetch the yearly historical standard met archive
+    /// (`data/historical/stdmet/<station>h<year>.txt.gz`) for a station and return it as a
+    /// DataFrame with the same columns as [`fetch_station_df`](Self::fetch_station_df), without
+    /// writing anything to disk.
+    ///
+    /// Returns [`NdbcError::HistoricalNotFound`] when NDBC has no archive for that station and year.
+    pub async fn fetch_historical_stdmet(&self, station: &str, year: i32) -> Result<DataFrame> {
+        self.fetch_historical_stdmet_with(station, year, &FetchOptions::default()).await
+    }
+
+    /// [`fetch_historical_stdmet`](Self::fetch_historical_stdmet) bounded by `opts`.
+    pub async fn fetch_historical_stdmet_with(&self, station: &str, year: i32, opts: &FetchOptions) -> Result<DataFrame> {
+        let text = opts.guard(self.year_text(station, year)).await?;
+
+        opts.check()?;
+        let df = self.parse_archive(&text)?;
+        self.with_station_columns(station, df)
+    }
+
+    /// Fetch the yearly historical standard met archive for a station, parse, and save it as
+    /// `<station>_<year>` in the configured format.
+    ///
+    /// Returns [`NdbcError::HistoricalNotFound`] when NDBC has no archive for that station and year.
+    pub async fn fetch_historical_year(&self, station: &str, year: i32) -> Result<()> {
+        self.fetch_historical_year_with(station, year, &FetchOptions::default()).await
+    }
+
+    /// [`fetch_historical_year`](Self::fetch_historical_year) bounded by `opts`.
+    pub async fn fetch_historical_year_with(&self, station: &str, year: i32, opts: &FetchOptions) -> Result<()> {
+        let text = opts.guard(self.year_text(station, year)).await?;
+
+        opts.check()?;
+        let stem = format!("{}_{}", station, year);
+        let df = self.parse_archive_for_save(station, &text, &stem)?;
+
+        opts.check()?;
+        self.save_station_frame(station, df, &stem, &year_url(&self.base_url, station, year)).map(|_| ())
+    }
+
+    /// Fetch a monthly standard met file for a station, parse, and save it as
+    /// `<station>_<year>_<MM>` in the configured format.
+    ///
+    /// Months of the current year that are no longer in the realtime window are published under
+    /// `data/stdmet/<Mon>/` as gzipped `<station><m><year>.txt.gz` files, where `<m>` is the
+    /// month code (`1`-`9`, `a`-`c`); the most recent month is a plain `<station>.txt`. The
+    /// output has the same schema as the yearly and realtime files.
+    ///
+    /// Returns [`NdbcError::HistoricalMonthNotFound`] when neither file exists.
+    pub async fn fetch_historical_month(&self, station: &str, year: i32, month: Month) -> Result<()> {
+        self.fetch_historical_month_with(station, year, month, &FetchOptions::default()).await
+    }
+
+    /// [`fetch_historical_month`](Self::fetch_historical_month) bounded by `opts`.
+    pub async fn fetch_historical_month_with(
+        &self,
+        station: &str,
+        year: i32,
+        month: Month,
+        opts: &FetchOptions,
+    ) -> Result<()> {
+        let (text, url) = opts.guard(self.month_text(station, year, month)).await?;
+
+        opts.check()?;
+        let stem = format!("{}_{}_{:02}", station, year, month as u8);
+        let df = self.parse_archive_for_save(station, &text, &stem)?;
+
+        opts.check()?;
+        self.save_station_frame(station, df, &stem, &url).map(|_| ())
+    }
+
+    /// Fetch every observation for a station between `start` and `end` (inclusive, UTC dates)
+    /// without writing anything to disk.
+    ///
+    /// Yearly archives, current-year monthly files and the realtime file are combined as needed,
+    /// limited to the range, deduplicated on `time_ms` (archived values win over realtime ones)
+    /// and sorted ascending. Pieces that are unavailable are logged as warnings and skipped;
+    /// an error is returned only if nothing at all could be fetched.
+    pub async fn fetch_range(&self, station: &str, start: Date, end: Date) -> Result<DataFrame> {
+        self.fetch_range_with(station, start, end, &FetchOptions::default()).await
+    }
+
+    /// [`fetch_range`](Self::fetch_range) bounded by `opts`.
+    pub async fn fetch_range_with(
+        &self,
+        station: &str,
+        start: Date,
+        end: Date,
+        opts: &FetchOptions,
+    ) -> Result<DataFrame> {
+        let mut texts: Vec<(RangePiece, Result<String>)> = Vec::new();
+        for piece in range_pieces(start, end) {
+            let text = opts.guard(self.range_piece_text(station, piece)).await;
+            texts.push((piece, text));
+        }
+
+        let mut frames: Vec<DataFrame> = Vec::new();
+        let mut first_err: Option<NdbcError> = None;
+        for (piece, text) in texts {
+            match text.and_then(|t| self.parse_archive(&t)) {
+                Ok(df) => frames.push(df),
+                Err(e @ (NdbcError::Cancelled | NdbcError::DeadlineExceeded)) => return Err(e),
+                Err(e) => {
+                    warn!(station = %station, piece = %piece, error = %e, "range piece unavailable; skipping");
+                    first_err.get_or_insert(e);
+                }
+            }
+        }
+        opts.check()?;
+        let Some(mut df) = frames.pop() else {
+            return Err(first_err.unwrap_or(NdbcError::NoMetRows));
+        };
+        // Archived pieces come first so they win the dedupe over realtime rows.
+        for other in frames.iter().rev() {
+            df = other.vstack(&df)?;
+        }
+
+        let (start_ms, end_ms) = range_bounds_ms(start, end);
+        let df = df
+            .lazy()
+            .filter(col("time_ms").gt_eq(lit(start_ms)).and(col("time_ms").lt(lit(end_ms))))
+            .unique_stable(Some(vec!["time_ms".into()]), UniqueKeepStrategy::First)
+            .sort(["time_ms"], Default::default())
+            .collect()?;
+        self.with_station_columns(station, df)
+    }
+
+    /// Download one piece of a date range.
+    async fn range_piece_text(&self, station: &str, piece: RangePiece) -> Result<String> {
+        match piece {
+            RangePiece::Year(year) => self.year_text(station, year).await,
+            RangePiece::Month(year, month) => self.month_text(station, year, month).await.map(|(text, _)| text),
+            RangePiece::Realtime => self.realtime_text(station, Window::FortyFiveDay).await,
+        }
+    }
+
+    /// Parse a data file, rejecting empty files, files without standard met rows and, in strict
+    /// mode, files with more anomalies than the [`StrictLimits`] allow.
+    fn parse_archive(&self, text: &str) -> Result<DataFrame> {
+        self.parse_archive_with_stats(text).map(|(df, _)| df)
+    }
+
+    /// [`parse_archive`](Self::parse_archive) for a file about to be saved as `stem`, writing
+    /// its parse issues sidecar when enabled.
+    fn parse_archive_for_save(&self, station: &str, text: &str, stem: &str) -> Result<DataFrame> {
+        let (df, stats) = self.parse_archive_with_stats(text)?;
+        if self.parse_issues {
+            self.save_parse_issues(station, stem, &stats)?;
+        }
+        Ok(df)
+    }
+
+    /// [`parse_archive`](Self::parse_archive), also returning the anomalies it tolerated.
+    fn parse_archive_with_stats(&self, text: &str) -> Result<(DataFrame, ParseStats)> {
+        if text.trim().is_empty() {
+            return Err(NdbcError::EmptyData);
+        }
+        // Derived variables need their inputs even when those are not selected for output.
+        let columns: Option<Vec<&str>> = self.columns.as_ref().map(|selected| {
+            let mut inputs: Vec<&str> = Vec::new();
+            if self.derive {
+                inputs.extend(derive::INPUT_COLUMNS);
+            }
+            if self.station_pressure {
+                inputs.extend(derive::STATION_PRESSURE_INPUT_COLUMNS);
+            }
+            selected.iter().map(String::as_str).chain(inputs.into_iter().filter(|c| !selected.iter().any(|s| s == c))).collect()
+        });
+        let (df, stats) = parse_std_met_delimited(text, columns.as_deref(), self.delimiter)?;
+        if df.height() == 0 {
+            return Err(NdbcError::NoMetRows);
+        }
+        match &self.strict {
+            Some(limits) => limits.check(&stats)?,
+            None if !stats.is_clean() => warn!(
+                malformed_lines = stats.malformed_lines,
+                token_mismatches = stats.token_mismatches,
+                duplicate_times = stats.duplicate_times,
+                "parse anomalies"
+            ),
+            None => {}
+        }
+        Ok((dedupe_times(df, self.duplicate_times)?, stats))
+    }
+
+    /// Download the realtime standard met file for a station.
+    async fn realtime_text(&self, station: &str, window: Window) -> Result<String> {
+        let url = window.url(&self.base_url, station);
+        info!(station = %station, %url, "downloading realtime data");
+        let resp = http::get(&self.client, &url, &self.retry, &self.headers).await?;
+        if resp.status() == StatusCode::NOT_FOUND {
+            return Err(NdbcError::StationNotFound(station.to_string()));
+        }
+        decode_text(&resp.error_for_status()?.bytes().await?)
+    }
+
+    /// Download and decompress the yearly archive for a station.
+    async fn year_text(&self, station: &str, year: i32) -> Result<String> {
+        let url = year_url(&self.base_url, station, year);
+        info!(station = %station, year, %url, "downloading historical data");
+        self.fetch_archive_text(&url)
+            .await?
+            .ok_or_else(|| NdbcError::HistoricalNotFound { station: station.to_string(), year })
+    }
+
+    /// Download a monthly file for a station, trying the gzipped archive before the latest-month
+    /// file. Returns the text and the URL it came from.
+    async fn month_text(&self, station: &str, year: i32, month: Month) -> Result<(String, String)> {
+        let dir = self.ndbc_url(&format!("/data/stdmet/{}", month_abbrev(month)));
+        let gz_url = format!("{}/{}{}{}.txt.gz", dir, station.to_lowercase(), month_code(month), year);
+        info!(station = %station, year, month = %month, url = %gz_url, "downloading monthly data");
+        if let Some(text) = self.fetch_archive_text(&gz_url).await? {
+            return Ok((text, gz_url));
+        }
+        let url = format!("{}/{}.txt", dir, station.to_uppercase());
+        info!(station = %station, %url, "no compressed monthly file; trying latest-month file");
+        let resp = http::get(&self.client, &url, &self.retry, &self.headers).await?;
+        if resp.status() == StatusCode::NOT_FOUND {
+            return Err(NdbcError::HistoricalMonthNotFound { station: station.to_string(), year, month: month as u8 });
+        }
+        Ok((decode_text(&resp.error_for_status()?.bytes().await?)?, url))
+    }
+
+    /// Download and decompress a gzipped archive; `None` when the server answers 404.
+    async fn fetch_archive_text(&self, url: &str) -> Result<Option<String>> {
+        let resp = http::get(&self.client, url, &self.retry, &self.headers).await?;
+        if resp.status() == StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        let bytes = resp.error_for_status()?.bytes().await?;
+        Ok(Some(decode_text(&bytes)?))
+    }
+
+    /// Append station id and coordinates from the loaded metadata to a parsed frame.
+    ///
+    /// Coordinates are matched per row to the deployment active on that row's date (see
+    /// [`station_coords_at`](Self::station_coords_at)), so historical rows carry the position
+    /// the buoy had at the time rather than its current one. Derived columns are added (when
+    /// enabled) and values converted to the configured [`Units`] first; station type and elevation
+    /// follow when enabled.
+    fn with_station_columns(&self, station: &str, df: DataFrame) -> Result<DataFrame> {
+        let df = if self.derive { derive::append_derived(df, self.trend_threshold)? } else { df };
+        let df = if self.station_pressure {
+            derive::append_station_pressure(df, self.station(station).and_then(|s| s.elevation))?
+        } else {
+            df
+        };
+        let df = self.units.convert(df)?;
+        // Add a new column with the station id for every row
+        let station_vals: Vec<String> = std::iter::repeat_n(station.to_string(), df.height()).collect();
+        let station_series = Series::new("station_id".into(), station_vals);
+        let dates: Vec<Option<Date>> = time_millis(&df)?
+            .into_iter()
+            .map(|ms| Some(ms.and_then(|ms| OffsetDateTime::from_unix_timestamp(ms.div_euclid(1000)).ok())?.date()))
+            .collect();
+        // Latitude/Longitude from metadata, if available
+        let coords: Vec<Option<(f64, f64)>> =
+            dates.iter().map(|date| self.station_coords_at(station, (*date)?)).collect();
+        let lat_series: Series = Series::new(
+            "latitude".into(),
+            coords.iter().map(|c| c.map(|(la, _)| la)).collect::<Vec<Option<f64>>>(),
+        );
+        let lon_series: Series = Series::new(
+            "longitude".into(),
+            coords.iter().map(|c| c.map(|(_, lo)| lo)).collect::<Vec<Option<f64>>>(),
+        );
+        let mut df = df.hstack(&[station_series, lat_series, lon_series])?;
+        if self.station_metadata_columns {
+            let meta = self.station(station);
+            let station_type = meta.and_then(|s| s.station_type.clone());
+            let elevation = meta.and_then(|s| s.elevation);
+            df.with_column(Series::new("station_type".into(), vec![station_type; df.height()]))?;
+            df.with_column(Series::new("elevation".into(), vec![elevation; df.height()]))?;
+            let anemometer_height: Vec<Option<f64>> =
+                dates.iter().map(|date| self.station_anemometer_height_at(station, (*date)?)).collect();
+            df.with_column(Series::new("anemometer_height".into(), anemometer_height))?;
+        }
+        // Fixed order and dtypes, whichever columns this station's file happened to fill.
+        align_columns(&df, &self.output_columns())
+    }
+
+    /// Schema of the frames this instance saves and returns: [`std_met_schema`] with default
+    /// settings, otherwise narrowed by [`NdbcDataBuilder::columns`] and extended by the
+    /// station metadata, derived and station pressure options, and ending in [`REVISION_COLUMN`]
+    /// under [`Revision::KeepBoth`] (null in frames not yet merged into a saved file). It does not
+    /// depend on the station or on which columns its file reports.
+    pub fn output_schema(&self) -> Schema {
+        self.output_columns().into_iter().map(|(name, dtype)| Field::new(name.into(), dtype)).collect()
+    }
+
+    /// Columns of the frames this instance saves and returns, in order: `time_ms`, the selected
+    /// met columns (all standard ones by default), `station_id`, `latitude`, `longitude`, then
+    /// the optional station metadata, derived and station pressure columns, and `revision` under
+    /// [`Revision::KeepBoth`].
+    pub(crate) fn output_columns(&self) -> Vec<(&str, DataType)> {
+        let mut columns = match &self.columns {
+            None => standard_columns(),
+            Some(selected) => {
+                let mut columns: Vec<(&str, DataType)> = vec![("time_ms", time_dtype())];
+                columns.extend(selected.iter().map(|name| (name.as_str(), DataType::Float64)));
+                columns.extend([("station_id", DataType::String), ("latitude", DataType::Float64), ("longitude", DataType::Float64)]);
+                columns
+            }
+        };
+        if self.station_metadata_columns {
+            columns.extend([
+                ("station_type", DataType::String),
+                ("elevation", DataType::Float64),
+                ("anemometer_height", DataType::Float64),
+            ]);
+        }
+        if self.derive {
+            columns.extend(DERIVED_COLUMNS.map(|name| (name, derive::derived_dtype(name))));
+        }
+        if self.station_pressure {
+            columns.push((STATION_PRESSURE_COLUMN, DataType::Float64));
+        }
+        if self.revision == Some(Revision::KeepBoth) {
+            columns.push((REVISION_COLUMN, DataType::UInt32));
+        }
+        columns
+    }
+
+    /// Append station id and coordinates to a parsed frame and write it as `<stem>.<ext>`, or
+    /// as one file per day with [`PartitionScheme::StationDate`].
+    ///
+    /// With a [`Revision`] policy and Parquet or Arrow IPC output, rows already in a file are
+    /// merged with the new ones rather than replaced. The returned entry describes every file
+    /// written, after merging. Parquet files record `source_url` and the station in their footer
+    /// (see [`Provenance`]).
+    fn save_station_frame(&self, station: &str, df: DataFrame, stem: &str, source_url: &str) -> Result<IndexEntry> {
+        self.save_station_frame_with(station, df, stem, source_url, self.revision)
+    }
+
+    /// [`save_station_frame`](Self::save_station_frame) with `revision` in place of the
+    /// configured policy.
+    fn save_station_frame_with(
+        &self,
+        station: &str,
+        df: DataFrame,
+        stem: &str,
+        source_url: &str,
+        revision: Option<Revision>,
+    ) -> Result<IndexEntry> {
+        let df = self.with_station_columns(station, df)?;
+        #[cfg(feature = "sqlite")]
+        if self.format == OutputFormat::Sqlite {
+            let entry = self.index_entry(station, &df, PathBuf::new())?;
+            let path = self.save_to_sqlite(std::slice::from_ref(&df))?;
+            return Ok(IndexEntry { output_path: path, ..entry });
+        }
+        if revision.is_some() && !self.format.supports_merge() {
+            return Err(NdbcError::MergeUnsupported(self.format));
+        }
+        let mut entry = self.index_entry(station, &df, PathBuf::new())?;
+        let parts: Vec<(String, DataFrame)> = match self.partition {
+            PartitionScheme::Flat => vec![(stem.to_string(), df)],
+            PartitionScheme::StationDate => partition::split_by_date(&df)?
+                .into_iter()
+                .map(|(date, part)| (partition::partition_stem(station, date), part))
+                .collect(),
+        };
+        if self.partition == PartitionScheme::StationDate {
+            entry = IndexEntry { row_count: 0, first_time_ms: None, last_time_ms: None, ..entry };
+            if let Some(out_dir) = &self.out_dir {
+                entry.output_path = out_dir.join(partition::station_dir(station));
+            }
+        }
+        for (stem, mut df) in parts {
+            if let (Some(policy), OutputFormat::Parquet | OutputFormat::ArrowIpc, Some(out_dir)) = (revision, self.format, &self.out_dir) {
+                let path = out_dir.join(format!("{}.{}", stem, self.format.extension()));
+                if path.exists() {
+                    // Surface a corrupt or foreign file with its path instead of a bare polars error;
+                    // it is left untouched.
+                    let merged = (|| -> Result<DataFrame> {
+                        let existing = match self.format {
+                            OutputFormat::ArrowIpc => IpcReader::new(fs::File::open(&path)?).finish()?,
+                            _ => coords::read_parquet(&path)?,
+                        };
+                        // Files written before `time_ms` became a Datetime hold it as Int64.
+                        if !matches!(existing.column("time_ms").map(|s| s.dtype()), Ok(DataType::Int64 | DataType::Datetime(..))) {
+                            return Err(NdbcError::Parse("no Int64 or Datetime time_ms column".to_string()));
+                        }
+                        revision::merge(existing, df, policy, &self.output_columns())
+                    })();
+                    df = merged.map_err(|e| NdbcError::ExistingOutput { path: path.clone(), reason: e.to_string() })?;
+                } else {
+                    // A first save under a policy is deduplicated and sorted like later merges.
+                    df = revision::merge(DataFrame::empty(), df, policy, &self.output_columns())?;
+                }
+            }
+            let written = self.index_entry(station, &df, PathBuf::new())?;
+            let (df, mut key_values) = match (self.format, self.coords_as) {
+                (OutputFormat::Parquet, CoordsAs::Metadata) => coords::coords_to_metadata(df)?,
+                _ => (df, Vec::new()),
+            };
+            key_values.extend(self.provenance_key_values(Some(station), Some(source_url)));
+            let path = self.write_frame(df, &stem, key_values)?;
+            match self.partition {
+                PartitionScheme::Flat => entry = IndexEntry { output_path: path, ..written },
+                PartitionScheme::StationDate => {
+                    entry.row_count += written.row_count;
+                    entry.first_time_ms = entry.first_time_ms.into_iter().chain(written.first_time_ms).min();
+                    entry.last_time_ms = entry.last_time_ms.into_iter().chain(written.last_time_ms).max();
+                }
+            }
+        }
+        Ok(entry)
+    }
+
+    /// Write a frame as `<stem>.<ext>` in the configured format, returning its path. Parquet
+    /// files get `key_values` added to their metadata.
+    fn write_frame(&self, df: DataFrame, stem: &str, key_values: Vec<KeyValue>) -> Result<PathBuf> {
+        let out_dir = self.out_dir.as_ref().ok_or(NdbcError::NoOutputDir)?;
+        let out_path = out_dir.join(format!("{}.{}", stem, self.format.extension()));
+        self.write_frame_to(df, &out_path, key_values)?;
+        Ok(out_path)
+    }
+
+    /// Write a frame to `out_path` in the configured format, atomically.
+    fn write_frame_to(&self, mut df: DataFrame, out_path: &Path, key_values: Vec<KeyValue>) -> Result<()> {
+        if let Some(parent) = out_path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            fs::create_dir_all(parent)?;
+        }
+        info!(file = %out_path.display(), rows = df.height(), cols = df.width(), format = ?self.format, "writing output");
+        // Write to a sibling temp file and rename so an interrupted write never leaves a partial output.
+        let tmp_path = out_path.with_extension(format!("{}.part", self.format.extension()));
+        let written = (|| -> Result<()> {
+            self.encode_frame(&mut df, std::fs::File::create(&tmp_path)?, key_values)?;
+            Ok(fs::rename(&tmp_path, out_path)?)
+        })();
+        if written.is_err() {
+            let _ = fs::remove_file(&tmp_path);
+        }
+        written
+    }
+
+    /// Encode a frame in the configured format into `out`. Parquet files get `key_values` added
+    /// to their metadata.
+    fn encode_frame<W: std::io::Write>(&self, df: &mut DataFrame, out: W, key_values: Vec<KeyValue>) -> Result<()> {
+        match self.format {
+            OutputFormat::Parquet => {
+                coords::write_parquet(self.parquet.writer(out)?, df, key_values, self.parquet.row_group_size)?;
+            }
+            OutputFormat::Jsonl => write_jsonl(df, out, self.precision, JSONL_BATCH_ROWS)?,
+            OutputFormat::Csv => {
+                let mut csv_df = to_text_frame(df, self.precision)?;
+                CsvWriter::new(out).include_header(true).finish(&mut csv_df)?;
+            }
+            OutputFormat::ArrowIpc => {
+                IpcWriter::new(out).finish(df)?;
+            }
+            #[cfg(feature = "sqlite")]
+            OutputFormat::Sqlite => {
+                let msg = "SQLite output is a database, not a file stream";
+                return Err(std::io::Error::new(std::io::ErrorKind::Unsupported, msg).into());
+            }
+        }
+        Ok(())
+    }
 }
 
 impl NdbcData {
-    /// Create a new instance and ensure the output directory exists and is gitignored.
-    pub fn new(out_dir: impl Into<PathBuf>) -> Result<Self> {
-        let out_dir = out_dir.into();
-        ensure_data_dir(&out_dir)?;
-        let client = reqwest::Client::builder()
-            .user_agent("ndbc-data-rust/0.1")
-            .build()?;
-        Ok(Self { client, out_dir, station_meta: HashMap::new() })
+    /// Return all station IDs in the loaded metadata that pass the [`MetadataFilter`] (by
+    /// default, stations with met data).
+    pub fn all_station_ids(&self) -> Vec<String> {
+        let mut v: Vec<String> =
+            self.stations.values().filter(|s| self.metadata_filter.includes(s)).map(|s| s.id.clone()).collect();
+        v.sort();
+        v
     }
 
-    /// Download and lightly-validate the station metadata XML.
-    pub async fn fetch_station_metadata(&mut self) -> Result<()> {
-        let url = "https://www.ndbc.noaa.gov/metadata/stationmetadata.xml";
-        info!(%url, "downloading station metadata");
-        let xml = self.client.get(url).send().await?.error_for_status()?.bytes().await?;
-
-        // Parse and populate station -> (lat, lon) for met-enabled stations
-        let mut reader = XmlReader::from_reader(xml.as_ref());
-        reader.trim_text(true);
-        let mut buf = Vec::new();
-        let mut in_stations = false;
-        let mut current_station_id: Option<String> = None;
-        let mut picked_lat_lon: Option<(f64, f64)> = None;
-
-        loop {
-            match reader.read_event_into(&mut buf) {
-                Ok(Event::Start(e)) => {
-                    let name = e.name();
-                    if name.as_ref() == b"stations" {
-                        in_stations = true;
-                    } else if in_stations && name.as_ref() == b"station" {
-                        // Start new station
-                        current_station_id = e
-                            .attributes()
-                            .filter_map(|a| a.ok())
-                            .find(|a| a.key.as_ref() == b"id")
-                            .and_then(|a| String::from_utf8(a.value.to_vec()).ok());
-                        picked_lat_lon = None;
-                    } else if in_stations && name.as_ref() == b"history" {
-                        // Consider this history as a candidate for current position if met="y"
-                        let mut met = None::<String>;
-                        let mut stop = None::<String>;
-                        let mut lat = None::<f64>;
-                        let mut lng = None::<f64>;
-                        for attr in e.attributes().filter_map(|a| a.ok()) {
-                            let key = attr.key;
-                            let val = String::from_utf8_lossy(&attr.value).to_string();
-                            match key.as_ref() {
-                                b"met" => met = Some(val),
-                                b"stop" => stop = Some(val),
-                                b"lat" => lat = val.parse().ok(),
-                                b"lng" => lng = val.parse().ok(),
-                                _ => {}
-                            }
-                        }
-                        let met_yes = met.as_deref() == Some("y");
-                        let is_current = stop.as_deref().map(|s| s.is_empty()).unwrap_or(true);
-                        if met_yes {
-                            if let (Some(la), Some(lo)) = (lat, lng) {
-                                // Prefer the current entry (stop empty). If not set yet, set. If we already set and current is false, keep existing.
-                                if picked_lat_lon.is_none() || is_current {
-                                    picked_lat_lon = Some((la, lo));
-                                }
-                            }
-                        }
-                    }
+    /// Return the IDs of stations passing the [`MetadataFilter`] (by default, stations with met
+    /// data) whose current coordinates fall inside the box (bounds inclusive), sorted.
+    ///
+    /// A box with `min_lon > max_lon` is taken to cross the antimeridian, so
+    /// `(50.0, 170.0, 60.0, -170.0)` covers 170°E through 180° to 170°W.
+    pub fn stations_in_bbox(&self, min_lat: f64, min_lon: f64, max_lat: f64, max_lon: f64) -> Vec<String> {
+        let bbox = [min_lat, min_lon, max_lat, max_lon];
+        let mut v: Vec<String> = self
+            .stations
+            .values()
+            .filter(|s| self.metadata_filter.includes(s))
+            .filter(|s| s.coords().is_some_and(|coords| in_bbox(coords, bbox)))
+            .map(|s| s.id.clone())
+            .collect();
+        v.sort();
+        v
+    }
+
+    /// IDs of stations that report every capability set in `caps`, sorted, e.g.
+    /// `Capabilities { met: true, waves: true, ..Default::default() }` for wave-reporting buoys.
+    pub fn station_ids_with(&self, caps: Capabilities) -> Vec<String> {
+        self.filter_stations(&StationFilter::new().capabilities(caps))
+    }
+
+    /// IDs of stations whose owner/operator contains `owner` (case-insensitive), sorted. Includes
+    /// stations without met data; see [`filter_stations`](Self::filter_stations) to narrow further.
+    pub fn station_ids_by_owner(&self, owner: &str) -> Vec<String> {
+        self.filter_stations(&StationFilter::new().owner(owner))
+    }
+
+    /// IDs of stations whose program (`pgm`) contains `pgm` (case-insensitive), sorted. Includes
+    /// stations without met data; see [`filter_stations`](Self::filter_stations) to narrow further.
+    pub fn station_ids_by_program(&self, pgm: &str) -> Vec<String> {
+        self.filter_stations(&StationFilter::new().program(pgm))
+    }
+
+    /// IDs of the loaded stations matching every criterion of `filter`, sorted, e.g.
+    /// `StationFilter::new().owner("CDIP").met_only().bbox(32.0, -125.0, 49.0, -117.0)`.
+    pub fn filter_stations(&self, filter: &StationFilter) -> Vec<String> {
+        let mut v: Vec<String> = self.stations.values().filter(|s| filter.matches(s)).map(|s| s.id.clone()).collect();
+        v.sort();
+        v
+    }
+
+    /// [`stations_in_bbox`](Self::stations_in_bbox) with the bounds given as latitude range then
+    /// longitude range. Returns a sorted list without duplicates, ready for the per-station fetch
+    /// loop; `min_lon > max_lon` crosses the antimeridian.
+    pub fn station_ids_in_bbox(&self, min_lat: f64, max_lat: f64, min_lon: f64, max_lon: f64) -> Vec<String> {
+        self.stations_in_bbox(min_lat, min_lon, max_lat, max_lon)
+    }
+
+    /// The `n` stations closest to `(lat, lon)` (met stations unless the [`MetadataFilter`] says
+    /// otherwise) with their great-circle (haversine) distance in kilometers, nearest first.
+    /// Stations without known coordinates are skipped.
+    pub fn nearest_stations(&self, lat: f64, lon: f64, n: usize) -> Vec<(String, f64)> {
+        let mut v = self.station_distances(lat, lon);
+        v.truncate(n);
+        v
+    }
+
+    /// Stations within `radius_km` of `(lat, lon)` (met stations unless the [`MetadataFilter`]
+    /// says otherwise) with their great-circle distance in kilometers, nearest first. Haversine
+    /// distances wrap around ±180° longitude, so a point at 179.9°E finds stations at 179.9°W.
+    pub fn stations_within_km(&self, lat: f64, lon: f64, radius_km: f64) -> Vec<(String, f64)> {
+        let mut v = self.station_distances(lat, lon);
+        v.retain(|(_, km)| *km <= radius_km);
+        v
+    }
+
+    /// Every station passing the [`MetadataFilter`] with known coordinates and its distance from
+    /// `(lat, lon)`, nearest first (ties by id).
+    fn station_distances(&self, lat: f64, lon: f64) -> Vec<(String, f64)> {
+        let mut v: Vec<(String, f64)> = self
+            .stations
+            .values()
+            .filter(|s| self.metadata_filter.includes(s))
+            .filter_map(|s| Some((s.id.clone(), haversine_km((lat, lon), s.coords()?))))
+            .collect();
+        v.sort_by(|a, b| a.1.total_cmp(&b.1).then_with(|| a.0.cmp(&b.0)));
+        v
+    }
+
+    /// Coordinates of a station on a given date, from the deployment history entry active then.
+    ///
+    /// Entries reporting met data are preferred when several cover the date. Falls back to the
+    /// station's current met coordinates when no entry covers the date.
+    pub fn station_coords_at(&self, id: &str, date: Date) -> Option<(f64, f64)> {
+        let covering = |e: &&HistoryEntry| {
+            e.start.is_none_or(|start| start <= date) && e.stop.is_none_or(|stop| date < stop)
+        };
+        self.stations
+            .get(id)
+            .map(|s| &s.history)
+            .and_then(|entries| {
+                let mut active = entries.iter().filter(covering).filter(|e| e.lat.is_some() && e.lng.is_some());
+                let first = active.clone().next();
+                active.find(|e| e.met).or(first)
+            })
+            .and_then(|e| Some((e.lat?, e.lng?)))
+            .or_else(|| self.stations.get(id).filter(|s| s.met).and_then(Station::coords))
+    }
+
+    /// Anemometer height in meters above the site for `id` on `date`, from the deployment
+    /// covering that date (the met-reporting one when several do), or the station's current
+    /// height when no dated deployment gives one. Wind speeds from different heights can be
+    /// compared after a log- or power-law correction to a common reference (usually 10 m).
+    pub fn station_anemometer_height_at(&self, id: &str, date: Date) -> Option<f64> {
+        let covering = |e: &&HistoryEntry| {
+            e.start.is_none_or(|start| start <= date) && e.stop.is_none_or(|stop| date < stop)
+        };
+        let station = self.stations.get(id)?;
+        let mut active = station.history.iter().filter(covering).filter(|e| e.anemometer_height.is_some());
+        let first = active.clone().next();
+        active.find(|e| e.met).or(first).and_then(|e| e.anemometer_height).or(station.anemometer_height)
+    }
+
+    /// Return the full deployment history for a station from the loaded metadata, in file order
+    /// (NDBC lists the most recent deployment first). Empty if the station is unknown.
+    pub fn station_history(&self, id: &str) -> Vec<HistoryEntry> {
+        self.stations.get(id).map(|s| s.history.clone()).unwrap_or_default()
+    }
+
+    /// Every deployment of a station from the `<history>` elements of `stationmetadata.xml`, most
+    /// recent first: start and stop dates (`stop` is `None` for the current one), position and
+    /// capability flags. Saved and returned rows already take their `latitude`/`longitude` from
+    /// the deployment covering each row's date. Empty when the station is unknown or the metadata
+    /// came from a source without history.
+    pub fn deployments(&self, station: &str) -> Vec<Deployment> {
+        self.station_history(station)
+    }
+
+    /// Metadata for a station, if it is listed in the loaded metadata.
+    pub fn station(&self, id: &str) -> Option<&Station> {
+        self.stations.get(id)
+    }
+
+    /// Current `(latitude, longitude)` of a station from the loaded metadata.
+    pub fn station_location(&self, station: &str) -> Option<(f64, f64)> {
+        self.locations.get(station).copied()
+    }
+
+    /// Current `(latitude, longitude)` of every station in the loaded metadata that has both.
+    /// This is a view of the [`Station`] records for distance and plotting code; use
+    /// [`station`](Self::station) or [`stations`](Self::stations) for name, owner and the rest.
+    pub fn station_locations(&self) -> &HashMap<String, (f64, f64)> {
+        &self.locations
+    }
+
+    /// The loaded stations as a GeoJSON FeatureCollection of points (`[lon, lat]`), sorted by id,
+    /// with name, owner, program, type, elevation, hull and capability flags as properties.
+    /// Stations without coordinates are left out.
+    pub fn metadata_geojson(&self) -> serde_json::Value {
+        stations_geojson(self.stations.values())
+    }
+
+    /// Write the current coordinates and name of each of `ids` (in the order given, matched
+    /// case-insensitively) from the loaded metadata to `out`, returning the ids it does not
+    /// list. In [`CoordsFormat::Table`] those get a `<id>  not found` line; CSV and JSON leave
+    /// them out so the output stays machine-readable. Unknown coordinates are empty (`null` in
+    /// JSON).
+    pub fn write_station_coords(&self, ids: &[String], format: CoordsFormat, mut out: impl std::io::Write) -> Result<Vec<String>> {
+        let mut missing = Vec::new();
+        let mut found = Vec::new();
+        let coord = |v: Option<f64>| v.map_or("-".to_string(), |v| format!("{:.3}", v));
+        for id in ids {
+            let station = self.station(id).or_else(|| self.station(&id.to_uppercase()));
+            match (station, format) {
+                (Some(s), CoordsFormat::Table) => {
+                    writeln!(out, "{:<8} {:>8} {:>9}  {}", s.id, coord(s.lat), coord(s.lon), s.name.as_deref().unwrap_or(""))?
                 }
-                Ok(Event::End(e)) => {
-                    if e.name().as_ref() == b"station" {
-                        if let (Some(id), Some(ll)) = (current_station_id.take(), picked_lat_lon.take()) {
-                            self.station_meta.insert(id, ll);
-                        }
-                    } else if e.name().as_ref() == b"stations" {
-                        // finished
-                        break;
-                    }
+                (Some(s), _) => found.push(s),
+                (None, CoordsFormat::Table) => writeln!(out, "{:<8} not found", id)?,
+                (None, _) => {}
+            }
+            if station.is_none() {
+                missing.push(id.clone());
+            }
+        }
+        match format {
+            CoordsFormat::Table => {}
+            CoordsFormat::Csv => {
+                writeln!(out, "id,lat,lon,name")?;
+                for s in found {
+                    let number = |v: Option<f64>| v.map(|v| v.to_string()).unwrap_or_default();
+                    let name = export::csv_field(s.name.as_deref().unwrap_or(""));
+                    writeln!(out, "{},{},{},{}", export::csv_field(&s.id), number(s.lat), number(s.lon), name)?;
                 }
-                Ok(Event::Eof) => break,
-                Err(e) => return Err(anyhow!("station metadata parse error: {}", e)),
-                _ => {}
+            }
+            CoordsFormat::Json => {
+                let rows: Vec<serde_json::Value> = found
+                    .into_iter()
+                    .map(|s| serde_json::json!({ "id": s.id, "lat": s.lat, "lon": s.lon, "name": s.name }))
+                    .collect();
+                let json = serde_json::to_string_pretty(&rows).map_err(|e| NdbcError::Parse(format!("station coordinates: {}", e)))?;
+                writeln!(out, "{}", json)?;
             }
         }
+        Ok(missing)
+    }
 
-        if self.station_meta.is_empty() {
-            return Err(anyhow!("no stations with met data found in metadata"));
+    /// [`metadata_geojson`](Self::metadata_geojson) serialized as a compact GeoJSON string.
+    pub fn stations_geojson(&self) -> Result<String> {
+        serde_json::to_string(&self.metadata_geojson()).map_err(|e| NdbcError::Parse(format!("station GeoJSON: {}", e)))
+    }
+
+    /// Write [`stations_geojson`](Self::stations_geojson) to `stations.geojson` in the output
+    /// directory, replacing it atomically.
+    pub fn save_stations_geojson(&self) -> Result<()> {
+        let out_dir = self.out_dir.as_ref().ok_or(NdbcError::NoOutputDir)?;
+        let json = self.stations_geojson()?;
+        let out_path = out_dir.join(format!("{}.geojson", STATIONS_STEM));
+        let tmp_path = out_path.with_extension("geojson.part");
+        info!(file = %out_path.display(), "writing station GeoJSON");
+        let written = fs::write(&tmp_path, json).and_then(|_| fs::rename(&tmp_path, &out_path));
+        if written.is_err() {
+            let _ = fs::remove_file(&tmp_path);
         }
-        info!(count = self.station_meta.len(), "station metadata retrieved");
-        Ok(())
+        Ok(written?)
     }
 
-    /// Fetch realtime data for a station, parse, and save as Parquet into the configured output directory.
-    pub async fn fetch_and_save_station(&self, station: &str) -> Result<()> {
-        let url = format!("https://www.ndbc.noaa.gov/data/realtime2/{}.txt", station);
-        info!(station = %station, %url, "downloading realtime data");
-        let resp = self.client.get(&url).send().await?;
-        if resp.status() == StatusCode::NOT_FOUND {
-            return Err(anyhow!("data unavailable (404)"));
+    /// The loaded stations as a DataFrame, one row per station sorted by id: `station_id`, `name`,
+    /// `owner`, `program`, `station_type`, `latitude`, `longitude`, `elevation`,
+    /// `anemometer_height`, `hull`, `payload`, `time_zone` and the `met`/`currents`/`water_quality`/`dart` flags.
+    /// Fields a station lacks are null. Join it to observations on `station_id`.
+    pub fn station_metadata_df(&self) -> Result<DataFrame> {
+        stations_frame(self.stations.values())
+    }
+
+    /// Write [`station_metadata_df`](Self::station_metadata_df) to `stations.parquet` in the
+    /// output directory, replacing it atomically.
+    pub fn save_station_metadata(&self) -> Result<()> {
+        let out_dir = self.out_dir.as_ref().ok_or(NdbcError::NoOutputDir)?;
+        let mut df = self.station_metadata_df()?;
+        let out_path = out_dir.join(format!("{}.parquet", STATIONS_STEM));
+        let tmp_path = out_path.with_extension("parquet.part");
+        info!(file = %out_path.display(), rows = df.height(), "writing station metadata");
+        let written = (|| -> Result<()> {
+            let key_values = self.provenance_key_values(None, None);
+            coords::write_parquet(self.parquet.writer(fs::File::create(&tmp_path)?)?, &mut df, key_values, self.parquet.row_group_size)?;
+            Ok(fs::rename(&tmp_path, &out_path)?)
+        })();
+        if written.is_err() {
+            let _ = fs::remove_file(&tmp_path);
         }
-        let text = resp.error_for_status()?.text().await?;
-        if text.trim().is_empty() {
-            return Err(anyhow!("empty data"));
+        written
+    }
+
+    /// Write the loaded stations to `out` in `format`: pretty-printed
+    /// [`metadata_geojson`](Self::metadata_geojson), or
+    /// [`station_metadata_df`](Self::station_metadata_df) as CSV or Parquet.
+    pub fn write_station_metadata(&self, format: MetadataFormat, mut out: impl std::io::Write) -> Result<()> {
+        match format {
+            MetadataFormat::Geojson => {
+                let json = serde_json::to_string_pretty(&self.metadata_geojson())
+                    .map_err(|e| NdbcError::Parse(format!("station GeoJSON: {}", e)))?;
+                writeln!(out, "{}", json)?;
+            }
+            MetadataFormat::Csv => {
+                CsvWriter::new(out).include_header(true).finish(&mut self.station_metadata_df()?)?;
+            }
+            MetadataFormat::Parquet => {
+                let key_values = self.provenance_key_values(None, None);
+                coords::write_parquet(self.parquet.writer(out)?, &mut self.station_metadata_df()?, key_values, self.parquet.row_group_size)?;
+            }
         }
+        Ok(())
+    }
 
-        let mut df = parse_std_met_to_df(&text)
-            .with_context(|| format!("parsing standard met data for {}", station))?;
+    /// All stations in the loaded metadata, in no particular order.
+    pub fn stations(&self) -> impl Iterator<Item = &Station> {
+        self.stations.values()
+    }
+}
 
-        if df.height() == 0 {
-            return Err(anyhow!("no standard met rows found"));
-        }
+/// Server files are downloaded from unless [`NdbcDataBuilder::base_url`] picks another.
+pub const NDBC_BASE_URL: &str = "https://www.ndbc.noaa.gov";
 
-        // Add a new column with the station id for every row
-        let station_vals: Vec<String> = std::iter::repeat(station.to_string()).take(df.height()).collect();
-        let station_series = Series::new("station_id".into(), station_vals);
-        // Latitude/Longitude from metadata, if available
-        let (lat_opt, lon_opt) = self
-            .station_meta
-            .get(station)
-            .cloned()
-            .map(|(la, lo)| (Some(la), Some(lo)))
-            .unwrap_or((None, None));
-        let lat_series: Series = Series::new(
-            "latitude".into(),
-            std::iter::repeat(lat_opt).take(df.height()).collect::<Vec<Option<f64>>>(),
-        );
-        let lon_series: Series = Series::new(
-            "longitude".into(),
-            std::iter::repeat(lon_opt).take(df.height()).collect::<Vec<Option<f64>>>(),
-        );
-        df = df.hstack(&[station_series, lat_series, lon_series])?;
+/// Full station metadata with deployment history.
+const STATION_METADATA_PATH: &str = "/metadata/stationmetadata.xml";
 
-        let out_path = self.out_dir.join(format!("{}.parquet", station));
-        info!(file = %out_path.display(), rows = df.height(), cols = df.width(), "writing parquet");
-        let file = std::fs::File::create(&out_path)?;
-        ParquetWriter::new(file).finish(&mut df)?;
-        Ok(())
+/// Compact list of currently active stations.
+const ACTIVE_STATIONS_PATH: &str = "/activestations.xml";
+
+/// File stem of the single file written by [`NdbcData::save_combined`].
+const COMBINED_STEM: &str = "combined";
+
+/// File stem of the station table written by [`NdbcData::save_station_metadata`].
+const STATIONS_STEM: &str = "stations";
+
+/// Days of history covered by the realtime standard met file.
+const REALTIME_DAYS: i64 = 45;
+
+/// Default per-request HTTP timeout.
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Dtype of `time_ms` in saved and returned frames: UTC timestamps in milliseconds. The parser
+/// produces plain Int64 milliseconds, which [`align_columns`] casts to this.
+pub(crate) fn time_dtype() -> DataType {
+    DataType::Datetime(TimeUnit::Milliseconds, Some("UTC".into()))
+}
+
+/// `time_ms` of `df` as milliseconds since the Unix epoch, from the parser's Int64 column or the
+/// Datetime one of saved and returned frames.
+pub(crate) fn time_millis(df: &DataFrame) -> Result<Int64Chunked> {
+    Ok(df.column("time_ms")?.cast(&DataType::Int64)?.i64()?.clone())
+}
+
+/// Standard output columns: `time_ms`, the standard met columns, `station_id`, `latitude` and
+/// `longitude`.
+pub(crate) fn standard_columns() -> Vec<(&'static str, DataType)> {
+    let mut columns: Vec<(&str, DataType)> = vec![("time_ms", time_dtype())];
+    columns.extend(STD_MET_COLUMNS.iter().map(|(name, _)| (*name, DataType::Float64)));
+    columns.extend([("station_id", DataType::String), ("latitude", DataType::Float64), ("longitude", DataType::Float64)]);
+    columns
+}
+
+/// Reorder and cast a station frame to `columns` (usually [`standard_columns`] or
+/// [`NdbcData::output_columns`]), dropping any others and adding all-null columns for any that
+/// are missing.
+pub(crate) fn align_columns(df: &DataFrame, columns: &[(&str, DataType)]) -> Result<DataFrame> {
+    let series = columns
+        .iter()
+        .map(|(name, dtype)| match df.column(name) {
+            Ok(s) => s
+                .cast(dtype)
+                .map_err(|e| NdbcError::Parse(format!("column {} ({}) cannot be read as {}: {}", name, s.dtype(), dtype, e))),
+            Err(_) => Ok(Series::full_null((*name).into(), df.height(), dtype)),
+        })
+        .collect::<Result<Vec<Series>>>()?;
+    Ok(DataFrame::new(series)?)
+}
+
+/// URL of the yearly historical standard met archive for a station on the server at `base_url`.
+fn year_url(base_url: &str, station: &str, year: i32) -> String {
+    format!("{}/data/historical/stdmet/{}h{}.txt.gz", base_url, station.to_lowercase(), year)
+}
+
+/// Three-letter month directory name used by NDBC (`Jan`, `Feb`, ...).
+fn month_abbrev(month: Month) -> &'static str {
+    match month {
+        Month::January => "Jan",
+        Month::February => "Feb",
+        Month::March => "Mar",
+        Month::April => "Apr",
+        Month::May => "May",
+        Month::June => "Jun",
+        Month::July => "Jul",
+        Month::August => "Aug",
+        Month::September => "Sep",
+        Month::October => "Oct",
+        Month::November => "Nov",
+        Month::December => "Dec",
     }
 }
 
-impl NdbcData {
-    /// Return all station IDs that have met data in the loaded metadata.
-    pub fn all_station_ids(&self) -> Vec<String> {
-        let mut v: Vec<String> = self.station_meta.keys().cloned().collect();
-        v.sort();
-        v
+/// Single-character month code in monthly file names: `1`-`9` for January-September,
+/// `a`-`c` for October-December.
+fn month_code(month: Month) -> char {
+    std::char::from_digit(month as u32, 13).unwrap_or('1')
+}
+
+/// Decode a downloaded or local data file to text, decompressing it first if it is gzipped.
+///
+/// Compression is detected from the gzip magic bytes rather than the file extension or
+/// `Content-Encoding`, so mislabelled content is handled either way: a `.gz` URL whose body was
+/// already inflated in transit is used as is. Concatenated gzip members are all read. Invalid
+/// UTF-8 is replaced rather than rejected.
+pub fn decode_text(bytes: &[u8]) -> Result<String> {
+    if bytes.starts_with(&[0x1f, 0x8b]) {
+        let mut raw = Vec::new();
+        MultiGzDecoder::new(bytes).read_to_end(&mut raw)?;
+        return Ok(String::from_utf8_lossy(&raw).into_owned());
     }
+    Ok(String::from_utf8_lossy(bytes).into_owned())
 }
 
 fn ensure_data_dir(dir: &Path) -> Result<()> {
+    let context = |context: String| move |source| NdbcError::IoContext { context, source };
     if !dir.exists() {
-        fs::create_dir_all(dir).with_context(|| format!("creating data dir {}", dir.display()))?;
+        fs::create_dir_all(dir).map_err(context(format!("creating data dir {}", dir.display())))?;
+    }
+    // An absolute directory is not inside the working tree, so there is nothing to ignore.
+    if dir.is_absolute() {
+        return Ok(());
     }
     // Ensure .gitignore has /data (or the provided dir name)
     let gi = Path::new(".gitignore");
@@ -183,61 +1841,237 @@ fn ensure_data_dir(dir: &Path) -> Result<()> {
                 new_txt.push('\n');
             }
             new_txt.push_str(&rule);
-            fs::write(gi, new_txt).context("updating .gitignore")?;
+            fs::write(gi, new_txt).map_err(context("updating .gitignore".to_string()))?;
         }
     } else {
-        fs::write(gi, rule).context("creating .gitignore")?;
+        fs::write(gi, rule).map_err(context("creating .gitignore".to_string()))?;
     }
     Ok(())
 }
 
-fn parse_std_met_to_df(text: &str) -> Result<DataFrame> {
-    // Identify standard met header (first group of two comment lines starting with #YY and #yr)
-    let mut lines = text.lines().peekable();
-    let mut header_cols: Vec<String> = Vec::new();
+/// Reshape a station frame for JSON Lines or CSV output: lowercase column names and an ISO 8601
+/// `time` string in place of `time_ms`. Float columns are rounded to `precision` decimals when
+/// given.
+pub(crate) fn to_text_frame(df: &DataFrame, precision: Option<u32>) -> Result<DataFrame> {
+    let times: Vec<Option<String>> = time_millis(df)?
+        .into_iter()
+        .map(|ms| {
+            ms.and_then(|ms| OffsetDateTime::from_unix_timestamp_nanos(ms as i128 * 1_000_000).ok())
+                .and_then(|dt| dt.format(&Rfc3339).ok())
+        })
+        .collect();
+    let mut series: Vec<Series> = vec![Series::new("time".into(), times)];
+    for s in df.get_columns() {
+        if s.name().as_str() == "time_ms" {
+            continue;
+        }
+        let mut s = match precision {
+            Some(decimals) if s.dtype() == &DataType::Float64 => round_series(s, decimals)?,
+            _ => s.clone(),
+        };
+        s.rename(s.name().to_lowercase().into());
+        series.push(s);
+    }
+    Ok(DataFrame::new(series)?)
+}
+
+/// Rows converted and serialized at a time by [`write_jsonl`].
+const JSONL_BATCH_ROWS: usize = 10_000;
+
+/// Write a frame as JSON Lines, converting and serializing `batch_rows` rows at a time so the
+/// text copy of a long frame (see [`to_text_frame`]) is never held whole.
+fn write_jsonl<W: std::io::Write>(df: &DataFrame, mut out: W, precision: Option<u32>, batch_rows: usize) -> Result<()> {
+    let mut offset = 0;
+    while offset < df.height() {
+        let mut batch = to_text_frame(&df.slice(offset as i64, batch_rows), precision)?;
+        JsonWriter::new(&mut out).with_json_format(JsonFormat::JsonLines).finish(&mut batch)?;
+        offset += batch_rows;
+    }
+    Ok(())
+}
 
+/// Round a float column to `decimals` places for text output; nulls stay null.
+fn round_series(s: &Series, decimals: u32) -> Result<Series> {
+    let factor = 10f64.powi(decimals as i32);
+    Ok(s.f64()?.apply_values(|v| (v * factor).round() / factor).into_series())
+}
+
+/// Names and units header of a standard met file.
+struct StdMetHeader {
+    /// Column names as printed, without a leading `#` (`YY`, `MM`, ..., `TIDE`).
+    cols: Vec<String>,
+    /// Character position of the middle of each name on the header line.
+    centers: Vec<f64>,
+    /// Units line tokens (`yr`, `mo`, ..., `degT`); empty when the file has no units line.
+    units: Vec<String>,
+}
+
+/// Advance `lines` past the standard met header (the `#YY MM DD ...` names line and the `#yr mo
+/// dy ...` units line after it) and return it, or `None` when there is none. Warns when the units
+/// line does not have one token per column.
+fn find_std_met_header(lines: &mut std::iter::Peekable<std::str::Lines<'_>>, delimiter: Delimiter) -> Option<StdMetHeader> {
     while let Some(line) = lines.next() {
-        let l = line.trim_start();
-        if l.starts_with('#') {
-            let header = l.trim_start_matches('#').trim_start();
-            // We expect this to be the names header; verify it contains WDIR and WSPD at least.
-            let tokens: Vec<&str> = header.split_whitespace().collect();
-            if tokens.len() >= 5 && tokens[0].ends_with("YY") && tokens[1] == "MM" && tokens[2] == "DD" {
-                // Consume the next units line if present
-                if let Some(next) = lines.peek() {
-                    if next.trim_start().starts_with('#') {
-                        let _ = lines.next();
-                    }
+        // Older historical archives print the names header without the leading '#'.
+        let header = line.trim_start().trim_start_matches('#').trim_start();
+        let tokens: Vec<&str> = delimiter.split(header);
+        if tokens.len() >= 5 && tokens[0].ends_with("YY") && tokens[1] == "MM" && tokens[2] == "DD" {
+            let mut units = Vec::new();
+            if let Some(next) = lines.next_if(|next| next.trim_start().starts_with('#')) {
+                units = delimiter.split(next.trim_start().trim_start_matches('#')).into_iter().map(String::from).collect();
+                if units.len() != tokens.len() {
+                    warn!(columns = tokens.len(), units = units.len(), "units line does not match the column names line");
                 }
-                header_cols = tokens.into_iter().map(|s| s.to_string()).collect();
-                break;
             }
+            // Character positions of the names, skipping a lone '#' before the first one. Only
+            // aligned (whitespace-separated) files have meaningful positions.
+            let centers = match delimiter {
+                Delimiter::Whitespace => {
+                    let spans = token_spans(line);
+                    spans[spans.len() - tokens.len()..].iter().map(|&(a, b)| (a + b) as f64 / 2.0).collect()
+                }
+                Delimiter::Comma => Vec::new(),
+            };
+            return Some(StdMetHeader { cols: tokens.into_iter().map(String::from).collect(), centers, units });
         }
     }
+    None
+}
+
+/// Observation time of a data row split into `toks`, with the minute and second columns at
+/// `minute_idx`/`second_idx` (0 when the file has none). The year may be 4-digit or two-digit
+/// (archives before 1999). `None` when the fields do not form a valid time.
+fn row_time(toks: &[&str], minute_idx: Option<usize>, second_idx: Option<usize>) -> Option<OffsetDateTime> {
+    let field = |i: Option<usize>| -> Option<u8> {
+        match i {
+            Some(i) => toks.get(i)?.parse().ok(),
+            None => Some(0),
+        }
+    };
+    let year = match toks.first()?.parse::<i32>().ok()? {
+        y if y >= 1000 => y,
+        y @ 50..=99 => 1900 + y,
+        y @ 0..=49 => 2000 + y,
+        _ => return None,
+    };
+    let month: u8 = toks.get(1)?.parse().ok()?;
+    let day: u8 = toks.get(2)?.parse().ok()?;
+    let date = Date::from_calendar_date(year, Month::try_from(month).ok()?, day).ok()?;
+    let time = Tm::from_hms(toks.get(3)?.parse().ok()?, field(minute_idx)?, field(second_idx)?).ok()?;
+    Some(date.with_time(time).assume_utc())
+}
+
+/// Oldest and newest observation times in standard met `text`, read from its first and last
+/// data lines only, without building a frame. Realtime files (newest first) and archives
+/// (oldest first) both work. Cheap enough for freshness checks on many files; a file whose
+/// rows are out of order can have times outside this span. `None` when there is no standard
+/// met header or no data line with a valid time.
+pub fn time_span(text: &str) -> Option<(OffsetDateTime, OffsetDateTime)> {
+    let mut lines = text.lines().peekable();
+    let header = find_std_met_header(&mut lines, Delimiter::Whitespace)?;
+    let position = |name: &str| header.cols.iter().position(|c| c == name);
+    let (minute_idx, second_idx) = (position("mm"), position("ss"));
+    let time_of = |line: &str| {
+        let l = line.trim();
+        if l.is_empty() || l.starts_with('#') {
+            return None;
+        }
+        row_time(&l.split_whitespace().collect::<Vec<_>>(), minute_idx, second_idx)
+    };
+    let first = lines.find_map(time_of)?;
+    let last = text.lines().rev().find_map(time_of).unwrap_or(first);
+    Some((first.min(last), first.max(last)))
+}
 
-    if header_cols.is_empty() {
-        return Ok(DataFrame::empty());
+/// Current name of a standard met column; historical archives use older names for a couple.
+fn canonical_column(name: &str) -> &str {
+    match name {
+        "BAR" => "PRES",
+        "WD" => "WDIR",
+        other => other,
     }
+}
+
+/// Frame column name for a user-supplied column name: upper case, with old archive names mapped
+/// to current ones.
+fn column_key(name: &str) -> String {
+    canonical_column(&name.trim().to_uppercase()).to_string()
+}
+
+/// Units published in the header of standard met `text`, keyed by column name as used in
+/// [`parse_std_met`] frames (e.g. `WSPD` -> `m/s`, `PRES` -> `hPa`; the time columns map to
+/// `yr`, `mo`, ...). Empty when there is no header or units line; columns beyond the end of a
+/// short units line are left out.
+pub fn std_met_units(text: &str) -> HashMap<String, String> {
+    let Some(header) = find_std_met_header(&mut text.lines().peekable(), Delimiter::Whitespace) else {
+        return HashMap::new();
+    };
+    header.cols.iter().zip(header.units).map(|(name, unit)| (canonical_column(name).to_string(), unit)).collect()
+}
+
+/// Parse standard meteorological text (realtime or historical layout) into a DataFrame with a
+/// `time_ms` column and one `f64` column per standard met field. Returns an empty frame when no
+/// standard met header is found.
+pub fn parse_std_met(text: &str) -> Result<DataFrame> {
+    parse_std_met_with_stats(text).map(|(df, _)| df)
+}
+
+/// [`parse_std_met`], also counting the anomalies it tolerated (see [`ParseStats`]).
+pub fn parse_std_met_with_stats(text: &str) -> Result<(DataFrame, ParseStats)> {
+    parse_std_met_columns(text, None)
+}
+
+/// [`parse_std_met_with_stats`] keeping only `columns` (matched case-insensitively, e.g. `wvht`,
+/// in the order given) after `time_ms`; `None` keeps every standard met column. Any numeric
+/// column in the header can be requested, not just the standard ones. Requested columns the
+/// header lacks are logged as a warning and come out null.
+pub fn parse_std_met_columns(text: &str, columns: Option<&[&str]>) -> Result<(DataFrame, ParseStats)> {
+    parse_std_met_delimited(text, columns, Delimiter::Whitespace)
+}
+
+/// [`parse_std_met_columns`] for text whose fields are separated by `delimiter`, e.g. an NDBC
+/// file converted to CSV. Column names, `MM` sentinels and time fields are handled the same way.
+pub fn parse_std_met_delimited(text: &str, columns: Option<&[&str]>, delimiter: Delimiter) -> Result<(DataFrame, ParseStats)> {
+    let mut stats = ParseStats::default();
+    let mut lines = text.lines().peekable();
+    let Some(StdMetHeader { cols: header_cols, centers: header_centers, .. }) = find_std_met_header(&mut lines, delimiter) else {
+        return Ok((DataFrame::empty(), stats));
+    };
 
     // Map column name to index after the time fields (first positions include date/time)
     let mut col_idx: HashMap<String, usize> = HashMap::new();
     for (i, name) in header_cols.iter().enumerate() {
-        col_idx.insert(name.clone(), i);
+        col_idx.insert(canonical_column(name).to_string(), i);
     }
+    // Archives before 2005 have no minute column; a few carry seconds as well.
+    let minute_idx = col_idx.get("mm").cloned();
+    let second_idx = col_idx.get("ss").cloned();
 
-    // We'll capture a subset of known standard met columns if present
-    let wanted = [
-        "WDIR", "WSPD", "GST", "WVHT", "DPD", "APD", "MWD", "PRES", "ATMP", "WTMP", "DEWP", "VIS", "PTDY", "TIDE",
-    ];
+    // Capture the standard met columns, or the requested ones, null where the header lacks them.
+    // PTDY is normalized to a signed hPa/3h value; see `parse_ptdy` for the accepted encodings.
+    let wanted: Vec<String> = match columns {
+        None => STD_MET_COLUMNS.iter().map(|(name, _)| name.to_string()).collect(),
+        Some(columns) => {
+            let mut wanted: Vec<String> = Vec::new();
+            for name in columns.iter().map(|c| column_key(c)) {
+                if !wanted.contains(&name) {
+                    wanted.push(name);
+                }
+            }
+            let absent: Vec<&String> = wanted.iter().filter(|w| !col_idx.contains_key(*w)).collect();
+            if !absent.is_empty() {
+                warn!(columns = ?absent, "requested columns are not in the file header");
+            }
+            wanted
+        }
+    };
 
     let mut times: Vec<i64> = Vec::new(); // as milliseconds since epoch
-    let mut cols: HashMap<&'static str, Vec<Option<f64>>> = HashMap::new();
-    for w in wanted.iter() {
-        cols.insert(w, Vec::new());
-    }
+    let mut seen_times: HashSet<i64> = HashSet::new();
+    let mut cols: Vec<Vec<Option<f64>>> = vec![Vec::new(); wanted.len()];
 
-    // Read data lines until next comment header or EOF
-    for line in lines {
+    // Read data lines until next comment header or EOF, numbering them (1-based) for ParseStats.
+    let first_data_line = text.lines().count() - lines.clone().count() + 1;
+    for (line_no, line) in (first_data_line..).zip(lines) {
         let l = line.trim();
         if l.is_empty() {
             continue;
@@ -245,38 +2079,72 @@ fn parse_std_met_to_df(text: &str) -> Result<DataFrame> {
         if l.starts_with('#') {
             break;
         }
-        let toks: Vec<&str> = l.split_whitespace().collect();
+        let toks: Vec<&str> = delimiter.split(l);
         if toks.len() < 5 {
+            stats.record(ParseIssueKind::TooFewFields, line_no, l);
             continue;
         }
 
-        // Time components may be 4-digit year in first token or two-digit.
-        let year_s = toks[0];
-        let year: i32 = year_s.parse().unwrap_or_else(|_| 0);
-        let year = if year >= 1000 { year } else { 2000 + year };
-        let month: u8 = toks.get(1).and_then(|s| s.parse().ok()).unwrap_or(1);
-        let day: u8 = toks.get(2).and_then(|s| s.parse().ok()).unwrap_or(1);
-        let hour: u8 = toks.get(3).and_then(|s| s.parse().ok()).unwrap_or(0);
-        let minute: u8 = toks.get(4).and_then(|s| s.parse().ok()).unwrap_or(0);
-
-        let date = Date::from_calendar_date(year, month.try_into().unwrap_or(time::Month::January), day.into())
-            .unwrap_or_else(|_| Date::from_calendar_date(2000, time::Month::January, 1).unwrap());
-        let time = Tm::from_hms(hour, minute, 0).unwrap_or_else(|_| Tm::from_hms(0, 0, 0).unwrap());
-        let dt = date.with_time(time).assume_utc();
+        // Rows whose date or time does not parse are skipped.
+        let Some(dt) = row_time(&toks, minute_idx, second_idx) else {
+            stats.record(ParseIssueKind::InvalidTime, line_no, l);
+            continue;
+        };
         // Convert to milliseconds since epoch as i64
-        let ts_ms: i64 = dt.unix_timestamp() * 1000 + (dt.millisecond() as i64);
+        let Some(ts_ms) = dt.unix_timestamp().checked_mul(1000).and_then(|ms| ms.checked_add(dt.millisecond() as i64)) else {
+            warn!(line = l, "timestamp out of range; skipping row");
+            stats.record(ParseIssueKind::TimeOutOfRange, line_no, l);
+            continue;
+        };
+        if !seen_times.insert(ts_ms) {
+            stats.record(ParseIssueKind::DuplicateTime, line_no, l);
+        }
         times.push(ts_ms);
 
-        for &w in wanted.iter() {
+        // Some historical rows carry PTDY as a code + magnitude pair, which adds one token and
+        // shifts every later column by one. Only a code followed by an unsigned number counts,
+        // and in whitespace files the code must sit under the PTDY name; any other extra token is
+        // left to the ragged-row alignment below.
+        let ptdy_idx = col_idx.get("PTDY").cloned();
+        let ptdy_split = ptdy_idx.filter(|&idx| {
+            toks.len() == header_cols.len() + 1
+                && toks.get(idx).is_some_and(|t| ptdy_code_sign(t).is_some())
+                && toks.get(idx + 1).is_some_and(|m| is_unsigned_number(m))
+                && (delimiter != Delimiter::Whitespace
+                    || token_spans(line).get(idx).and_then(|&span| nearest_column(span, &header_centers)) == Some(idx))
+        });
+        // A row with a different number of fields than the header is matched to the columns by
+        // position on the line instead: NDBC aligns values under their names, so a value missing
+        // without an `MM` placeholder leaves its column empty rather than shifting the rest.
+        // Delimited rows keep empty fields, so they are always matched by field order.
+        let mut slots: Option<Vec<Option<usize>>> = None;
+        if toks.len() != header_cols.len() && ptdy_split.is_none() {
+            stats.record(ParseIssueKind::FieldCountMismatch, line_no, l);
+            if delimiter == Delimiter::Whitespace {
+                slots = align_to_header(line, &header_centers);
+            }
+        }
+        let tok_at = |idx: usize| match &slots {
+            Some(slots) => slots.get(idx).copied().flatten().and_then(|t| toks.get(t)),
+            None => toks.get(idx),
+        };
+
+        for (w, vals) in wanted.iter().zip(cols.iter_mut()) {
             let idx_opt = col_idx.get(w).cloned();
             if let Some(idx) = idx_opt {
-                let val = toks.get(idx).and_then(|s| match *s {
-                    "MM" | "NaN" => None,
-                    other => other.parse::<f64>().ok(),
-                });
-                cols.get_mut(w).unwrap().push(val);
+                let val = if w == "PTDY" {
+                    let magnitude = ptdy_split.and_then(|p| toks.get(p + 1)).copied();
+                    tok_at(idx).and_then(|s| parse_ptdy(s, magnitude))
+                } else {
+                    let idx = match ptdy_split {
+                        Some(p) if idx > p => idx + 1,
+                        _ => idx,
+                    };
+                    tok_at(idx).and_then(|s| parse_value(s))
+                };
+                vals.push(val);
             } else {
-                cols.get_mut(w).unwrap().push(None);
+                vals.push(None);
             }
         }
     }
@@ -285,12 +2153,367 @@ fn parse_std_met_to_df(text: &str) -> Result<DataFrame> {
     let mut series: Vec<Series> = Vec::new();
     let time_series = Series::new("time_ms".into(), times);
     series.push(time_series);
-    for &w in wanted.iter() {
-        let vals = cols.remove(w).unwrap();
+    for (w, vals) in wanted.iter().zip(cols) {
         let s = Series::new(w.into(), vals);
         series.push(s);
     }
 
     let df = DataFrame::new(series)?;
-    Ok(df)
+    stats.rows = df.height();
+    Ok((df, stats))
+}
+
+/// Byte ranges of the whitespace-separated tokens of `line`.
+fn token_spans(line: &str) -> Vec<(usize, usize)> {
+    let mut spans = Vec::new();
+    let mut start = None;
+    for (i, c) in line.char_indices() {
+        match (c.is_whitespace(), start) {
+            (true, Some(s)) => {
+                spans.push((s, i));
+                start = None;
+            }
+            (false, None) => start = Some(i),
+            _ => {}
+        }
+    }
+    if let Some(s) = start {
+        spans.push((s, line.len()));
+    }
+    spans
+}
+
+/// For each header column, the index of the token of `line` that sits under it: every token goes
+/// to the column whose name is centered nearest to it. `None` when two tokens land on the same
+/// column, i.e. the row is not aligned with the header and only positional matching is left.
+fn align_to_header(line: &str, header_centers: &[f64]) -> Option<Vec<Option<usize>>> {
+    let mut slots = vec![None; header_centers.len()];
+    for (t, span) in token_spans(line).into_iter().enumerate() {
+        let col = nearest_column(span, header_centers)?;
+        if slots[col].replace(t).is_some() {
+            return None;
+        }
+    }
+    Some(slots)
+}
+
+/// Header column whose name is centered nearest to the token spanning bytes `a..b` of a line.
+fn nearest_column((a, b): (usize, usize), header_centers: &[f64]) -> Option<usize> {
+    let center = (a + b) as f64 / 2.0;
+    (0..header_centers.len())
+        .min_by(|&i, &j| (header_centers[i] - center).abs().total_cmp(&(header_centers[j] - center).abs()))
+}
+
+/// Standard met columns that are null in every row of `df`, in [`STD_MET_COLUMNS`] order.
+///
+/// A column that is `MM` throughout a file means the station has no such sensor, as opposed to
+/// intermittent dropouts. Empty frames report nothing.
+pub fn missing_sensors(df: &DataFrame) -> Vec<String> {
+    if df.height() == 0 {
+        return Vec::new();
+    }
+    STD_MET_COLUMNS
+        .iter()
+        .filter(|(name, _)| df.column(name).is_ok_and(|s| s.null_count() == df.height()))
+        .map(|(name, _)| name.to_string())
+        .collect()
+}
+
+/// Parse a single numeric field, mapping the `MM`/`NaN` missing markers to `None`.
+fn parse_value(tok: &str) -> Option<f64> {
+    match tok {
+        "MM" | "NaN" => None,
+        other => other.parse::<f64>().ok(),
+    }
+}
+
+/// Whether `tok` is a number without a sign, as the magnitude of a PTDY code + magnitude pair.
+fn is_unsigned_number(tok: &str) -> bool {
+    !tok.starts_with(['+', '-']) && tok.parse::<f64>().is_ok_and(f64::is_finite)
+}
+
+/// Sign implied by a PTDY tendency code: `+`/`-`, or a WMO pressure tendency
+/// characteristic (0-3 rising, 4 steady, 5-8 falling).
+fn ptdy_code_sign(code: &str) -> Option<f64> {
+    match code {
+        "+" | "0" | "1" | "2" | "3" => Some(1.0),
+        "4" => Some(0.0),
+        "-" | "5" | "6" | "7" | "8" => Some(-1.0),
+        _ => None,
+    }
+}
+
+/// Parse pressure tendency (hPa/3h) to a signed value.
+///
+/// Accepts a plain signed number (`-1.2`, `+0.4`) or, when `magnitude` is given, a
+/// tendency code followed by an unsigned magnitude (`- 1.2`, `7 1.2`).
+fn parse_ptdy(tok: &str, magnitude: Option<&str>) -> Option<f64> {
+    match magnitude {
+        Some(m) => {
+            let sign = ptdy_code_sign(tok)?;
+            parse_value(m).map(|v| sign * v.abs())
+        }
+        None => parse_value(tok),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Empty scratch directory for one test, removed first if an earlier run left it behind.
+    fn scratch_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("ndbc-data-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    fn times(df: &DataFrame) -> Result<Vec<Option<i64>>> {
+        Ok(df.column("time_ms")?.cast(&DataType::Int64)?.i64()?.into_iter().collect())
+    }
+
+    #[test]
+    fn parses_std_met_fixture() -> Result<()> {
+        let df = parse_std_met(include_str!("../data-samples/parser_cases.txt"))?;
+        // The short `2025 11` line and the February 30th row are skipped.
+        assert_eq!(df.height(), 3);
+        let nov_13 = |minute: i64| (1_762_992_000 + minute * 60) * 1000;
+        assert_eq!(times(&df)?, [Some(nov_13(40)), Some(nov_13(30)), Some(nov_13(-10))]);
+        // `MM` is null, not zero.
+        let wvht: Vec<Option<f64>> = df.column("WVHT")?.f64()?.into_iter().collect();
+        assert_eq!(wvht, [None, Some(0.5), Some(0.4)]);
+        assert_eq!(df.column("TIDE")?.null_count(), 3);
+        Ok(())
+    }
+
+    #[test]
+    fn seconds_column_shifts_data_columns() -> Result<()> {
+        let df = parse_std_met(include_str!("../data-samples/seconds_column.txt"))?;
+        assert_eq!(times(&df)?, [Some(1_717_246_230_000), Some(1_717_245_600_000)]);
+        // WDIR is the first column after `ss`, not the seconds value.
+        let column = |name: &str| -> Result<Vec<Option<f64>>> { Ok(df.column(name)?.f64()?.into_iter().collect()) };
+        assert_eq!(column("WDIR")?, [Some(200.0), Some(210.0)]);
+        assert_eq!(column("PTDY")?, [Some(-0.8), Some(-0.6)]);
+        assert_eq!(column("TIDE")?, [None, None]);
+        Ok(())
+    }
+
+    #[test]
+    fn two_digit_years_pivot_at_50() -> Result<()> {
+        let text = "#YY  MM DD hh mm WDIR WSPD\n#yr  mo dy hr mn degT m/s\n  25 11 13 00 30 190  3.0\n  49 11 13 00 30 190  3.0\n  50 11 13 00 30 190  3.0\n  99 11 13 00 30 190  3.0\n";
+        let df = parse_std_met(text)?;
+        let mut years = Vec::new();
+        for ms in times(&df)? {
+            let time = OffsetDateTime::from_unix_timestamp(ms.unwrap_or_default() / 1000).map_err(|e| NdbcError::Parse(e.to_string()))?;
+            years.push(time.year());
+        }
+        assert_eq!(years, [2025, 2049, 1950, 1999]);
+        Ok(())
+    }
+
+    #[test]
+    fn stations_reporting_different_columns_share_one_schema() -> Result<()> {
+        let data = NdbcData::in_memory()?;
+        let full = data.frame_from_text("41001", include_str!("../data-samples/realtimedata.txt"))?;
+        let wind_only = data.frame_from_text("41001", "#YY  MM DD hh mm WSPD\n#yr  mo dy hr mn m/s\n2025 11 13 00 30  3.0\n")?;
+        assert_eq!(full.schema(), wind_only.schema());
+        assert_eq!(full.schema(), std_met_schema());
+        assert_eq!(full.column("time_ms")?.dtype(), &DataType::Datetime(TimeUnit::Milliseconds, Some("UTC".into())));
+        Ok(())
+    }
+
+    #[test]
+    fn decode_text_inflates_gzip_fixture() -> Result<()> {
+        let plain = include_str!("../data-samples/parser_cases.txt");
+        let gz = include_bytes!("../data-samples/parser_cases.txt.gz");
+        // The magic bytes decide: compressed and already-inflated bodies give the same text.
+        assert_eq!(decode_text(gz)?, plain);
+        assert_eq!(decode_text(plain.as_bytes())?, plain);
+        // Every member of a multi-member file is read.
+        let twice = [&gz[..], &gz[..]].concat();
+        assert_eq!(decode_text(&twice)?, [plain, plain].concat());
+        assert_eq!(parse_std_met(&decode_text(gz)?)?.height(), 3);
+        Ok(())
+    }
+
+    #[test]
+    fn jsonl_output_keeps_missing_values_as_null() -> Result<()> {
+        let data = NdbcData::builder().format(OutputFormat::Jsonl).in_memory().build()?;
+        let text = "#YY  MM DD hh mm WSPD WVHT\n#yr  mo dy hr mn m/s  m\n2025 11 13 00 30  3.0   MM\n";
+        let mut df = data.frame_from_text("41001", text)?;
+        let mut out = Vec::new();
+        data.encode_frame(&mut df, &mut out, Vec::new())?;
+        let text = String::from_utf8_lossy(&out);
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines.len(), 1);
+        let record: serde_json::Value = serde_json::from_str(lines[0]).map_err(|e| NdbcError::Parse(e.to_string()))?;
+        let record = record.as_object().ok_or_else(|| NdbcError::Parse("not an object".into()))?;
+        assert_eq!(record["time"], "2025-11-13T00:30:00Z");
+        assert_eq!(record["wspd"], 3.0);
+        assert_eq!(record["station_id"], "41001");
+        // Missing values are written as null rather than left out, so every line has every key.
+        assert!(record["wvht"].is_null());
+        assert!(record["atmp"].is_null());
+        assert!(record.keys().all(|k| *k == k.to_lowercase()), "{:?}", record.keys());
+        assert!(!record.contains_key("time_ms"));
+        Ok(())
+    }
+
+    #[test]
+    fn jsonl_batches_match_a_single_pass() -> Result<()> {
+        let data = NdbcData::in_memory()?;
+        let df = data.frame_from_text("41001", include_str!("../data-samples/realtimedata.txt"))?;
+        let mut whole = Vec::new();
+        write_jsonl(&df, &mut whole, Some(2), df.height())?;
+        let mut batched = Vec::new();
+        write_jsonl(&df, &mut batched, Some(2), 3)?;
+        assert!(df.height() > 3);
+        assert_eq!(String::from_utf8_lossy(&whole).lines().count(), df.height());
+        assert_eq!(batched, whole);
+        Ok(())
+    }
+
+    #[test]
+    fn imperial_converts_pressure_and_tendency_to_inhg() -> Result<()> {
+        let df = df!("PRES" => [Some(1013.0), None], "PTDY" => [Some(-1.0), None], "VIS" => [Some(10.0), None])?;
+        let imperial = Units::Imperial.convert(df.clone())?;
+        assert_eq!(imperial.column("PRES")?.f64()?.get(0), Some(1013.0 * 0.029_53));
+        assert_eq!(imperial.column("PTDY")?.f64()?.get(0), Some(-0.029_53));
+        assert_eq!(imperial.column("PTDY")?.f64()?.get(1), None);
+        assert_eq!(Units::Imperial.unit("PTDY"), Some("inHg"));
+        // Metric only touches VIS.
+        let metric = Units::Metric.convert(df.clone())?;
+        assert_eq!(metric.column("VIS")?.f64()?.get(0), Some(18.52));
+        assert_eq!(metric.drop("VIS")?, df.drop("VIS")?);
+        Ok(())
+    }
+
+    #[test]
+    fn stray_token_is_not_read_as_a_ptdy_code() -> Result<()> {
+        let (df, stats) = parse_std_met_columns(include_str!("../data-samples/ptdy_stray_token.txt"), None)?;
+        let ptdy: Vec<Option<f64>> = df.column("PTDY")?.f64()?.into_iter().collect();
+        // The extra `9` shifts a `5` to the PTDY field, but it sits under VIS and is followed by
+        // the real value rather than a magnitude, so the row is ragged rather than coded.
+        assert_ne!(ptdy[0], Some(-1.2));
+        assert_eq!(ptdy[1], Some(1.2));
+        let mismatched: Vec<usize> =
+            stats.issues.iter().filter(|i| i.kind == ParseIssueKind::FieldCountMismatch).map(|i| i.line).collect();
+        assert_eq!(mismatched, [3]);
+        Ok(())
+    }
+
+    #[test]
+    fn nearest_stations_are_ordered_by_great_circle_distance() -> Result<()> {
+        let mut data = NdbcData::in_memory()?;
+        data.load_stations(parse_station_metadata(include_bytes!("../data-samples/stationmetadata.xml"))?, MetadataSource::StationMetadata)?;
+        // From 41001 (34.703 N 72.242 W): 41083 at 35.725 N 74.853 W is 263.0 km away and DSLN7
+        // at 35.153 N 75.297 W is 283.0 km, both on a 6371 km sphere.
+        let nearest = data.nearest_stations(34.703, -72.242, 3);
+        let ids: Vec<&str> = nearest.iter().map(|(id, _)| id.as_str()).collect();
+        assert_eq!(ids, ["41001", "41083", "DSLN7"]);
+        assert_eq!(nearest[0].1, 0.0);
+        assert!((nearest[1].1 - 263.005).abs() < 0.01, "{}", nearest[1].1);
+        assert!((nearest[2].1 - 282.958).abs() < 0.01, "{}", nearest[2].1);
+        assert!(data.nearest_stations(34.703, -72.242, 0).is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn stations_within_km_wraps_around_the_antimeridian() -> Result<()> {
+        let station = |id: &str, lat: f64, lon: f64| {
+            (id.to_string(), Station { id: id.to_string(), lat: Some(lat), lon: Some(lon), met: true, ..Default::default() })
+        };
+        let mut data = NdbcData::in_memory()?;
+        data.load_stations(
+            HashMap::from([station("EAST", 0.0, 179.9), station("WEST", 0.0, -179.9), station("GREENWICH", 0.0, 0.0)]),
+            MetadataSource::StationMetadata,
+        )?;
+        // 0.2° of longitude on the equator across ±180° is 22.24 km, not most of the way around.
+        let near = data.stations_within_km(0.0, 179.9, 50.0);
+        let ids: Vec<&str> = near.iter().map(|(id, _)| id.as_str()).collect();
+        assert_eq!(ids, ["EAST", "WEST"]);
+        assert!((near[1].1 - 22.239).abs() < 0.01, "{}", near[1].1);
+        assert!(data.stations_within_km(0.0, -180.0, 11.0).is_empty());
+        assert_eq!(data.stations_within_km(0.0, -180.0, 12.0).len(), 2);
+        Ok(())
+    }
+
+    #[test]
+    fn fixtures_match_golden_files() -> Result<()> {
+        let results = check_golden(Path::new("data-samples"), Path::new("data-samples/golden"), false)?;
+        assert!(!results.is_empty());
+        for r in results {
+            assert_eq!(r.outcome, GoldenOutcome::Match, "{} vs {} (line {:?})", r.fixture.display(), r.golden.display(), r.first_difference);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn append_dedupes_and_sorts_by_time() -> Result<()> {
+        let dir = scratch_dir("append");
+        let data = NdbcData::new(&dir)?;
+        let first = df!("time_ms" => [3_000i64, 1_000, 2_000], "WSPD" => [3.0, 1.0, 2.0])?;
+        data.save_station_frame_with("T1", first, "T1", "test", Some(Revision::KeepLatest))?;
+        let second = df!("time_ms" => [4_000i64, 2_000, 2_000], "WSPD" => [4.0, 2.4, 2.5])?;
+        let entry = data.save_station_frame_with("T1", second, "T1", "test", Some(Revision::KeepLatest))?;
+
+        let saved = coords::read_parquet(&entry.output_path)?;
+        assert_eq!(times(&saved)?, [Some(1_000), Some(2_000), Some(3_000), Some(4_000)]);
+        let wspd: Vec<Option<f64>> = saved.column("WSPD")?.f64()?.into_iter().collect();
+        assert_eq!(wspd, [Some(1.0), Some(2.5), Some(3.0), Some(4.0)]);
+        assert_eq!(entry.row_count, 4);
+        fs::remove_dir_all(&dir)?;
+        Ok(())
+    }
+
+    #[test]
+    fn keep_both_numbers_revised_observations() -> Result<()> {
+        let dir = scratch_dir("keep-both");
+        let data = NdbcData::builder().out_dir(&dir).revision(Revision::KeepBoth).build()?;
+        let first = df!("time_ms" => [1_000i64, 2_000], "WSPD" => [1.0, 2.0])?;
+        let entry = data.save_station_frame("T1", first, "T1", "test")?;
+        assert_eq!(coords::read_parquet(&entry.output_path)?.schema(), data.output_schema());
+
+        // The second fetch repeats the first observation and revises the second.
+        let second = df!("time_ms" => [1_000i64, 2_000, 3_000], "WSPD" => [1.0, 2.5, 3.0])?;
+        let entry = data.save_station_frame("T1", second, "T1", "test")?;
+        let saved = coords::read_parquet(&entry.output_path)?;
+        assert_eq!(saved.schema(), data.output_schema());
+        assert_eq!(times(&saved)?, [Some(1_000), Some(2_000), Some(2_000), Some(3_000)]);
+        let wspd: Vec<Option<f64>> = saved.column("WSPD")?.f64()?.into_iter().collect();
+        assert_eq!(wspd, [Some(1.0), Some(2.0), Some(2.5), Some(3.0)]);
+        let revisions: Vec<Option<u32>> = saved.column(REVISION_COLUMN)?.u32()?.into_iter().collect();
+        assert_eq!(revisions, [Some(0), Some(0), Some(1), Some(0)]);
+        fs::remove_dir_all(&dir)?;
+        Ok(())
+    }
+
+    #[test]
+    fn arrow_ipc_round_trips_schema_and_rows() -> Result<()> {
+        let dir = scratch_dir("ipc");
+        let data = NdbcData::builder().out_dir(&dir).format(OutputFormat::ArrowIpc).build()?;
+        let df = data.parse_archive(include_str!("../data-samples/realtimedata.txt"))?;
+        let rows = df.height();
+        let entry = data.save_station_frame("41001", df, "41001", "test")?;
+        assert_eq!(entry.output_path, dir.join("41001.arrow"));
+
+        let read = IpcReader::new(fs::File::open(&entry.output_path)?).finish()?;
+        assert_eq!(read.schema(), data.output_schema());
+        assert_eq!(read.height(), rows);
+        fs::remove_dir_all(&dir)?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn append_rejects_formats_that_are_rewritten() -> Result<()> {
+        let dir = scratch_dir("append-csv");
+        let data = NdbcData::builder().out_dir(&dir).format(OutputFormat::Csv).build()?;
+        assert!(matches!(data.append_station("T1").await, Err(NdbcError::MergeUnsupported(OutputFormat::Csv))));
+        let frame = df!("time_ms" => [1_000i64], "WSPD" => [1.0])?;
+        let merged = data.save_station_frame_with("T1", frame, "T1", "test", Some(Revision::KeepFirst));
+        assert!(matches!(merged, Err(NdbcError::MergeUnsupported(OutputFormat::Csv))));
+        assert!(!dir.join("T1.csv").exists());
+        fs::remove_dir_all(&dir)?;
+        Ok(())
+    }
 }
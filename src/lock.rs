@@ -0,0 +1,178 @@
+//! Advisory lock on an output directory so overlapping runs cannot interleave writes.
+
+use crate::{NdbcError, Result};
+use std::fs::{File, OpenOptions, TryLockError};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use time::format_description::well_known::Rfc3339;
+use time::OffsetDateTime;
+
+/// Name of the lock file created inside the output directory.
+pub const LOCK_FILE_NAME: &str = ".ndbc-archive.lock";
+
+/// How often a waiting caller retries a held lock.
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Whether an [`ArchiveLock`] excludes every other holder or only writers.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LockMode {
+    /// For commands that write files; excludes all other holders.
+    Exclusive,
+    /// For read-only commands; any number may be held at once, but not alongside a writer.
+    Shared,
+}
+
+/// Held advisory lock on `out_dir/.ndbc-archive.lock`, released when dropped.
+///
+/// Exclusive holders record their pid and start time in the lock file so a competing run
+/// can report who holds it.
+#[derive(Debug)]
+pub struct ArchiveLock {
+    file: File,
+    path: PathBuf,
+    mode: LockMode,
+}
+
+impl ArchiveLock {
+    /// Take the lock on `dir`, failing with [`NdbcError::ArchiveLocked`] if it is held, or
+    /// retrying until `wait` has elapsed when given.
+    pub async fn acquire(dir: &Path, mode: LockMode, wait: Option<Duration>) -> Result<Self> {
+        let path = dir.join(LOCK_FILE_NAME);
+        let file = OpenOptions::new().read(true).write(true).create(true).truncate(false).open(&path)?;
+        let deadline = wait.map(|w| Instant::now() + w);
+        loop {
+            let attempt = match mode {
+                LockMode::Exclusive => file.try_lock(),
+                LockMode::Shared => file.try_lock_shared(),
+            };
+            match attempt {
+                Ok(()) => break,
+                Err(TryLockError::Error(e)) => return Err(e.into()),
+                Err(TryLockError::WouldBlock) => {
+                    if deadline.is_none_or(|d| Instant::now() >= d) {
+                        let (pid, since) = read_holder(&path);
+                        return Err(NdbcError::ArchiveLocked { pid, since });
+                    }
+                    tokio::time::sleep(POLL_INTERVAL).await;
+                }
+            }
+        }
+
+        let mut lock = Self { file, path, mode };
+        if mode == LockMode::Exclusive {
+            lock.write_holder()?;
+        }
+        Ok(lock)
+    }
+
+    /// Path of the lock file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Mode the lock is held in.
+    pub fn mode(&self) -> LockMode {
+        self.mode
+    }
+
+    fn write_holder(&mut self) -> Result<()> {
+        let since = OffsetDateTime::now_utc().format(&Rfc3339).unwrap_or_default();
+        self.file.set_len(0)?;
+        self.file.seek(SeekFrom::Start(0))?;
+        write!(self.file, "pid={}\nsince={}\n", std::process::id(), since)?;
+        self.file.flush()?;
+        Ok(())
+    }
+}
+
+impl Drop for ArchiveLock {
+    fn drop(&mut self) {
+        // Clear the holder record before the lock is released so it never names a finished run.
+        if self.mode == LockMode::Exclusive {
+            let _ = self.file.set_len(0);
+        }
+        let _ = self.file.unlock();
+    }
+}
+
+/// Pid and start time recorded by the current exclusive holder, when readable.
+fn read_holder(path: &Path) -> (Option<u32>, Option<String>) {
+    let mut text = String::new();
+    if File::open(path).and_then(|mut f| f.read_to_string(&mut text)).is_err() {
+        return (None, None);
+    }
+    let field = |key: &str| {
+        text.lines()
+            .find_map(|line| line.strip_prefix(key)?.strip_prefix('='))
+            .map(str::to_string)
+    };
+    (field("pid").and_then(|p| p.parse().ok()), field("since"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lock_dir(name: &str) -> Result<PathBuf> {
+        let dir = std::env::temp_dir().join(format!("ndbc-data-lock-{}-{}", name, std::process::id()));
+        std::fs::create_dir_all(&dir)?;
+        Ok(dir)
+    }
+
+    #[tokio::test]
+    async fn exclusive_excludes_exclusive_and_names_the_holder() -> Result<()> {
+        let dir = lock_dir("exclusive")?;
+        let held = ArchiveLock::acquire(&dir, LockMode::Exclusive, None).await?;
+        match ArchiveLock::acquire(&dir, LockMode::Exclusive, None).await {
+            Err(NdbcError::ArchiveLocked { pid, since }) => {
+                assert_eq!(pid, Some(std::process::id()));
+                assert!(since.is_some_and(|t| OffsetDateTime::parse(&t, &Rfc3339).is_ok()));
+            }
+            other => panic!("expected ArchiveLocked, got {:?}", other),
+        }
+        // Readers are kept out too.
+        assert!(matches!(ArchiveLock::acquire(&dir, LockMode::Shared, None).await, Err(NdbcError::ArchiveLocked { .. })));
+
+        drop(held);
+        ArchiveLock::acquire(&dir, LockMode::Exclusive, None).await?;
+        std::fs::remove_dir_all(&dir)?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn shared_locks_coexist() -> Result<()> {
+        let dir = lock_dir("shared")?;
+        let first = ArchiveLock::acquire(&dir, LockMode::Shared, None).await?;
+        let second = ArchiveLock::acquire(&dir, LockMode::Shared, None).await?;
+        assert_eq!((first.mode(), second.mode()), (LockMode::Shared, LockMode::Shared));
+        // A writer waits for the readers; shared holders record no pid.
+        assert!(matches!(
+            ArchiveLock::acquire(&dir, LockMode::Exclusive, None).await,
+            Err(NdbcError::ArchiveLocked { pid: None, since: None })
+        ));
+        drop((first, second));
+        std::fs::remove_dir_all(&dir)?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn wait_gives_up_after_its_timeout() -> Result<()> {
+        let dir = lock_dir("wait")?;
+        let held = ArchiveLock::acquire(&dir, LockMode::Exclusive, None).await?;
+        let started = Instant::now();
+        let result = ArchiveLock::acquire(&dir, LockMode::Exclusive, Some(Duration::from_millis(300))).await;
+        assert!(matches!(result, Err(NdbcError::ArchiveLocked { .. })));
+        assert!(started.elapsed() >= Duration::from_millis(300));
+
+        // Released while waiting: the waiter gets it.
+        let release = tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(200)).await;
+            drop(held);
+        });
+        ArchiveLock::acquire(&dir, LockMode::Exclusive, Some(Duration::from_secs(10))).await?;
+        let _ = release.await;
+        std::fs::remove_dir_all(&dir)?;
+        Ok(())
+    }
+}
@@ -1,69 +1,740 @@
+//! Command-line front end. Only argument parsing and orchestration live here; downloading,
+//! parsing and writing are done through `ndbc_data::NdbcData`, so the CLI and library always
+//! produce the same output.
+
 use anyhow::Result;
-use clap::Parser;
+use clap::builder::PossibleValuesParser;
+use clap::error::ErrorKind;
+use clap::{CommandFactory, Parser, Subcommand};
+use indicatif::{ProgressBar, ProgressStyle};
+use std::io::IsTerminal;
 use std::path::PathBuf;
+use std::time::Duration;
 use tracing::{info, warn};
-use ndbc_data::NdbcData;
+use futures::stream::{self, StreamExt};
+use polars::prelude::DataFrame;
+use ndbc_data::{
+    check_golden, station_group, station_summary, BatchReport, Compression, CoordsAs, CoordsFormat, DuplicateTimes, ExportFormat, FetchOptions, GoldenOutcome, HttpVersion, IndexEntry, MetadataFilter, MetadataFormat, MetadataSource, NdbcData, OutputFormat, ParquetOptions, PartitionScheme, Revision, SelectionMethod,
+    SiteReportOptions, StationFilter, StationSelection, StrictLimits, Units, Window, DEFAULT_TREND_THRESHOLD_HPA, STATION_GROUPS,
+};
 
 /// Simple CLI to download, parse, and save NOAA NDBC standard met data (last ~45 days) to Parquet.
 #[derive(Parser, Debug)]
-#[command(name = "ndbc-data", version, about = "Fetch NDBC realtime standard meteorological data and save as Parquet")] 
+#[command(name = "ndbc-data", version, long_version = concat!(env!("CARGO_PKG_VERSION"), " (", env!("NDBC_GIT_HASH"), ")"), about = "Fetch NDBC realtime standard meteorological data and save as Parquet")] 
 struct Args {
+    #[command(subcommand)]
+    command: Option<Command>,
+
     /// Station identifiers to retrieve (e.g., 42040, 46042, FPKA2)
     #[arg(required = false)]
     stations: Vec<String>,
 
+    /// Process every station inside minlat,minlon,maxlat,maxlon, in addition to any listed ids and
+    /// groups (a box with minlon > maxlon crosses the antimeridian)
+    #[arg(long, value_name = "MINLAT,MINLON,MAXLAT,MAXLON", value_parser = parse_bbox, allow_hyphen_values = true)]
+    bbox: Option<[f64; 4]>,
+
+    /// Process a built-in station group, in addition to any listed ids and --bbox; repeatable
+    #[arg(long, value_name = "NAME", ignore_case = true, value_parser = PossibleValuesParser::new(STATION_GROUPS.map(|g| g.name)))]
+    group: Vec<String>,
+
+    /// Process the stations nearest to LAT,LON instead of listing ids
+    #[arg(long, value_name = "LAT,LON", value_parser = parse_point, allow_hyphen_values = true, conflicts_with_all = ["stations", "bbox", "group"])]
+    near: Option<(f64, f64)>,
+
+    /// Number of stations to process with --near
+    #[arg(long, default_value_t = 5, requires = "near")]
+    count: usize,
+
+    /// Only process stations whose owner contains this text (case-insensitive), e.g. CDIP
+    #[arg(long)]
+    owner: Option<String>,
+
+    /// Only process stations whose program contains this text (case-insensitive)
+    #[arg(long)]
+    program: Option<String>,
+
+    /// Fail a station when parsing its file hits more anomalies than the --max-* limits allow,
+    /// instead of logging a warning
+    #[arg(long)]
+    strict: bool,
+
+    /// With --strict: malformed lines tolerated per file
+    #[arg(long, value_name = "N", default_value_t = 0, requires = "strict")]
+    max_malformed_lines: usize,
+
+    /// With --strict: rows whose field count differs from the header, tolerated per file
+    #[arg(long, value_name = "N", default_value_t = 0, requires = "strict")]
+    max_token_mismatches: usize,
+
+    /// With --strict: repeated timestamps tolerated per file
+    #[arg(long, value_name = "N", default_value_t = 0, requires = "strict")]
+    max_duplicate_times: usize,
+
+    /// Repeated timestamps in a file: keep (every row, in file order), or first/last (sort by time
+    /// and keep that row of each timestamp)
+    #[arg(long, value_enum, default_value_t = DuplicateTimes::Keep)]
+    duplicate_times: DuplicateTimes,
+
+    /// Write <station>.parse_issues.json next to each saved file, listing the line number, kind and
+    /// text of every line the parser skipped or flagged
+    #[arg(long)]
+    write_parse_issues: bool,
+
+    /// Also keep each downloaded realtime file as <station>.txt in the output directory, e.g. to
+    /// attach to a bug report or use as a parser fixture
+    #[arg(long)]
+    save_raw: bool,
+
     /// Output directory for Parquet files (default: ./data)
-    #[arg(short, long, default_value = "data")]
+    #[arg(short, long, default_value = "data", global = true)]
     out_dir: PathBuf,
+
+    /// Output file format
+    #[arg(long, value_enum, default_value_t = OutputFormat::Parquet)]
+    format: OutputFormat,
+
+    /// SQLite database for --format sqlite (default: OUT_DIR/ndbc.sqlite)
+    #[cfg(feature = "sqlite")]
+    #[arg(long, value_name = "PATH")]
+    sqlite_path: Option<PathBuf>,
+
+    /// Table observations are upserted into with --format sqlite
+    #[cfg(feature = "sqlite")]
+    #[arg(long, value_name = "NAME", default_value = "observations")]
+    sqlite_table: String,
+
+    /// Parquet compression: uncompressed, snappy, lz4, zstd or zstd:LEVEL (1-22)
+    #[arg(long, value_name = "CODEC", default_value_t = Compression::default())]
+    compression: Compression,
+
+    /// Rows per Parquet row group (default: polars' 262144, one row group for a station file)
+    #[arg(long, value_name = "ROWS")]
+    row_group_size: Option<usize>,
+
+    /// Do not write min/max/null-count statistics to Parquet files
+    #[arg(long)]
+    no_statistics: bool,
+
+    /// Merge into existing Parquet or Arrow IPC files instead of overwriting: keep-first, keep-latest, or
+    /// keep-both (adds a revision column numbering QC corrections)
+    #[arg(long, value_enum)]
+    revision: Option<Revision>,
+
+    /// Append to existing station files, keeping the latest values for repeated timestamps
+    /// (same as --revision keep-latest)
+    #[arg(long, conflicts_with = "revision")]
+    append: bool,
+
+    /// Skip stations whose saved file was written less than this long ago (e.g. 50m, 6h), judged by
+    /// its modification time
+    #[arg(long, value_name = "DURATION", value_parser = parse_duration, conflicts_with_all = ["combine", "combined"])]
+    incremental: Option<Duration>,
+
+    /// Save all stations into a single combined.<ext> file instead of one file per station
+    #[arg(long)]
+    combine: bool,
+
+    /// Like --combine, but write the combined file to PATH (relative to the working directory)
+    #[arg(long, value_name = "PATH")]
+    combined: Option<PathBuf>,
+
+    /// At the end of the run, print each station's row count, time range and the share of rows
+    /// with a value per column (also added to --summary-json)
+    #[arg(long)]
+    report: bool,
+
+    /// Print the resolved stations (id, coordinates, whether the metadata lists a met/realtime
+    /// feed, name) as tab-separated lines and exit without downloading any data
+    #[arg(long)]
+    dry_run: bool,
+
+    /// Output layout: flat (<station>.<ext>) or station-date
+    /// (station=<id>/date=<YYYY-MM-DD>/part-0.<ext>, replacing only the days fetched)
+    #[arg(long, value_enum, default_value_t = PartitionScheme::Flat, conflicts_with_all = ["combine", "combined"])]
+    partition: PartitionScheme,
+
+    /// Where Parquet station files keep latitude/longitude: a column on every row, or once in
+    /// the file's key-value metadata (when constant over the file)
+    #[arg(long, value_enum, default_value_t = CoordsAs::Column, conflicts_with_all = ["combine", "combined"])]
+    coords_as: CoordsAs,
+
+    /// After the run, write index.parquet listing each saved station with its output file, time
+    /// range and row count
+    #[arg(long)]
+    write_index: bool,
+
+    /// HTTP protocol: 1.1 (default), auto (HTTP/2 when the server offers it) or 2 (HTTP/2 only)
+    #[arg(long, value_enum, default_value_t = HttpVersion::Http1)]
+    http_version: HttpVersion,
+
+    /// Per-request HTTP timeout in seconds
+    #[arg(long, default_value_t = 30)]
+    timeout: u64,
+
+    /// Server to download from (scheme and host), e.g. an NDBC mirror
+    #[arg(long, value_name = "URL", default_value = ndbc_data::NDBC_BASE_URL)]
+    base_url: String,
+
+    /// Round float columns to N decimals in text outputs (JSON Lines and CSV, not Parquet or Arrow IPC)
+    #[arg(long, value_name = "N")]
+    precision: Option<u32>,
+
+    /// Units for saved values: native (as published), metric (visibility in km) or imperial
+    /// (knots, feet, inHg, degrees F)
+    #[arg(long, value_enum, default_value_t = Units::Native)]
+    units: Units,
+
+    /// Keep only these met columns, comma-separated (e.g. wvht,dpd,mwd); default: all standard
+    /// met columns
+    #[arg(long, value_delimiter = ',', value_name = "COLS")]
+    columns: Option<Vec<String>>,
+
+    /// Add station_type (e.g. buoy, fixed), elevation and anemometer_height columns from the
+    /// station metadata
+    #[arg(long)]
+    station_columns: bool,
+
+    /// Append wind_chill, heat_index (null outside each formula's valid range) and pressure_trend
+    /// columns
+    #[arg(long)]
+    derive: bool,
+
+    /// Append station_pressure: PRES (published at sea level) brought up to the station's metadata
+    /// elevation using ATMP (null when the elevation is unknown)
+    #[arg(long)]
+    add_station_pressure: bool,
+
+    /// With --derive: largest 3-hour pressure change (hPa, either way) classed as a steady trend
+    #[arg(long, value_name = "HPA", default_value_t = DEFAULT_TREND_THRESHOLD_HPA, requires = "derive")]
+    trend_threshold: f64,
+
+    /// Realtime window to download: 45day (realtime2) or 5day (5day2)
+    #[arg(long, value_enum, default_value_t = Window::FortyFiveDay)]
+    window: Window,
+
+    /// Station list to load: full (stationmetadata.xml, with history), active (activestations.xml)
+    /// or both (merged per station)
+    #[arg(long, value_enum, default_value_t = MetadataSource::StationMetadata)]
+    metadata_source: MetadataSource,
+
+    /// Stations eligible for selection: met-only, or all (including currents, water quality and
+    /// DART stations)
+    #[arg(long, value_enum, default_value_t = MetadataFilter::MetOnly)]
+    metadata_filter: MetadataFilter,
+
+    /// Reuse station metadata cached in the output directory if younger than this (e.g. 1h, 24h);
+    /// only applies to --metadata-source full
+    #[arg(long, value_name = "DURATION", value_parser = parse_duration)]
+    metadata_max_age: Option<Duration>,
+
+    /// Re-download station metadata and rewrite the cache even if it is still fresh
+    #[arg(long)]
+    refresh_metadata: bool,
+
+    /// Retries for transient HTTP failures (5xx, 429, connection errors); 0 disables retrying
+    #[arg(long, default_value_t = 3)]
+    max_retries: u32,
+
+    /// Delay before the first retry in milliseconds; doubles on each further retry
+    #[arg(long, default_value_t = 500)]
+    retry_base_delay: u64,
+
+    /// Longest wait between retries in milliseconds, also capping a server's Retry-After
+    #[arg(long, default_value_t = 60_000)]
+    retry_max_delay: u64,
+
+    /// Maximum seconds to spend on each station end-to-end (download, parse and write)
+    #[arg(long)]
+    station_timeout: Option<u64>,
+
+    /// Number of stations downloaded at once
+    #[arg(long, default_value_t = 4)]
+    concurrency: usize,
+
+    /// Wait up to this long (e.g. 30s, 5m, 1h) for another run's archive lock instead of failing
+    #[arg(long, value_name = "DURATION", value_parser = parse_duration)]
+    wait_lock: Option<Duration>,
+
+    /// Print build information (version, git hash, features) as JSON and exit
+    #[arg(long)]
+    build_info: bool,
+
+    /// Print the JSON Schema of a JSON Lines output record under the other options given
+    /// (--units, --columns, --derive, ...) and exit
+    #[arg(long)]
+    json_schema: bool,
+
+    /// Print the end-of-run summary as JSON on stdout
+    #[arg(long)]
+    summary_json: bool,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Stream every station saved as Parquet in the output directory as one file
+    Export {
+        /// Export format
+        #[arg(long, value_enum, default_value_t = ExportFormat::Csv)]
+        format: ExportFormat,
+
+        /// Destination file, or - for stdout
+        #[arg(long, default_value = "-")]
+        out: PathBuf,
+    },
+    /// Write the loaded station metadata for use elsewhere: GeoJSON points for a web map, or the
+    /// full station table (id, name, owner, type, coordinates, ...) as CSV or Parquet for joins
+    Metadata {
+        /// Output format
+        #[arg(long, value_enum, default_value_t = MetadataFormat::Geojson)]
+        format: MetadataFormat,
+
+        /// Destination file, or - for stdout
+        #[arg(long, default_value = "-")]
+        out: PathBuf,
+    },
+    /// Print the current coordinates and name of the given stations from the metadata, without
+    /// fetching observations
+    Coords {
+        /// Station ids, e.g. 42040 46042
+        #[arg(required = true)]
+        ids: Vec<String>,
+
+        /// Output format
+        #[arg(long, value_enum, default_value_t = CoordsFormat::Table)]
+        format: CoordsFormat,
+    },
+    /// Fetch recent data for every met station around a point and write a directory of
+    /// artifacts (stations.csv, summary.csv, observations/<station>.parquet, index.json)
+    Report {
+        /// Center of the site
+        #[arg(long, value_name = "LAT,LON", value_parser = parse_point, allow_hyphen_values = true)]
+        near: (f64, f64),
+
+        /// Include met stations within this distance of the center
+        #[arg(long, value_name = "KM", default_value_t = 50.0)]
+        radius_km: f64,
+
+        /// How far back to fetch (e.g. 30d, 72h); rounded to whole days
+        #[arg(long, value_name = "DURATION", value_parser = parse_duration, default_value = "30d")]
+        last: Duration,
+
+        /// Report directory
+        #[arg(long, default_value = "report")]
+        out: PathBuf,
+    },
+    /// Regression check: parse every *.txt fixture and compare the CSV output with the committed
+    /// golden files; exits with an error on any difference
+    #[command(hide = true)]
+    Golden {
+        /// Directory of standard met fixtures
+        #[arg(long, default_value = "data-samples")]
+        fixtures: PathBuf,
+
+        /// Directory of golden CSV files
+        #[arg(long, default_value = "data-samples/golden")]
+        golden: PathBuf,
+
+        /// Rewrite the golden files from the current output instead of comparing
+        #[arg(long)]
+        bless: bool,
+    },
 }
 
 #[tokio::main]
 async fn main() -> Result<()> {
     setup_tracing();
     let args = Args::parse();
+    if (args.append || args.revision.is_some()) && !args.format.supports_merge() {
+        let msg = format!("--append and --revision merge into saved files, which --format {} rewrites; use parquet or arrow-ipc", format!("{:?}", args.format).to_lowercase());
+        Args::command().error(ErrorKind::ArgumentConflict, msg).exit();
+    }
+    if args.build_info {
+        println!("{}", serde_json::to_string_pretty(&ndbc_data::build_info())?);
+        return Ok(());
+    }
+    if let Some(Command::Golden { fixtures, golden, bless }) = &args.command {
+        let results = check_golden(fixtures, golden, *bless)?;
+        let mut failed = 0;
+        for r in &results {
+            match r.outcome {
+                GoldenOutcome::Match => {}
+                GoldenOutcome::Blessed => eprintln!("blessed {}", r.golden.display()),
+                GoldenOutcome::Missing => {
+                    failed += 1;
+                    eprintln!("{}: no golden file {} (run with --bless)", r.fixture.display(), r.golden.display());
+                }
+                GoldenOutcome::Differs => {
+                    failed += 1;
+                    let line = r.first_difference.unwrap_or_default();
+                    eprintln!("{}: differs from {} at line {}", r.fixture.display(), r.golden.display(), line);
+                }
+            }
+        }
+        if failed > 0 {
+            anyhow::bail!("{} of {} fixtures do not match their golden files", failed, results.len());
+        }
+        return Ok(());
+    }
+    info!(build = ?ndbc_data::build_info(), "starting");
 
     // Initialize core library with output directory
-    let mut core = NdbcData::new(args.out_dir)?;
+    let mut builder = NdbcData::builder()
+        .out_dir(args.out_dir)
+        .base_url(args.base_url)
+        .timeout(Duration::from_secs(args.timeout))
+        .http_version(args.http_version)
+        .max_retries(args.max_retries)
+        .retry_base_delay(Duration::from_millis(args.retry_base_delay))
+        .retry_max_delay(Duration::from_millis(args.retry_max_delay))
+        .concurrency(args.concurrency)
+        .format(args.format)
+        .parquet_options(ParquetOptions {
+            compression: args.compression,
+            row_group_size: args.row_group_size,
+            statistics: !args.no_statistics,
+        })
+        .window(args.window)
+        .units(args.units)
+        .metadata_filter(args.metadata_filter)
+        .derive(args.derive)
+        .station_pressure(args.add_station_pressure)
+        .station_metadata_columns(args.station_columns)
+        .coords_as(args.coords_as)
+        .partition(args.partition)
+        .duplicate_times(args.duplicate_times)
+        .parse_issues(args.write_parse_issues)
+        .save_raw(args.save_raw)
+        .trend_threshold(args.trend_threshold);
+    #[cfg(feature = "sqlite")]
+    {
+        builder = builder.sqlite_target(ndbc_data::SqliteTarget { path: args.sqlite_path.clone(), table: args.sqlite_table.clone() });
+    }
+    if let Some(columns) = &args.columns {
+        builder = builder.columns(columns);
+    }
+    if let Some(decimals) = args.precision {
+        builder = builder.precision(decimals);
+    }
+    if let Some(revision) = args.revision.or(args.append.then_some(Revision::KeepLatest)) {
+        builder = builder.revision(revision);
+    }
+    if let Some(max_age) = args.incremental {
+        builder = builder.incremental(max_age);
+    }
+    if args.strict {
+        builder = builder.strict(StrictLimits {
+            max_malformed_lines: args.max_malformed_lines,
+            max_token_mismatches: args.max_token_mismatches,
+            max_duplicate_times: args.max_duplicate_times,
+        });
+    }
+    if args.json_schema {
+        // Describe the records these options write, without preparing the output directory.
+        println!("{}", serde_json::to_string_pretty(&builder.in_memory().build()?.output_json_schema())?);
+        return Ok(());
+    }
+    let mut core = builder.build()?;
+
+    if let Some(Command::Export { format, out }) = &args.command {
+        // Read-only: a shared lock keeps writers out without blocking other readers.
+        let _lock = core.lock_archive_shared(args.wait_lock).await?;
+        let rows = if out.as_os_str() == "-" {
+            core.export(*format, std::io::BufWriter::new(std::io::stdout().lock()))?
+        } else {
+            core.export(*format, std::io::BufWriter::new(std::fs::File::create(out)?))?
+        };
+        info!(rows, "export finished");
+        return Ok(());
+    }
 
-    // Fetch fresh station metadata every run.
-    core.fetch_station_metadata().await?;
+    // Writing runs hold the exclusive archive lock throughout so overlapping runs cannot
+    // interleave writes. Commands that only read the archive take a shared lock, after an
+    // exclusive one while the metadata cache in the output directory may be rewritten.
+    let read_only = args.dry_run || matches!(args.command, Some(Command::Metadata { .. } | Command::Coords { .. } | Command::Report { .. }));
+    let writes_cache = args.metadata_source == MetadataSource::StationMetadata && (args.refresh_metadata || args.metadata_max_age.is_some());
+    let mut _lock = if read_only && !writes_cache {
+        core.lock_archive_shared(args.wait_lock).await?
+    } else {
+        core.lock_archive(args.wait_lock).await?
+    };
+
+    // Fetch station metadata, from the on-disk cache when allowed and fresh.
+    match (args.metadata_source, args.metadata_max_age) {
+        (MetadataSource::StationMetadata, _) if args.refresh_metadata => core.force_refresh().await?,
+        (MetadataSource::StationMetadata, Some(max_age)) => core.fetch_station_metadata_cached(max_age).await?,
+        (source, _) => core.fetch_metadata_from(source).await?,
+    }
+    if read_only && writes_cache {
+        drop(_lock);
+        _lock = core.lock_archive_shared(args.wait_lock).await?;
+    }
+
+    if let Some(Command::Metadata { format, out }) = &args.command {
+        if out.as_os_str() == "-" {
+            core.write_station_metadata(*format, std::io::BufWriter::new(std::io::stdout().lock()))?;
+        } else {
+            core.write_station_metadata(*format, std::io::BufWriter::new(std::fs::File::create(out)?))?;
+        }
+        return Ok(());
+    }
+
+    if let Some(Command::Coords { ids, format }) = &args.command {
+        let missing = core.write_station_coords(ids, *format, std::io::stdout().lock())?;
+        if *format != CoordsFormat::Table {
+            for id in missing {
+                eprintln!("{}: not found", id);
+            }
+        }
+        return Ok(());
+    }
+
+    if let Some(Command::Report { near: (lat, lon), radius_km, last, out }) = &args.command {
+        let opts = SiteReportOptions { lat: *lat, lon: *lon, radius_km: *radius_km, last: *last };
+        let index = core.site_report(&opts, out).await?;
+        info!(
+            stations = index.stations.len(),
+            failures = index.failures.len(),
+            artifacts = index.artifacts.len(),
+            dir = %out.display(),
+            "site report written"
+        );
+        return Ok(());
+    }
 
     // Determine stations to process. If none specified, process all from metadata.
-    let stations: Vec<String> = if args.stations.is_empty() {
+    // Ids, groups and the bounding box add up; --near and the all-stations default stand alone.
+    let mut sources = Vec::new();
+    if !args.stations.is_empty() {
+        sources.push((SelectionMethod::Ids, args.stations.clone()));
+    }
+    for name in &args.group {
+        let group = station_group(name).map(|g| g.stations).unwrap_or_default();
+        info!(group = %name, count = group.len(), "selected station group");
+        sources.push((SelectionMethod::Group, group.iter().map(|id| id.to_string()).collect()));
+    }
+    if let Some([min_lat, min_lon, max_lat, max_lon]) = args.bbox {
+        let inside = core.stations_in_bbox(min_lat, min_lon, max_lat, max_lon);
+        info!(count = inside.len(), "selected stations inside bounding box");
+        sources.push((SelectionMethod::Bbox, inside));
+    }
+    let mut sources = sources.into_iter();
+    let selection = if let Some((method, ids)) = sources.next() {
+        sources.fold(StationSelection::new(method, ids), |selection, (method, ids)| selection.extend(method, ids))
+    } else if let Some((lat, lon)) = args.near {
+        let nearest = core.stations_within_km(lat, lon, f64::INFINITY);
+        for (station, km) in nearest.iter().take(args.count) {
+            info!(station = %station, distance_km = format!("{:.1}", km), "selected nearby station");
+        }
+        let ids = nearest.into_iter().map(|(station, _)| station).collect();
+        StationSelection::new(SelectionMethod::Near, ids).limit(args.count)
+    } else {
         let all = core.all_station_ids();
         info!(count = all.len(), "no stations specified; defaulting to all stations in metadata");
-        all
-    } else {
-        args.stations
+        StationSelection::new(SelectionMethod::All, all)
     };
 
+    // Collapse repeated ids and narrow by owner/program.
+    let mut filter = StationFilter::new();
+    if let Some(owner) = &args.owner {
+        filter = filter.owner(owner);
+    }
+    if let Some(program) = &args.program {
+        filter = filter.program(program);
+    }
+    let mut selection = selection.dedup();
+    if filter != StationFilter::new() {
+        selection = selection.filter(|id| core.station(id).is_some_and(|s| filter.matches(s)));
+        info!(count = selection.stations.len(), "selected stations by owner/program");
+    }
+    let stations = selection.stations.clone();
+
+    if args.dry_run {
+        print_stations(&core, &stations);
+        return Ok(());
+    }
+
     // Process each requested station.
-    let mut successes = 0usize;
-    let mut failures: Vec<(String, String)> = Vec::new();
-
-    for station in &stations {
-        match core.fetch_and_save_station(station).await {
-            Ok(_) => successes += 1,
-            Err(e) => {
-                warn!(station = %station, error = %e, "failed to process station");
-                failures.push((station.clone(), format!("{}", e)));
+    let core = &core;
+    let combine = args.combine || args.combined.is_some();
+    let progress = progress_bar(stations.len());
+    let (mut succeeded, mut failed) = (0, 0);
+    let results: Vec<_> = stream::iter(&stations)
+        .map(|station| async move {
+            let mut opts = FetchOptions::default();
+            if let Some(secs) = args.station_timeout {
+                opts = opts.with_timeout(Duration::from_secs(secs));
+            }
+            let result = if combine {
+                core.fetch_station_df_with(station, &opts)
+                    .await
+                    .map(|df| (core.check_columns(station, &df), station_summary(&df), Output::Pending(df)))
+            } else {
+                core.fetch_and_save_station_checked(station, &opts)
+                    .await
+                    .map(|saved| (saved.check, saved.summary, Output::Saved(saved.entry)))
+            };
+            (station, result)
+        })
+        .buffer_unordered(core.concurrency())
+        .inspect(|(station, result)| {
+            match result {
+                Ok(_) => succeeded += 1,
+                Err(_) => failed += 1,
+            }
+            progress.set_message(format!("{} ({} ok, {} failed)", station, succeeded, failed));
+            progress.inc(1);
+        })
+        .collect()
+        .await;
+    progress.finish_and_clear();
+
+    let mut report = BatchReport { selection: Some(selection), ..Default::default() };
+    let mut frames = Vec::new();
+    let mut index = Vec::new();
+    for (station, result) in results {
+        if let Err(e) = &result {
+            warn!(station = %station, error = %e, "failed to process station");
+        }
+        report.record(station, &result);
+        if let Ok((check, summary, output)) = result {
+            report.record_column_check(station, check);
+            if args.report {
+                report.record_summary(station, summary);
+            }
+            match output {
+                Output::Saved(entry) => index.push(entry),
+                Output::Pending(df) => frames.push((station, df)),
             }
         }
     }
-
-    info!(%successes, failures = failures.len(), "done");
-    if !failures.is_empty() {
-        eprintln!("Warnings:");
-        for (st, err) in failures {
-            eprintln!("- {}: {}", st, err);
+    if !frames.is_empty() {
+        let frames_only = frames.iter().map(|(_, df)| df.clone()).collect();
+        let path = match &args.combined {
+            Some(path) => core.save_combined_to(frames_only, path)?,
+            None => core.save_combined(frames_only)?,
+        };
+        for (station, df) in &frames {
+            index.push(core.index_entry(station, df, path.clone())?);
         }
     }
+    if args.write_index {
+        core.save_index(&index)?;
+    }
+
+    info!(successes = report.succeeded.len(), failures = report.failures.len(), "done");
+    if !report.failures.is_empty() || report.has_column_findings() {
+        eprint!("{}", report.render());
+    }
+    if args.report {
+        eprint!("{}", report.render_quality());
+    }
+    if args.summary_json {
+        println!("{}", report.to_json());
+    }
     Ok(())
 }
 
+/// Print one tab-separated line per station for `--dry-run`: id, latitude, longitude, whether the
+/// metadata lists a met (realtime) feed, and name. Stations missing from the metadata show empty
+/// fields and `unknown`.
+fn print_stations(core: &NdbcData, stations: &[String]) {
+    println!("station_id\tlatitude\tlongitude\tmet\tname");
+    for id in stations {
+        let station = core.station(id);
+        let coord = |v: Option<f64>| v.map(|v| v.to_string()).unwrap_or_default();
+        let met = match station {
+            Some(s) if s.met => "yes",
+            Some(_) => "no",
+            None => "unknown",
+        };
+        println!(
+            "{}\t{}\t{}\t{}\t{}",
+            id,
+            coord(station.and_then(|s| s.lat)),
+            coord(station.and_then(|s| s.lon)),
+            met,
+            station.and_then(|s| s.name.as_deref()).unwrap_or_default()
+        );
+    }
+}
+
+/// Progress bar advanced per finished station. Only drawn for several stations on an
+/// interactive terminal without `RUST_LOG`, so logs and CI output stay clean; hidden otherwise.
+fn progress_bar(stations: usize) -> ProgressBar {
+    if stations < 2 || !std::io::stdout().is_terminal() || std::env::var_os("RUST_LOG").is_some() {
+        return ProgressBar::hidden();
+    }
+    let style = ProgressStyle::with_template("{bar:40} {pos}/{len} stations {msg}").unwrap_or_else(|_| ProgressStyle::default_bar());
+    ProgressBar::new(stations as u64).with_style(style)
+}
+
+/// What processing one station produced: a saved file, or a frame waiting for `--combine`.
+enum Output {
+    Saved(IndexEntry),
+    Pending(DataFrame),
+}
+
+/// Parse `minlat,minlon,maxlat,maxlon` in decimal degrees.
+fn parse_bbox(s: &str) -> Result<[f64; 4], String> {
+    let parts: Vec<f64> = s
+        .split(',')
+        .map(|p| p.trim().parse::<f64>().map_err(|_| format!("invalid coordinate: {}", p)))
+        .collect::<Result<_, _>>()?;
+    let [min_lat, min_lon, max_lat, max_lon] = parts[..] else {
+        return Err("expected minlat,minlon,maxlat,maxlon".into());
+    };
+    if !(-90.0..=90.0).contains(&min_lat) || !(-90.0..=90.0).contains(&max_lat) || min_lat > max_lat {
+        return Err("latitudes must be within -90..90 with minlat <= maxlat".into());
+    }
+    if !(-180.0..=180.0).contains(&min_lon) || !(-180.0..=180.0).contains(&max_lon) {
+        return Err("longitudes must be within -180..180".into());
+    }
+    Ok([min_lat, min_lon, max_lat, max_lon])
+}
+
+/// Parse `lat,lon` in decimal degrees.
+fn parse_point(s: &str) -> Result<(f64, f64), String> {
+    let (lat, lon) = s.split_once(',').ok_or("expected lat,lon")?;
+    let lat: f64 = lat.trim().parse().map_err(|_| format!("invalid latitude: {}", lat))?;
+    let lon: f64 = lon.trim().parse().map_err(|_| format!("invalid longitude: {}", lon))?;
+    if !(-90.0..=90.0).contains(&lat) || !(-180.0..=180.0).contains(&lon) {
+        return Err("latitude must be within -90..90 and longitude within -180..180".into());
+    }
+    Ok((lat, lon))
+}
+
+/// Parse a duration such as `90`, `30s`, `5m` or `1h` (bare numbers are seconds).
+fn parse_duration(s: &str) -> Result<Duration, String> {
+    let (num, unit) = s.find(|c: char| !c.is_ascii_digit()).map_or((s, ""), |i| s.split_at(i));
+    let n: u64 = num.parse().map_err(|_| format!("invalid duration: {}", s))?;
+    let factor = match unit {
+        "" | "s" => 1,
+        "m" => 60,
+        "h" => 3600,
+        "d" => 86_400,
+        _ => return Err(format!("invalid duration unit in {} (use s, m, h or d)", s)),
+    };
+    let secs = n.checked_mul(factor).ok_or_else(|| format!("invalid duration: {}", s))?;
+    Ok(Duration::from_secs(secs))
+}
+
 fn setup_tracing() {
     let _ = tracing_subscriber::fmt()
         .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
         .with_target(false)
         .try_init();
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_duration_rejects_overflow() {
+        assert_eq!(parse_duration("5m"), Ok(Duration::from_secs(300)));
+        assert_eq!(parse_duration("2d"), Ok(Duration::from_secs(172_800)));
+        assert_eq!(parse_duration("213503982334602d").map_err(|e| e.starts_with("invalid duration")), Err(true));
+        assert_eq!(parse_duration("18446744073709551615h").map_err(|e| e.starts_with("invalid duration")), Err(true));
+    }
+}
@@ -0,0 +1,132 @@
+//! Parquet writer settings (codec, row groups, statistics) applied to every Parquet file written.
+
+use crate::Result;
+use polars::prelude::*;
+use std::fmt;
+use std::io::Write;
+use std::str::FromStr;
+
+/// Compression codec for Parquet data pages.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Compression {
+    Uncompressed,
+    Snappy,
+    Lz4,
+    /// Zstandard at a level from 1 (fastest) to 22 (smallest); `None` uses the codec's default (3).
+    Zstd(Option<i32>),
+}
+
+impl Default for Compression {
+    fn default() -> Self {
+        Compression::Zstd(None)
+    }
+}
+
+impl FromStr for Compression {
+    type Err = String;
+
+    /// Parse `uncompressed` (or `none`), `snappy`, `lz4`, `zstd` or `zstd:<level>`.
+    fn from_str(s: &str) -> Result<Self, String> {
+        let (codec, level) = match s.split_once(':') {
+            Some((codec, level)) => (codec, Some(level)),
+            None => (s, None),
+        };
+        match (codec.to_ascii_lowercase().as_str(), level) {
+            ("uncompressed" | "none", None) => Ok(Compression::Uncompressed),
+            ("snappy", None) => Ok(Compression::Snappy),
+            ("lz4", None) => Ok(Compression::Lz4),
+            ("zstd", None) => Ok(Compression::Zstd(None)),
+            ("zstd", Some(level)) => {
+                let level: i32 = level.parse().map_err(|_| format!("invalid zstd level: {}", level))?;
+                ZstdLevel::try_new(level).map_err(|_| format!("zstd level must be within 1..=22, got {}", level))?;
+                Ok(Compression::Zstd(Some(level)))
+            }
+            (_, Some(_)) => Err(format!("only zstd takes a level: {}", s)),
+            _ => Err(format!("unknown compression {} (use uncompressed, snappy, lz4, zstd or zstd:LEVEL)", s)),
+        }
+    }
+}
+
+impl fmt::Display for Compression {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Compression::Uncompressed => f.write_str("uncompressed"),
+            Compression::Snappy => f.write_str("snappy"),
+            Compression::Lz4 => f.write_str("lz4"),
+            Compression::Zstd(None) => f.write_str("zstd"),
+            Compression::Zstd(Some(level)) => write!(f, "zstd:{}", level),
+        }
+    }
+}
+
+/// Rows per row group when [`ParquetOptions::row_group_size`] is `None`: polars' own default.
+pub(crate) const DEFAULT_ROW_GROUP_SIZE: usize = 512 * 512;
+
+/// How Parquet files are encoded. The default (zstd, polars' row group size, min/max/null-count
+/// statistics) is what earlier versions wrote.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ParquetOptions {
+    pub compression: Compression,
+    /// Rows per row group; `None` uses polars' default of 262,144 (512 × 512) rows, which keeps a
+    /// station file in one row group but splits a large combined file.
+    pub row_group_size: Option<usize>,
+    /// Write min/max/null-count statistics per column chunk, which lets readers skip row groups.
+    pub statistics: bool,
+}
+
+impl Default for ParquetOptions {
+    fn default() -> Self {
+        Self { compression: Compression::default(), row_group_size: None, statistics: true }
+    }
+}
+
+impl ParquetOptions {
+    /// A polars writer to `out` configured with these options. Fails on an out-of-range zstd
+    /// level.
+    pub(crate) fn writer<W: Write>(&self, out: W) -> Result<ParquetWriter<W>> {
+        let compression = match self.compression {
+            Compression::Uncompressed => ParquetCompression::Uncompressed,
+            Compression::Snappy => ParquetCompression::Snappy,
+            Compression::Lz4 => ParquetCompression::Lz4Raw,
+            Compression::Zstd(level) => ParquetCompression::Zstd(level.map(ZstdLevel::try_new).transpose()?),
+        };
+        let statistics = if self.statistics { StatisticsOptions::default() } else { StatisticsOptions::empty() };
+        Ok(ParquetWriter::new(out)
+            .with_compression(compression)
+            .with_statistics(statistics)
+            .with_row_group_size(self.row_group_size))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::coords;
+    use polars_parquet::parquet::compression::Compression as Codec;
+    use polars_parquet::parquet::metadata::KeyValue;
+    use std::io::Cursor;
+
+    /// Codec of every column chunk and rows of every row group in the footer of `bytes`.
+    fn footer(bytes: Vec<u8>) -> Result<(Vec<Codec>, Vec<usize>)> {
+        let metadata = ParquetReader::new(Cursor::new(bytes)).get_metadata()?.clone();
+        let codecs = metadata.row_groups.iter().flat_map(|rg| rg.columns_under_root_iter("x").map(|c| c.compression())).collect();
+        Ok((codecs, metadata.row_groups.iter().map(|rg| rg.num_rows()).collect()))
+    }
+
+    #[test]
+    fn footer_records_codec_and_row_groups() -> Result<()> {
+        let options = ParquetOptions { compression: Compression::Snappy, row_group_size: Some(4), statistics: true };
+        // Row groups hold exactly `row_group_size` rows, with or without key-value metadata.
+        for key_values in [Vec::new(), vec![KeyValue { key: "k".to_string(), value: Some("v".to_string()) }]] {
+            let mut bytes = Vec::new();
+            coords::write_parquet(options.writer(&mut bytes)?, &mut df!("x" => (0..10i64).collect::<Vec<_>>())?, key_values, options.row_group_size)?;
+            assert_eq!(footer(bytes)?, (vec![Codec::Snappy; 3], vec![4, 4, 2]));
+        }
+
+        let mut bytes = Vec::new();
+        let options = ParquetOptions::default();
+        coords::write_parquet(options.writer(&mut bytes)?, &mut df!("x" => (0..10i64).collect::<Vec<_>>())?, Vec::new(), None)?;
+        assert_eq!(footer(bytes)?, (vec![Codec::Zstd], vec![10]));
+        Ok(())
+    }
+}
@@ -0,0 +1,43 @@
+//! Hive-style partitioned layout for saved station files (`station=<id>/date=<YYYY-MM-DD>/`).
+
+use crate::Result;
+use polars::prelude::*;
+use std::collections::BTreeMap;
+use time::{Date, OffsetDateTime};
+
+/// How saved station files are laid out in the output directory.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum PartitionScheme {
+    /// One file per station (and per archive year or month): `<station>.<ext>`.
+    #[default]
+    Flat,
+    /// One file per station and observation day (UTC):
+    /// `station=<station>/date=<YYYY-MM-DD>/part-0.<ext>`. Saving replaces only the days present
+    /// in the new rows.
+    StationDate,
+}
+
+/// File stem, relative to the output directory, of one station-day partition.
+pub(crate) fn partition_stem(station: &str, date: Date) -> String {
+    format!("station={}/date={}/part-0", station, date)
+}
+
+/// Directory holding every partition of `station`.
+pub(crate) fn station_dir(station: &str) -> String {
+    format!("station={}", station)
+}
+
+/// Split `df` into one frame per UTC day of `time_ms`, in date order. Rows keep their order
+/// within a day; rows without a time are dropped.
+pub(crate) fn split_by_date(df: &DataFrame) -> Result<Vec<(Date, DataFrame)>> {
+    let mut days: BTreeMap<Date, Vec<IdxSize>> = BTreeMap::new();
+    for (row, ms) in crate::time_millis(df)?.into_iter().enumerate() {
+        let Some(date) = ms.and_then(|ms| OffsetDateTime::from_unix_timestamp(ms.div_euclid(1000)).ok()).map(|dt| dt.date()) else {
+            continue;
+        };
+        days.entry(date).or_default().push(row as IdxSize);
+    }
+    days.into_iter()
+        .map(|(date, rows)| Ok((date, df.take(&IdxCa::from_vec("row".into(), rows))?)))
+        .collect()
+}
@@ -0,0 +1,138 @@
+//! Where a saved Parquet file came from, kept as key-value metadata in its footer so the file
+//! still says which station, source file and run produced it after it is copied elsewhere.
+
+use crate::{build_info, NdbcData, Result};
+use polars::prelude::*;
+use polars_parquet::parquet::metadata::KeyValue;
+use serde::Serialize;
+use std::fs;
+use std::path::Path;
+use time::format_description::well_known::Rfc3339;
+use time::OffsetDateTime;
+
+const STATION_ID_KEY: &str = "ndbc.station_id";
+const STATION_NAME_KEY: &str = "ndbc.station_name";
+const STATION_OWNER_KEY: &str = "ndbc.station_owner";
+const SOURCE_URL_KEY: &str = "ndbc.source_url";
+const FETCHED_AT_KEY: &str = "ndbc.fetched_at";
+const CRATE_VERSION_KEY: &str = "ndbc.crate_version";
+const GIT_HASH_KEY: &str = "ndbc.git_hash";
+const FEATURES_KEY: &str = "ndbc.features";
+
+/// Provenance stored in the footer of a saved Parquet station file; see
+/// [`read_file_provenance`]. Each field is `None` when the file does not carry it (files written
+/// by earlier versions or other tools carry none).
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct Provenance {
+    pub station_id: Option<String>,
+    /// Station name from the loaded metadata.
+    pub station_name: Option<String>,
+    /// Station owner from the loaded metadata.
+    pub station_owner: Option<String>,
+    /// NDBC file the rows were parsed from; absent for date ranges, which span several.
+    pub source_url: Option<String>,
+    /// When the file was written, UTC.
+    #[serde(with = "time::serde::rfc3339::option")]
+    pub fetched_at: Option<OffsetDateTime>,
+    /// Version of this crate that wrote the file.
+    pub crate_version: Option<String>,
+    /// Short git hash of the source the writer was built from (see [`build_info`](crate::build_info)).
+    pub git_hash: Option<String>,
+    /// Cargo features enabled in the writer; stored comma-separated.
+    pub features: Option<Vec<String>>,
+}
+
+impl Provenance {
+    /// Footer key-value pairs for this provenance, skipping unset fields.
+    fn key_values(&self) -> Vec<KeyValue> {
+        let fetched_at = self.fetched_at.and_then(|t| t.format(&Rfc3339).ok());
+        [
+            (STATION_ID_KEY, self.station_id.clone()),
+            (STATION_NAME_KEY, self.station_name.clone()),
+            (STATION_OWNER_KEY, self.station_owner.clone()),
+            (SOURCE_URL_KEY, self.source_url.clone()),
+            (FETCHED_AT_KEY, fetched_at),
+            (CRATE_VERSION_KEY, self.crate_version.clone()),
+            (GIT_HASH_KEY, self.git_hash.clone()),
+            (FEATURES_KEY, self.features.as_ref().map(|features| features.join(","))),
+        ]
+        .into_iter()
+        .filter_map(|(key, value)| Some(KeyValue { key: key.to_string(), value: Some(value?) }))
+        .collect()
+    }
+}
+
+/// Read the [`Provenance`] from the footer of a Parquet file saved by this crate. Only the
+/// footer is read, not the rows. A timestamp that does not parse is `None`.
+pub fn read_file_provenance(path: &Path) -> Result<Provenance> {
+    let mut reader = ParquetReader::new(fs::File::open(path)?);
+    let key_values = reader.get_metadata()?.key_value_metadata().clone().unwrap_or_default();
+    let value = |key: &str| key_values.iter().find(|kv| kv.key == key).and_then(|kv| kv.value.clone());
+    Ok(Provenance {
+        station_id: value(STATION_ID_KEY),
+        station_name: value(STATION_NAME_KEY),
+        station_owner: value(STATION_OWNER_KEY),
+        source_url: value(SOURCE_URL_KEY),
+        fetched_at: value(FETCHED_AT_KEY).and_then(|t| OffsetDateTime::parse(&t, &Rfc3339).ok()),
+        crate_version: value(CRATE_VERSION_KEY),
+        git_hash: value(GIT_HASH_KEY),
+        features: value(FEATURES_KEY).map(|f| f.split(',').filter(|f| !f.is_empty()).map(str::to_string).collect()),
+    })
+}
+
+impl NdbcData {
+    /// Footer key-value pairs recording `station` (with its name and owner when metadata is
+    /// loaded; `None` for files covering several stations), the `source_url` it was parsed from, the current time and the build's version,
+    /// git hash and features.
+    pub(crate) fn provenance_key_values(&self, station: Option<&str>, source_url: Option<&str>) -> Vec<KeyValue> {
+        let meta = station.and_then(|station| self.station(station));
+        let build = build_info();
+        Provenance {
+            station_id: station.map(str::to_string),
+            station_name: meta.and_then(|s| s.name.clone()),
+            station_owner: meta.and_then(|s| s.owner.clone()),
+            source_url: source_url.map(str::to_string),
+            fetched_at: Some(OffsetDateTime::now_utc()),
+            crate_version: Some(build.version.to_string()),
+            git_hash: Some(build.git_hash.to_string()),
+            features: Some(build.features.iter().map(|f| f.to_string()).collect()),
+        }
+        .key_values()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse_station_metadata, MetadataSource};
+
+    #[test]
+    fn provenance_round_trips_through_the_footer() -> Result<()> {
+        let dir = std::env::temp_dir().join(format!("ndbc-data-provenance-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let mut data = NdbcData::new(&dir)?;
+        data.load_stations(parse_station_metadata(include_bytes!("../data-samples/stationmetadata.xml"))?, MetadataSource::StationMetadata)?;
+        let url = "https://www.ndbc.noaa.gov/data/realtime2/42092.txt";
+        let df = df!("time_ms" => [1_000i64], "WSPD" => [1.0])?;
+        let before = OffsetDateTime::now_utc().replace_nanosecond(0).unwrap_or(OffsetDateTime::UNIX_EPOCH);
+        let entry = data.save_station_frame("42092", df, "42092", url)?;
+
+        let provenance = read_file_provenance(&entry.output_path)?;
+        let build = build_info();
+        assert_eq!(provenance.station_id.as_deref(), Some("42092"));
+        assert_eq!(provenance.station_name.as_deref(), Some("Aransas Pass Channel Entrance S, TX (252)"));
+        assert!(provenance.station_owner.is_some_and(|owner| owner.ends_with("Texas A&M University-Corpus Christi")));
+        assert_eq!(provenance.source_url.as_deref(), Some(url));
+        assert_eq!(provenance.crate_version.as_deref(), Some(env!("CARGO_PKG_VERSION")));
+        assert_eq!(provenance.git_hash.as_deref(), Some(build.git_hash));
+        assert_eq!(provenance.features, Some(build.features.iter().map(|f| f.to_string()).collect()));
+        assert!(provenance.fetched_at.is_some_and(|t| t >= before && t <= OffsetDateTime::now_utc()));
+
+        // Files written by other tools carry none.
+        let plain = dir.join("plain.parquet");
+        ParquetWriter::new(fs::File::create(&plain)?).finish(&mut df!("x" => [1i64])?)?;
+        assert_eq!(read_file_provenance(&plain)?, Provenance::default());
+        fs::remove_dir_all(&dir)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,62 @@
+//! Completeness of a station's rows: how much of each column is actually populated.
+
+use crate::REVISION_COLUMN;
+use polars::prelude::*;
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+/// Columns that identify or place a row rather than measure something; left out of a
+/// [`StationSummary`].
+const ID_COLUMNS: [&str; 8] =
+    ["time_ms", "station_id", "latitude", "longitude", "station_type", "elevation", "anemometer_height", REVISION_COLUMN];
+
+/// Rows, time range and per-column completeness of one station's frame, from
+/// [`station_summary`].
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+pub struct StationSummary {
+    pub rows: usize,
+    /// Earliest and latest `time_ms`; `None` for an empty frame.
+    pub time_min_ms: Option<i64>,
+    pub time_max_ms: Option<i64>,
+    /// Column -> rows with a value.
+    pub non_null: BTreeMap<String, usize>,
+    /// Column -> fraction of rows without a value (`MM`), 0.0 to 1.0; 0.0 for an empty frame.
+    pub null_fraction: BTreeMap<String, f64>,
+}
+
+/// Summarize `df` (a parsed or fetched station frame): row count, time range and, for every
+/// measurement column (met, selected and derived ones; not time, id or position), how many rows
+/// have a value. A column that is present but 100% null points to a dead sensor.
+pub fn station_summary(df: &DataFrame) -> StationSummary {
+    let times = crate::time_millis(df).ok();
+    let mut summary = StationSummary {
+        rows: df.height(),
+        time_min_ms: times.as_ref().and_then(|t| t.min()),
+        time_max_ms: times.as_ref().and_then(|t| t.max()),
+        ..Default::default()
+    };
+    for s in df.get_columns().iter().filter(|s| !ID_COLUMNS.contains(&s.name().as_str())) {
+        let non_null = s.len() - s.null_count();
+        let null_fraction = if s.is_empty() { 0.0 } else { s.null_count() as f64 / s.len() as f64 };
+        summary.non_null.insert(s.name().to_string(), non_null);
+        summary.null_fraction.insert(s.name().to_string(), null_fraction);
+    }
+    summary
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn summary_leaves_out_station_metadata_columns() -> crate::Result<()> {
+        let data = crate::NdbcData::builder().in_memory().station_metadata_columns(true).build()?;
+        let df = data.frame_from_text("41001", include_str!("../data-samples/realtimedata.txt"))?;
+        let summary = station_summary(&df);
+        let columns: Vec<&str> = summary.non_null.keys().map(String::as_str).collect();
+        let mut expected: Vec<&str> = crate::STD_MET_COLUMNS.iter().map(|(name, _)| *name).collect();
+        expected.sort();
+        assert_eq!(columns, expected);
+        Ok(())
+    }
+}
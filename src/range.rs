@@ -0,0 +1,166 @@
+//! Date ranges spanning several NDBC files, and saving them a piece at a time so a multi-year
+//! range never has to fit in memory as one frame.
+
+use crate::{FetchOptions, NdbcData, NdbcError, OutputFormat, Result, REALTIME_DAYS};
+use polars::prelude::*;
+use std::collections::HashSet;
+use std::fmt;
+use std::fs;
+use std::io::Write;
+use std::path::PathBuf;
+use time::{Date, Month, OffsetDateTime};
+use tracing::{info, warn};
+
+/// One file downloaded for a date range.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum RangePiece {
+    /// Yearly historical archive.
+    Year(i32),
+    /// Monthly file of the current year.
+    Month(i32, Month),
+    /// The 45-day realtime file.
+    Realtime,
+}
+
+impl fmt::Display for RangePiece {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RangePiece::Year(year) => write!(f, "{}", year),
+            RangePiece::Month(year, month) => write!(f, "{}-{:02}", year, *month as u8),
+            RangePiece::Realtime => f.write_str("realtime"),
+        }
+    }
+}
+
+/// Files covering `start..=end`, oldest first: yearly archives for past years, monthly files for
+/// completed months of the current year, and the realtime file when the range reaches into its
+/// last 45 days.
+pub(crate) fn range_pieces(start: Date, end: Date) -> Vec<RangePiece> {
+    let today = OffsetDateTime::now_utc().date();
+    let mut pieces = Vec::new();
+    for year in start.year()..=end.year().min(today.year()) {
+        if year < today.year() {
+            pieces.push(RangePiece::Year(year));
+            continue;
+        }
+        // Current year: monthly files for completed months, realtime for the rest.
+        let first = if start.year() == year { start.month() } else { Month::January };
+        let last = if end.year() == year { end.month().min(today.month()) } else { today.month() };
+        let mut month = first;
+        while month != today.month() && month as u8 <= last as u8 {
+            pieces.push(RangePiece::Month(year, month));
+            month = month.next();
+        }
+    }
+    if end >= today - time::Duration::days(REALTIME_DAYS) {
+        pieces.push(RangePiece::Realtime);
+    }
+    pieces
+}
+
+/// `start..=end` as a half-open range of milliseconds since the Unix epoch (UTC midnights).
+pub(crate) fn range_bounds_ms(start: Date, end: Date) -> (i64, i64) {
+    let start_ms = start.midnight().assume_utc().unix_timestamp() * 1000;
+    let end_ms = end.next_day().unwrap_or(end).midnight().assume_utc().unix_timestamp() * 1000;
+    (start_ms, end_ms)
+}
+
+impl NdbcData {
+    /// Save the rows [`fetch_range`](Self::fetch_range) would return as
+    /// `<station>_<start>_<end>.<ext>` in the output directory (e.g.
+    /// `42040_2015-01-01_2024-12-31.parquet`), returning its path.
+    ///
+    /// Parquet output is written a piece at a time: each yearly archive, monthly file or realtime
+    /// file is downloaded, parsed, trimmed to the range and appended as row groups of the
+    /// configured [`row_group_size`](crate::ParquetOptions::row_group_size) (one per piece when
+    /// unset) before the next is fetched. Peak memory is then about one piece (a year of 10-minute
+    /// rows is ~53k rows, roughly 10 MB parsed) plus ~16 bytes per written row for the duplicate
+    /// check, rather than the whole range: 1M rows over twenty years peak at about a fifth of
+    /// what `fetch_range` needs for them. Rows whose time an earlier piece already wrote are
+    /// dropped, so archived values win as in `fetch_range`. Each piece is sorted by time and
+    /// pieces go oldest first; coordinates stay columns regardless of
+    /// [`CoordsAs`](crate::CoordsAs). Other formats are written from the merged frame.
+    pub async fn save_range(&self, station: &str, start: Date, end: Date) -> Result<PathBuf> {
+        self.save_range_with(station, start, end, &FetchOptions::default()).await
+    }
+
+    /// [`save_range`](Self::save_range) bounded by `opts`.
+    pub async fn save_range_with(&self, station: &str, start: Date, end: Date, opts: &FetchOptions) -> Result<PathBuf> {
+        let out_dir = self.out_dir.as_ref().ok_or(NdbcError::NoOutputDir)?;
+        let out_path = out_dir.join(format!("{}_{}_{}.{}", station, start, end, self.format.extension()));
+        if self.format != OutputFormat::Parquet {
+            let df = self.fetch_range_with(station, start, end, opts).await?;
+            self.write_frame_to(df, &out_path, Vec::new())?;
+            return Ok(out_path);
+        }
+        let tmp_path = out_path.with_extension("parquet.part");
+        let written = async {
+            let rows = self.write_range_chunked(station, start, end, opts, fs::File::create(&tmp_path)?).await?;
+            info!(file = %out_path.display(), rows, "wrote range");
+            Ok(fs::rename(&tmp_path, &out_path)?)
+        }
+        .await;
+        if written.is_err() {
+            let _ = fs::remove_file(&tmp_path);
+        }
+        written.map(|_| out_path)
+    }
+
+    /// Stream the pieces of `start..=end` into a Parquet file on `out`, returning the rows
+    /// written. Fails like `fetch_range` when no piece yields rows.
+    async fn write_range_chunked<W: Write>(&self, station: &str, start: Date, end: Date, opts: &FetchOptions, out: W) -> Result<usize> {
+        let (start_ms, end_ms) = range_bounds_ms(start, end);
+        let mut out = Some(out);
+        let mut writer: Option<(polars::io::parquet::write::BatchedWriter<W>, Vec<PlSmallStr>)> = None;
+        let mut seen: HashSet<i64> = HashSet::new();
+        let mut first_err: Option<NdbcError> = None;
+        for piece in range_pieces(start, end) {
+            let text = opts.guard(self.range_piece_text(station, piece)).await;
+            let df = match text.and_then(|t| self.parse_archive(&t)) {
+                Ok(df) => df,
+                Err(e @ (NdbcError::Cancelled | NdbcError::DeadlineExceeded)) => return Err(e),
+                Err(e) => {
+                    warn!(station = %station, piece = %piece, error = %e, "range piece unavailable; skipping");
+                    first_err.get_or_insert(e);
+                    continue;
+                }
+            };
+            // Keep the first row per time across pieces, as the merged frame would.
+            let keep: BooleanChunked = crate::time_millis(&df)?
+                .into_iter()
+                .map(|t| t.is_some_and(|t| (start_ms..end_ms).contains(&t) && seen.insert(t)))
+                .collect();
+            let df = df.filter(&keep)?.sort(["time_ms"], Default::default())?;
+            if df.height() == 0 {
+                continue;
+            }
+            let df = self.with_station_columns(station, df)?;
+
+            // The first piece with rows fixes the schema; later pieces are written in its order.
+            if writer.is_none()
+                && let Some(out) = out.take()
+            {
+                writer = Some((self.parquet.writer(out)?.batched(&df.schema())?, df.get_column_names_owned()));
+            }
+            let Some((batched, names)) = &mut writer else {
+                continue;
+            };
+            let df = df.select(names.iter().cloned())?;
+            let chunk = self.parquet.row_group_size.unwrap_or(df.height()).max(1);
+            for offset in (0..df.height()).step_by(chunk) {
+                let mut part = df.slice(offset as i64, chunk);
+                part.as_single_chunk_par();
+                batched.write_batch(&part)?;
+            }
+            info!(station = %station, piece = %piece, rows = df.height(), "range piece written");
+        }
+        opts.check()?;
+        let Some((batched, _)) = writer else {
+            return Err(first_err.unwrap_or(NdbcError::NoMetRows));
+        };
+        // The range spans several source files, so no single source URL is recorded.
+        let key_values = self.provenance_key_values(Some(station), None);
+        batched.get_writer().lock().unwrap_or_else(|e| e.into_inner()).end(Some(key_values))?;
+        Ok(seen.len())
+    }
+}
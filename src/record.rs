@@ -0,0 +1,109 @@
+//! Typed standard met rows for row-by-row code, read from the parsed frame so they always match
+//! the DataFrame output.
+
+use crate::{parse_std_met, FetchOptions, NdbcData, NdbcError, Result, STD_MET_COLUMNS};
+use polars::prelude::*;
+use serde::{Deserialize, Serialize};
+use time::OffsetDateTime;
+
+/// One standard met observation. Values are `None` where NDBC reported `MM` (or the column is
+/// absent); units are those of the frame it was read from (see
+/// [`STD_MET_COLUMNS`](crate::STD_MET_COLUMNS) for the native ones).
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct StdMetRecord {
+    /// Observation time, UTC; RFC 3339 in serialized form.
+    #[serde(with = "time::serde::rfc3339")]
+    pub timestamp: OffsetDateTime,
+    /// Wind direction, degrees clockwise from true north.
+    pub wdir: Option<u16>,
+    pub wspd: Option<f64>,
+    pub gst: Option<f64>,
+    pub wvht: Option<f64>,
+    pub dpd: Option<f64>,
+    pub apd: Option<f64>,
+    /// Wave direction at the dominant period, degrees clockwise from true north.
+    pub mwd: Option<u16>,
+    pub pres: Option<f64>,
+    pub atmp: Option<f64>,
+    pub wtmp: Option<f64>,
+    pub dewp: Option<f64>,
+    pub vis: Option<f64>,
+    pub ptdy: Option<f64>,
+    pub tide: Option<f64>,
+    /// Empty for text parsed without a station ([`parse_std_met_records`]).
+    pub station_id: String,
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
+}
+
+/// Parse standard met text into records, via [`parse_std_met`]. `station_id` is empty and the
+/// coordinates are `None`; use [`NdbcData::fetch_station_records`] for those.
+pub fn parse_std_met_records(text: &str) -> Result<Vec<StdMetRecord>> {
+    records_from_frame(&parse_std_met(text)?)
+}
+
+/// Read every row of a standard met frame (as returned by [`parse_std_met`] or
+/// [`NdbcData::fetch_station_df`]) as a record. Only `time_ms` is required; other columns that
+/// are missing come out `None` (or an empty `station_id`). Directions outside `0..=360` are
+/// `None`.
+pub fn records_from_frame(df: &DataFrame) -> Result<Vec<StdMetRecord>> {
+    let f64_col = |name: &str| -> Result<Option<Float64Chunked>> {
+        match df.column(name) {
+            Ok(col) => Ok(Some(col.cast(&DataType::Float64)?.f64()?.clone())),
+            Err(_) => Ok(None),
+        }
+    };
+    let value = |col: &Option<Float64Chunked>, i: usize| col.as_ref().and_then(|c| c.get(i));
+    let degrees = |col: &Option<Float64Chunked>, i: usize| {
+        value(col, i).filter(|v| (0.0..=360.0).contains(v)).map(|v| v.round() as u16)
+    };
+    let times = crate::time_millis(df)?;
+    let station_id = df.column("station_id").ok().map(|c| c.str().cloned()).transpose()?;
+    let [wdir, wspd, gst, wvht, dpd, apd, mwd, pres, atmp, wtmp, dewp, vis, ptdy, tide] =
+        STD_MET_COLUMNS.map(|(name, _)| f64_col(name));
+    let (wdir, wspd, gst, wvht, dpd, apd, mwd) = (wdir?, wspd?, gst?, wvht?, dpd?, apd?, mwd?);
+    let (pres, atmp, wtmp, dewp, vis, ptdy, tide) = (pres?, atmp?, wtmp?, dewp?, vis?, ptdy?, tide?);
+    let (latitude, longitude) = (f64_col("latitude")?, f64_col("longitude")?);
+
+    let mut records = Vec::with_capacity(df.height());
+    for i in 0..df.height() {
+        let timestamp = times
+            .get(i)
+            .and_then(|ms| OffsetDateTime::from_unix_timestamp_nanos(ms as i128 * 1_000_000).ok())
+            .ok_or_else(|| NdbcError::Parse(format!("missing or invalid time_ms in row {}", i)))?;
+        records.push(StdMetRecord {
+            timestamp,
+            wdir: degrees(&wdir, i),
+            wspd: value(&wspd, i),
+            gst: value(&gst, i),
+            wvht: value(&wvht, i),
+            dpd: value(&dpd, i),
+            apd: value(&apd, i),
+            mwd: degrees(&mwd, i),
+            pres: value(&pres, i),
+            atmp: value(&atmp, i),
+            wtmp: value(&wtmp, i),
+            dewp: value(&dewp, i),
+            vis: value(&vis, i),
+            ptdy: value(&ptdy, i),
+            tide: value(&tide, i),
+            station_id: station_id.as_ref().and_then(|c| c.get(i)).unwrap_or_default().to_string(),
+            latitude: value(&latitude, i),
+            longitude: value(&longitude, i),
+        });
+    }
+    Ok(records)
+}
+
+impl NdbcData {
+    /// [`fetch_station_df`](Self::fetch_station_df) as typed records, in file order, with the
+    /// configured units, strict limits and coordinates.
+    pub async fn fetch_station_records(&self, station: &str) -> Result<Vec<StdMetRecord>> {
+        self.fetch_station_records_with(station, &FetchOptions::default()).await
+    }
+
+    /// [`fetch_station_records`](Self::fetch_station_records) bounded by `opts`.
+    pub async fn fetch_station_records_with(&self, station: &str, opts: &FetchOptions) -> Result<Vec<StdMetRecord>> {
+        records_from_frame(&self.fetch_station_df_with(station, opts).await?)
+    }
+}
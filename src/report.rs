@@ -0,0 +1,392 @@
+//! End-of-run summary for batch station processing.
+
+use crate::{ColumnCheck, NdbcError, StationSummary};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use time::format_description::well_known::Rfc3339;
+use time::OffsetDateTime;
+
+/// Broad reason a station failed, used to group the batch summary.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FailureCategory {
+    /// NDBC has no data for the station/period (404).
+    NotFound,
+    /// Network trouble (timeouts, connection errors, 5xx, 429); likely to succeed later.
+    Transient,
+    /// The file was fetched but could not be turned into rows.
+    Parse,
+    /// The station was not attempted or was stopped on purpose (cancellation, or still fresh
+    /// with `--incremental`).
+    Skipped,
+    /// Anything else, e.g. failures writing output.
+    Other,
+}
+
+impl FailureCategory {
+    /// All categories, in summary order.
+    pub const ALL: [FailureCategory; 5] = [
+        FailureCategory::NotFound,
+        FailureCategory::Transient,
+        FailureCategory::Parse,
+        FailureCategory::Skipped,
+        FailureCategory::Other,
+    ];
+
+    /// Human-readable heading.
+    pub fn label(&self) -> &'static str {
+        match self {
+            FailureCategory::NotFound => "Not found",
+            FailureCategory::Transient => "Transient network errors",
+            FailureCategory::Parse => "Parse errors",
+            FailureCategory::Skipped => "Skipped",
+            FailureCategory::Other => "Other errors",
+        }
+    }
+
+    /// One-line suggestion for what to do about failures in this category.
+    pub fn hint(&self) -> &'static str {
+        match self {
+            FailureCategory::NotFound => "no data published for these ids; check the station ids or the requested period",
+            FailureCategory::Transient => "likely to succeed on a later run; rerun these stations or raise --max-retries/--timeout",
+            FailureCategory::Parse => "need investigation; the file layout may have changed (see the first diagnostic line)",
+            FailureCategory::Skipped => "not processed by policy or cancellation; rerun them explicitly if needed",
+            FailureCategory::Other => "check the output directory and disk space",
+        }
+    }
+}
+
+impl NdbcError {
+    /// Group this error into a [`FailureCategory`].
+    pub fn category(&self) -> FailureCategory {
+        match self {
+            NdbcError::StationNotFound(_)
+            | NdbcError::HistoricalNotFound { .. }
+            | NdbcError::HistoricalMonthNotFound { .. } => FailureCategory::NotFound,
+            NdbcError::DeadlineExceeded => FailureCategory::Transient,
+            NdbcError::Http(e) => {
+                let transient_status = e.status().is_some_and(|s| s.is_server_error() || s.as_u16() == 429);
+                if transient_status || e.is_timeout() || e.is_connect() || e.is_request() || e.is_body() {
+                    FailureCategory::Transient
+                } else {
+                    FailureCategory::Other
+                }
+            }
+            NdbcError::EmptyData
+            | NdbcError::NoMetRows
+            | NdbcError::ParseAnomalies(_)
+            | NdbcError::Parse(_)
+            | NdbcError::Polars(_) => {
+                FailureCategory::Parse
+            }
+            NdbcError::Cancelled | NdbcError::UpToDate { .. } => FailureCategory::Skipped,
+            NdbcError::Io(_)
+            | NdbcError::IoContext { .. }
+            | NdbcError::NoOutputDir
+            | NdbcError::MergeUnsupported(_)
+            | NdbcError::ArchiveLocked { .. }
+            | NdbcError::ExistingOutput { .. } => FailureCategory::Other,
+            #[cfg(feature = "duckdb")]
+            NdbcError::DuckDb(_) => FailureCategory::Other,
+            #[cfg(feature = "sqlite")]
+            NdbcError::Sqlite(_) => FailureCategory::Other,
+            #[cfg(feature = "object-store")]
+            NdbcError::Store { .. } => FailureCategory::Other,
+        }
+    }
+}
+
+/// How the station list of a run was produced.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SelectionMethod {
+    /// Ids listed explicitly.
+    Ids,
+    /// Every met station inside a bounding box.
+    Bbox,
+    /// The met stations nearest to a point.
+    Near,
+    /// Every met station in the metadata.
+    All,
+    /// The stations of built-in groups ([`STATION_GROUPS`](crate::STATION_GROUPS)).
+    Group,
+    /// Several of the above (ids, groups and a bounding box) added together.
+    Combined,
+}
+
+impl SelectionMethod {
+    /// Human-readable name.
+    pub fn label(&self) -> &'static str {
+        match self {
+            SelectionMethod::Ids => "explicit ids",
+            SelectionMethod::Bbox => "bounding box",
+            SelectionMethod::Near => "nearest to point",
+            SelectionMethod::All => "all stations",
+            SelectionMethod::Group => "station groups",
+            SelectionMethod::Combined => "ids, groups and bounding box combined",
+        }
+    }
+}
+
+/// Provenance of a run's station list, so automated runs can audit what was chosen and why.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct StationSelection {
+    pub method: SelectionMethod,
+    /// Stations the method produced before any narrowing.
+    pub candidates: usize,
+    /// Repeated ids collapsed into one.
+    pub duplicates_removed: usize,
+    /// Candidates dropped by a count limit (`--count` with `--near`).
+    pub truncated: usize,
+    /// Stations dropped by owner/program filters.
+    pub filtered_out: usize,
+    /// The final list, in the order it was processed.
+    pub stations: Vec<String>,
+}
+
+impl StationSelection {
+    /// Start from the `candidates` produced by `method`.
+    pub fn new(method: SelectionMethod, candidates: Vec<String>) -> Self {
+        Self { method, candidates: candidates.len(), duplicates_removed: 0, truncated: 0, filtered_out: 0, stations: candidates }
+    }
+
+    /// Add the `candidates` produced by another `method` after the current ones. Mixing methods
+    /// makes the selection [`SelectionMethod::Combined`]; call [`dedup`](Self::dedup) afterwards.
+    pub fn extend(mut self, method: SelectionMethod, candidates: Vec<String>) -> Self {
+        if self.method != method {
+            self.method = SelectionMethod::Combined;
+        }
+        self.candidates += candidates.len();
+        self.stations.extend(candidates);
+        self
+    }
+
+    /// Drop repeated ids, keeping the first occurrence.
+    pub fn dedup(mut self) -> Self {
+        let before = self.stations.len();
+        let mut seen = std::collections::HashSet::new();
+        self.stations.retain(|id| seen.insert(id.clone()));
+        self.duplicates_removed += before - self.stations.len();
+        self
+    }
+
+    /// Keep at most `limit` stations.
+    pub fn limit(mut self, limit: usize) -> Self {
+        self.truncated += self.stations.len().saturating_sub(limit);
+        self.stations.truncate(limit);
+        self
+    }
+
+    /// Keep only the stations for which `keep` is true.
+    pub fn filter(mut self, keep: impl Fn(&str) -> bool) -> Self {
+        let before = self.stations.len();
+        self.stations.retain(|id| keep(id));
+        self.filtered_out += before - self.stations.len();
+        self
+    }
+}
+
+/// One failed station in a [`BatchReport`].
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct StationFailure {
+    pub station: String,
+    pub category: FailureCategory,
+    /// First line of the error message.
+    pub diagnostic: String,
+}
+
+/// Outcome of processing a batch of stations.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct BatchReport {
+    pub succeeded: Vec<String>,
+    pub failures: Vec<StationFailure>,
+    /// Station id -> columns missing in every row (absent sensors), for stations that have any.
+    pub missing_sensors: BTreeMap<String, Vec<String>>,
+    /// Station id -> columns its station class should report but that were missing in every row.
+    pub failed_sensors: BTreeMap<String, Vec<String>>,
+    /// Station id -> columns its station class never reports but that had values.
+    pub unexpected_columns: BTreeMap<String, Vec<String>>,
+    /// How the station list was chosen, when the caller recorded it.
+    pub selection: Option<StationSelection>,
+    /// Station id -> completeness of its rows, for stations the caller recorded one for.
+    pub quality: BTreeMap<String, StationSummary>,
+}
+
+/// Stable JSON shape of a [`BatchReport`]: every category is always present.
+#[derive(Serialize)]
+struct SummaryJson<'a> {
+    succeeded: usize,
+    failed: usize,
+    categories: Vec<CategoryJson<'a>>,
+    missing_sensors: &'a BTreeMap<String, Vec<String>>,
+    failed_sensors: &'a BTreeMap<String, Vec<String>>,
+    unexpected_columns: &'a BTreeMap<String, Vec<String>>,
+    selection: &'a Option<StationSelection>,
+    quality: &'a BTreeMap<String, StationSummary>,
+}
+
+#[derive(Serialize)]
+struct CategoryJson<'a> {
+    category: FailureCategory,
+    count: usize,
+    hint: &'static str,
+    stations: Vec<&'a StationFailure>,
+}
+
+impl BatchReport {
+    /// Record the outcome for one station.
+    pub fn record<T>(&mut self, station: &str, result: &Result<T, NdbcError>) {
+        match result {
+            Ok(_) => self.succeeded.push(station.to_string()),
+            Err(e) => self.record_failure(station, e.category(), &e.to_string()),
+        }
+    }
+
+    /// Record a failure that did not come from an [`NdbcError`], e.g. a station skipped by policy.
+    pub fn record_failure(&mut self, station: &str, category: FailureCategory, message: &str) {
+        self.failures.push(StationFailure {
+            station: station.to_string(),
+            category,
+            diagnostic: message.lines().next().unwrap_or_default().to_string(),
+        });
+    }
+
+    /// Record the columns a successfully processed station never reported (see
+    /// [`missing_sensors`](crate::missing_sensors)); nothing is recorded when there are none.
+    pub fn record_missing_sensors(&mut self, station: &str, columns: Vec<String>) {
+        if !columns.is_empty() {
+            self.missing_sensors.insert(station.to_string(), columns);
+        }
+    }
+
+    /// Record a station's [`ColumnCheck`]: its absent sensors plus any failed sensors or
+    /// unexpected columns for its station class.
+    pub fn record_column_check(&mut self, station: &str, check: ColumnCheck) {
+        self.record_missing_sensors(station, check.missing);
+        if !check.failed.is_empty() {
+            self.failed_sensors.insert(station.to_string(), check.failed);
+        }
+        if !check.unexpected.is_empty() {
+            self.unexpected_columns.insert(station.to_string(), check.unexpected);
+        }
+    }
+
+    /// Record the [`StationSummary`] of a successfully processed station.
+    pub fn record_summary(&mut self, station: &str, summary: StationSummary) {
+        self.quality.insert(station.to_string(), summary);
+    }
+
+    /// True when any station recorded absent sensors, failed sensors or unexpected columns.
+    pub fn has_column_findings(&self) -> bool {
+        !self.missing_sensors.is_empty() || !self.failed_sensors.is_empty() || !self.unexpected_columns.is_empty()
+    }
+
+    /// Failures in `category`, sorted by station id.
+    pub fn failures_in(&self, category: FailureCategory) -> Vec<&StationFailure> {
+        let mut v: Vec<&StationFailure> = self.failures.iter().filter(|f| f.category == category).collect();
+        v.sort_by(|a, b| a.station.cmp(&b.station));
+        v
+    }
+
+    /// Human-readable summary grouped by category, with a hint per non-empty category.
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+        let _ = writeln!(out, "{} succeeded, {} failed", self.succeeded.len(), self.failures.len());
+        if let Some(sel) = &self.selection {
+            let _ = writeln!(
+                out,
+                "Selection: {} -> {} of {} candidates ({} duplicates, {} over limit, {} filtered out)",
+                sel.method.label(),
+                sel.stations.len(),
+                sel.candidates,
+                sel.duplicates_removed,
+                sel.truncated,
+                sel.filtered_out
+            );
+        }
+        for category in FailureCategory::ALL {
+            let failures = self.failures_in(category);
+            if failures.is_empty() {
+                continue;
+            }
+            let _ = writeln!(out, "{} ({}):", category.label(), failures.len());
+            for f in &failures {
+                match category {
+                    // The message adds nothing for a 404; list ids only.
+                    FailureCategory::NotFound => {
+                        let _ = writeln!(out, "  - {}", f.station);
+                    }
+                    _ => {
+                        let _ = writeln!(out, "  - {}: {}", f.station, f.diagnostic);
+                    }
+                }
+            }
+            let _ = writeln!(out, "  hint: {}", category.hint());
+        }
+        let sections = [
+            ("Absent sensors (missing in every row)", &self.missing_sensors),
+            ("Failed sensors (expected for the station type, missing in every row)", &self.failed_sensors),
+            ("Unexpected columns (never reported by the station type)", &self.unexpected_columns),
+        ];
+        for (heading, stations) in sections {
+            if stations.is_empty() {
+                continue;
+            }
+            let _ = writeln!(out, "{} ({}):", heading, stations.len());
+            for (station, columns) in stations {
+                let _ = writeln!(out, "  - {}: {}", station, columns.join(", "));
+            }
+        }
+        out
+    }
+
+    /// Per-station data quality from the recorded [`StationSummary`]s: rows, time range and the
+    /// share of rows with a value in each column, worst columns first.
+    pub fn render_quality(&self) -> String {
+        let mut out = String::new();
+        let _ = writeln!(out, "Data quality ({} stations):", self.quality.len());
+        let time = |ms: Option<i64>| {
+            ms.and_then(|ms| OffsetDateTime::from_unix_timestamp_nanos(ms as i128 * 1_000_000).ok())
+                .and_then(|dt| dt.format(&Rfc3339).ok())
+                .unwrap_or_else(|| "-".to_string())
+        };
+        for (station, summary) in &self.quality {
+            let _ = writeln!(
+                out,
+                "  - {}: {} rows, {} to {}",
+                station,
+                summary.rows,
+                time(summary.time_min_ms),
+                time(summary.time_max_ms)
+            );
+            let mut columns: Vec<(&String, &f64)> = summary.null_fraction.iter().collect();
+            columns.sort_by(|a, b| b.1.total_cmp(a.1).then_with(|| a.0.cmp(b.0)));
+            let columns: Vec<String> =
+                columns.into_iter().map(|(name, nulls)| format!("{} {:.0}%", name, (1.0 - nulls) * 100.0)).collect();
+            let _ = writeln!(out, "      {}", columns.join(", "));
+        }
+        out
+    }
+
+    /// Summary as pretty-printed JSON with a fixed shape (all categories, always present).
+    pub fn to_json(&self) -> String {
+        let summary = SummaryJson {
+            succeeded: self.succeeded.len(),
+            failed: self.failures.len(),
+            categories: FailureCategory::ALL
+                .iter()
+                .map(|&category| {
+                    let stations = self.failures_in(category);
+                    CategoryJson { category, count: stations.len(), hint: category.hint(), stations }
+                })
+                .collect(),
+            missing_sensors: &self.missing_sensors,
+            failed_sensors: &self.failed_sensors,
+            unexpected_columns: &self.unexpected_columns,
+            selection: &self.selection,
+            quality: &self.quality,
+        };
+        serde_json::to_string_pretty(&summary).unwrap_or_default()
+    }
+}
@@ -0,0 +1,64 @@
+//! Policies for merging a fresh fetch into a station file that already holds earlier fetches.
+
+use crate::{align_columns, Result};
+use polars::prelude::*;
+use std::collections::HashMap;
+
+/// Name of the revision counter column added by [`Revision::KeepBoth`].
+pub const REVISION_COLUMN: &str = "revision";
+
+/// What to do when a fetch contains an observation (same `time_ms`) that is already saved.
+///
+/// NDBC revises realtime values after QC, so the same observation can arrive with different
+/// values on later runs.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, clap::ValueEnum)]
+pub enum Revision {
+    /// Keep the value saved first and ignore later revisions.
+    KeepFirst,
+    /// Replace saved values with the most recent fetch. Also accepted as `merge`.
+    #[value(alias = "merge")]
+    KeepLatest,
+    /// Keep every distinct version of an observation, numbered by a `revision` column
+    /// (0 for the first version seen, counting up with each correction).
+    KeepBoth,
+}
+
+/// Merge `fresh` rows into `existing` ones according to `policy`, sorted by time.
+///
+/// Both frames are aligned to `columns` first, so files written by older versions or with other
+/// options still merge, and so is the result. An existing `revision` column only survives with
+/// [`Revision::KeepBoth`]; with other policies a [`REVISION_COLUMN`] in `columns` is all-null.
+pub(crate) fn merge(existing: DataFrame, fresh: DataFrame, policy: Revision, columns: &[(&str, DataType)]) -> Result<DataFrame> {
+    // Put earlier versions of an observation first so they keep their revision numbers.
+    let existing = match existing.column(REVISION_COLUMN) {
+        Ok(_) => existing.sort(["time_ms", REVISION_COLUMN], SortMultipleOptions::default())?,
+        Err(_) => existing,
+    };
+    // The revision counter is recomputed below, so rows are compared on the data columns only.
+    let data_columns: Vec<(&str, DataType)> = columns.iter().filter(|(name, _)| *name != REVISION_COLUMN).cloned().collect();
+    let stacked = align_columns(&existing, &data_columns)?.vstack(&align_columns(&fresh, &data_columns)?)?;
+    let merged = match policy {
+        Revision::KeepFirst => stacked.unique_stable(Some(&["time_ms".to_string()]), UniqueKeepStrategy::First, None)?,
+        Revision::KeepLatest => stacked.unique_stable(Some(&["time_ms".to_string()]), UniqueKeepStrategy::Last, None)?,
+        Revision::KeepBoth => {
+            // Identical re-fetches collapse; differing versions are numbered in arrival order.
+            let mut df = stacked.unique_stable(None, UniqueKeepStrategy::First, None)?;
+            let mut seen: HashMap<Option<i64>, u32> = HashMap::new();
+            let revisions: Vec<u32> = crate::time_millis(&df)?
+                .into_iter()
+                .map(|t| {
+                    let n = seen.entry(t).or_insert(0);
+                    *n += 1;
+                    *n - 1
+                })
+                .collect();
+            df.with_column(Series::new(REVISION_COLUMN.into(), revisions))?;
+            df.sort(["time_ms", REVISION_COLUMN], SortMultipleOptions::default())?
+        }
+    };
+    let merged = match policy {
+        Revision::KeepBoth => merged,
+        _ => merged.sort(["time_ms"], SortMultipleOptions::default())?,
+    };
+    align_columns(&merged, columns)
+}
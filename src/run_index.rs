@@ -0,0 +1,83 @@
+//! Per-run catalog of saved stations: where each one was written and the time range it covers.
+
+use crate::{coords, NdbcData, NdbcError, Result};
+use polars::prelude::*;
+use std::fs;
+use std::path::PathBuf;
+use tracing::info;
+
+/// File stem of the catalog written by [`NdbcData::save_index`].
+pub const INDEX_STEM: &str = "index";
+
+/// One row of the run catalog: a station, the file holding its rows and what they cover.
+#[derive(Clone, Debug, PartialEq)]
+pub struct IndexEntry {
+    pub station_id: String,
+    /// Name and current position from the loaded metadata; `None` when it does not list them.
+    pub name: Option<String>,
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
+    /// File the rows were written to (the `combined` file when stations were combined).
+    pub output_path: PathBuf,
+    /// Earliest and latest `time_ms` in the file for this station; `None` when it has no rows.
+    pub first_time_ms: Option<i64>,
+    pub last_time_ms: Option<i64>,
+    pub row_count: usize,
+}
+
+impl NdbcData {
+    /// Describe `df`, a station frame written to `output_path`, as an [`IndexEntry`]. Only the
+    /// rows of `station` are counted, so a combined frame can be passed as is.
+    pub fn index_entry(&self, station: &str, df: &DataFrame, output_path: PathBuf) -> Result<IndexEntry> {
+        let times = crate::time_millis(df)?;
+        let times = match df.column("station_id") {
+            Ok(ids) => times.filter(&ids.str()?.equal(station))?,
+            Err(_) => times.clone(),
+        };
+        let meta = self.station(station);
+        Ok(IndexEntry {
+            station_id: station.to_string(),
+            name: meta.and_then(|s| s.name.clone()),
+            latitude: meta.and_then(|s| s.lat),
+            longitude: meta.and_then(|s| s.lon),
+            output_path,
+            first_time_ms: times.min(),
+            last_time_ms: times.max(),
+            row_count: times.len(),
+        })
+    }
+
+    /// Write `entries` to `index.parquet` in the output directory, sorted by station id,
+    /// replacing it atomically. Columns are `station_id`, `name`, `latitude`, `longitude`,
+    /// `output_path`, `first_time_ms`, `last_time_ms` and `row_count`.
+    pub fn save_index(&self, entries: &[IndexEntry]) -> Result<()> {
+        let out_dir = self.out_dir.as_ref().ok_or(NdbcError::NoOutputDir)?;
+        let mut entries: Vec<&IndexEntry> = entries.iter().collect();
+        entries.sort_by(|a, b| a.station_id.cmp(&b.station_id));
+        let mut df = DataFrame::new(vec![
+            Series::new("station_id".into(), entries.iter().map(|e| e.station_id.as_str()).collect::<Vec<_>>()),
+            Series::new("name".into(), entries.iter().map(|e| e.name.as_deref()).collect::<Vec<_>>()),
+            Series::new("latitude".into(), entries.iter().map(|e| e.latitude).collect::<Vec<_>>()),
+            Series::new("longitude".into(), entries.iter().map(|e| e.longitude).collect::<Vec<_>>()),
+            Series::new(
+                "output_path".into(),
+                entries.iter().map(|e| e.output_path.to_string_lossy().into_owned()).collect::<Vec<_>>(),
+            ),
+            Series::new("first_time_ms".into(), entries.iter().map(|e| e.first_time_ms).collect::<Vec<_>>()),
+            Series::new("last_time_ms".into(), entries.iter().map(|e| e.last_time_ms).collect::<Vec<_>>()),
+            Series::new("row_count".into(), entries.iter().map(|e| e.row_count as u64).collect::<Vec<_>>()),
+        ])?;
+        let out_path = out_dir.join(format!("{}.parquet", INDEX_STEM));
+        let tmp_path = out_path.with_extension("parquet.part");
+        info!(file = %out_path.display(), rows = df.height(), "writing run index");
+        let written = (|| -> Result<()> {
+            let key_values = self.provenance_key_values(None, None);
+            coords::write_parquet(self.parquet.writer(fs::File::create(&tmp_path)?)?, &mut df, key_values, self.parquet.row_group_size)?;
+            Ok(fs::rename(&tmp_path, &out_path)?)
+        })();
+        if written.is_err() {
+            let _ = fs::remove_file(&tmp_path);
+        }
+        written
+    }
+}
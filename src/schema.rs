@@ -0,0 +1,249 @@
+//! Column definitions for the standard met output, the columns each station class is expected
+//! to report, and the JSON Schema derived from them.
+
+use crate::{NdbcData, Units, REVISION_COLUMN, STATION_PRESSURE_COLUMN};
+use polars::prelude::{DataFrame, DataType, Field, Schema};
+use serde::Serialize;
+use serde_json::{json, Map, Value};
+use std::collections::HashMap;
+
+/// Standard met columns kept by [`parse_std_met`](crate::parse_std_met), in output order, with
+/// a description including units. Every column is an `f64` that is null when NDBC reports `MM`.
+pub const STD_MET_COLUMNS: [(&str, &str); 14] = [
+    ("WDIR", "Wind direction, degrees clockwise from true north"),
+    ("WSPD", "Wind speed, m/s"),
+    ("GST", "Peak gust speed, m/s"),
+    ("WVHT", "Significant wave height, m"),
+    ("DPD", "Dominant wave period, s"),
+    ("APD", "Average wave period, s"),
+    ("MWD", "Wave direction at the dominant period, degrees clockwise from true north"),
+    ("PRES", "Sea level pressure, hPa"),
+    ("ATMP", "Air temperature, degrees C"),
+    ("WTMP", "Sea surface temperature, degrees C"),
+    ("DEWP", "Dewpoint temperature, degrees C"),
+    ("VIS", "Station visibility, nautical miles"),
+    ("PTDY", "Pressure tendency, signed hPa over 3 hours"),
+    ("TIDE", "Water level, ft above or below MLLW"),
+];
+
+/// Broad class of a station, from its metadata station type, used to decide which columns a
+/// fetch should populate.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum StationClass {
+    /// Moored buoys: waves and water temperature, but no tide gauge.
+    Buoy,
+    /// C-MAN and other fixed coastal stations: meteorological sensors only, never waves.
+    Cman,
+}
+
+impl StationClass {
+    /// Classify a metadata station type (`buoy`, `fixed`, or a `station_table.txt` type such as
+    /// `3-meter foil buoy` or `C-MAN Station`). Other types (DART, tides-only, ...) are `None`.
+    pub fn from_station_type(station_type: &str) -> Option<Self> {
+        let t = station_type.to_ascii_lowercase();
+        if t.contains("buoy") {
+            Some(StationClass::Buoy)
+        } else if t.contains("c-man") || t == "fixed" {
+            Some(StationClass::Cman)
+        } else {
+            None
+        }
+    }
+}
+
+/// Standard met columns a station class should and should not report.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ColumnExpectation {
+    /// Columns every station of the class reports; all-null means the sensor likely failed.
+    pub expected: Vec<String>,
+    /// Columns the class never reports; any value means the file format drifted.
+    pub absent: Vec<String>,
+}
+
+impl ColumnExpectation {
+    /// Expectation from `expected` and `absent` column names.
+    pub fn new(expected: &[&str], absent: &[&str]) -> Self {
+        Self {
+            expected: expected.iter().map(|c| c.to_string()).collect(),
+            absent: absent.iter().map(|c| c.to_string()).collect(),
+        }
+    }
+}
+
+/// [`ColumnExpectation`] per [`StationClass`]. The defaults only list columns that are reliably
+/// reported or reliably absent; replace them with [`set`](Self::set).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ExpectedColumns {
+    by_class: HashMap<StationClass, ColumnExpectation>,
+}
+
+impl Default for ExpectedColumns {
+    fn default() -> Self {
+        let mut by_class = HashMap::new();
+        by_class.insert(
+            StationClass::Buoy,
+            ColumnExpectation::new(&["WDIR", "WSPD", "GST", "WVHT", "DPD", "APD", "PRES", "ATMP", "WTMP"], &["TIDE"]),
+        );
+        by_class.insert(
+            StationClass::Cman,
+            ColumnExpectation::new(&["WDIR", "WSPD", "GST", "PRES", "ATMP"], &["WVHT", "DPD", "APD", "MWD"]),
+        );
+        Self { by_class }
+    }
+}
+
+impl ExpectedColumns {
+    /// Replace the expectation for `class`.
+    pub fn set(mut self, class: StationClass, expectation: ColumnExpectation) -> Self {
+        self.by_class.insert(class, expectation);
+        self
+    }
+
+    /// The expectation for `class`, if any.
+    pub fn get(&self, class: StationClass) -> Option<&ColumnExpectation> {
+        self.by_class.get(&class)
+    }
+
+    /// Compare the standard met columns populated in `df` with the expectation for `class`.
+    /// `missing` is always filled; `failed` and `unexpected` only when `class` has an expectation.
+    pub fn check(&self, class: Option<StationClass>, df: &DataFrame) -> ColumnCheck {
+        let missing = crate::missing_sensors(df);
+        let Some(expectation) = class.and_then(|c| self.get(c)).filter(|_| df.height() > 0) else {
+            return ColumnCheck { missing, ..Default::default() };
+        };
+        let populated = |name: &str| df.column(name).is_ok_and(|s| s.null_count() < df.height());
+        // Columns left out of the frame (see `NdbcData::with_columns`) are not checked.
+        let empty = |name: &str| df.column(name).is_ok_and(|s| s.null_count() == df.height());
+        ColumnCheck {
+            failed: expectation.expected.iter().filter(|c| empty(c)).cloned().collect(),
+            unexpected: expectation.absent.iter().filter(|c| populated(c)).cloned().collect(),
+            missing,
+        }
+    }
+}
+
+/// Outcome of comparing a fetch's columns with [`ExpectedColumns`].
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct ColumnCheck {
+    /// Standard met columns missing in every row (see [`missing_sensors`](crate::missing_sensors)).
+    pub missing: Vec<String>,
+    /// Expected columns missing in every row: the sensor likely failed.
+    pub failed: Vec<String>,
+    /// Columns the station class never reports that have values: likely format drift.
+    pub unexpected: Vec<String>,
+}
+
+/// Polars schema of the frames saved and returned with default settings, in column order:
+/// `time_ms` (Datetime in milliseconds, UTC), every [`STD_MET_COLUMNS`] column
+/// as Float64, `station_id` (String), then `latitude` and `longitude` (Float64).
+///
+/// Every station gets exactly this schema, whatever its file reports: a column the station never
+/// fills is written as all-null Float64, so Parquet files from different stations and runs can be
+/// read together without schema merging. Options that select or add columns change it; see
+/// [`NdbcData::output_schema`](crate::NdbcData::output_schema).
+pub fn std_met_schema() -> Schema {
+    crate::standard_columns().into_iter().map(|(name, dtype)| Field::new(name.into(), dtype)).collect()
+}
+
+/// Descriptions of the columns options add after the standard ones, for the JSON Schema.
+const EXTRA_COLUMN_DESCRIPTIONS: [(&str, &str); 8] = [
+    ("station_type", "Station type from the metadata, e.g. buoy or fixed"),
+    ("elevation", "Site elevation above mean sea level from the metadata, m"),
+    ("anemometer_height", "Anemometer height above the site on the observation date, m"),
+    ("wind_chill", "NWS wind chill, degrees C"),
+    ("heat_index", "NWS heat index, degrees C"),
+    ("pressure_trend", "3-hour pressure trend from PTDY: rising, falling or steady"),
+    (STATION_PRESSURE_COLUMN, "Pressure at the station elevation, hPa"),
+    (REVISION_COLUMN, "Version of the observation: 0 as first saved, counting up for each differing re-fetch"),
+];
+
+/// JSON Schema (draft 2020-12) for one record of the JSON Lines output with default settings;
+/// [`NdbcData::output_json_schema`] gives the schema for an instance's options.
+pub fn output_json_schema() -> Value {
+    json_schema_for(&crate::standard_columns(), Units::Native)
+}
+
+impl NdbcData {
+    /// JSON Schema (draft 2020-12) for one record of the JSON Lines output this instance writes,
+    /// built from the same column list as [`output_schema`](Self::output_schema): `time` in place
+    /// of `time_ms`, lowercase names, and units in the descriptions following
+    /// [`NdbcDataBuilder::units`](crate::NdbcDataBuilder::units). Every field is always present.
+    pub fn output_json_schema(&self) -> Value {
+        json_schema_for(&self.output_columns(), self.units)
+    }
+}
+
+/// JSON Schema for records with `columns` (as in [`NdbcData::output_columns`]) in `units`.
+fn json_schema_for(columns: &[(&str, DataType)], units: Units) -> Value {
+    let mut properties = Map::new();
+    for (name, dtype) in columns {
+        if *name == "time_ms" {
+            properties.insert(
+                "time".into(),
+                json!({ "type": "string", "format": "date-time", "description": "Observation time (UTC, RFC 3339)" }),
+            );
+            continue;
+        }
+        let json_type = match dtype {
+            DataType::String => "string",
+            DataType::Boolean => "boolean",
+            dtype if dtype.is_integer() => "integer",
+            _ => "number",
+        };
+        // Only the station id and the revision counter are set on every row.
+        let json_type = if ["station_id", REVISION_COLUMN].contains(name) { json!(json_type) } else { json!([json_type, "null"]) };
+        properties.insert(name.to_lowercase(), json!({ "type": json_type, "description": column_description(name, units) }));
+    }
+
+    let required: Vec<Value> = properties.keys().cloned().map(Value::String).collect();
+    json!({
+        "$schema": "https://json-schema.org/draft/2020-12/schema",
+        "title": "NDBC standard meteorological observation",
+        "type": "object",
+        "properties": properties,
+        "required": required,
+        "additionalProperties": false,
+    })
+}
+
+/// Description of output column `name`, with the unit changed to the one `units` converts it to.
+fn column_description(name: &str, units: Units) -> String {
+    let description = match name {
+        "station_id" => "NDBC station identifier".to_string(),
+        "latitude" => "Station latitude on the observation date, decimal degrees north".to_string(),
+        "longitude" => "Station longitude on the observation date, decimal degrees east".to_string(),
+        _ => STD_MET_COLUMNS
+            .iter()
+            .chain(&EXTRA_COLUMN_DESCRIPTIONS)
+            .find(|(column, _)| *column == name)
+            .map_or_else(|| format!("{} column of the NDBC file", name), |(_, description)| description.to_string()),
+    };
+    match (units.unit(name), Units::Native.unit(name), description.rsplit_once(", ")) {
+        (Some(unit), Some(native), Some((label, _))) if unit != native => format!("{}, {}", label, unit),
+        _ => description,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Result;
+
+    #[test]
+    fn json_schema_follows_instance_options() -> Result<()> {
+        let data = NdbcData::builder().in_memory().units(Units::Imperial).derive(true).station_metadata_columns(true).build()?;
+        let df = data.frame_from_text("41001", include_str!("../data-samples/realtimedata.txt"))?;
+        let record: Vec<String> = crate::to_text_frame(&df, None)?.get_column_names().iter().map(|name| name.to_string()).collect();
+
+        let schema = data.output_json_schema();
+        let mut fields: Vec<String> = schema["properties"].as_object().map(|p| p.keys().cloned().collect()).unwrap_or_default();
+        let mut expected = record.clone();
+        fields.sort();
+        expected.sort();
+        assert_eq!(fields, expected);
+        assert_eq!(schema["properties"]["wspd"]["description"], "Wind speed, kn");
+        assert_eq!(schema["properties"]["pressure_trend"]["type"], json!(["string", "null"]));
+        assert_eq!(schema["properties"]["station_id"]["type"], "string");
+        Ok(())
+    }
+}
@@ -0,0 +1,407 @@
+//! One-shot site report: select the stations around a point, fetch their recent data and write a
+//! directory of artifacts plus an `index.json` describing them.
+
+use crate::export::csv_field;
+use crate::{coords, missing_sensors, time_millis, BatchReport, NdbcData, NdbcError, Result, StationFailure, STD_MET_COLUMNS};
+use futures::StreamExt;
+use polars::prelude::*;
+use serde::Serialize;
+use std::fmt::Write as _;
+use std::fs;
+use std::path::Path;
+use std::time::Duration;
+use time::format_description::well_known::Rfc3339;
+use time::OffsetDateTime;
+
+/// Name of the machine-readable index written at the top of a site report directory.
+pub const INDEX_FILE: &str = "index.json";
+
+/// Thresholds counted in `exceedances.csv`, in NDBC's published units: a strong breeze (Beaufort
+/// 6) of sustained wind, gale-force gusts and very rough seas (Douglas 6). They are converted
+/// along with the data under other [`Units`](crate::Units).
+pub const EXCEEDANCE_THRESHOLDS: [(&str, f64); 3] = [("WSPD", 10.8), ("GST", 17.2), ("WVHT", 4.0)];
+
+/// Lower bounds of the wind rose speed bins, m/s; the last bin is open-ended.
+const WIND_ROSE_BINS: [f64; 6] = [0.0, 2.0, 4.0, 6.0, 8.0, 10.0];
+
+/// Lower bounds of the wave rose height bins, m; the last bin is open-ended.
+const WAVE_ROSE_BINS: [f64; 5] = [0.0, 1.0, 2.0, 3.0, 4.0];
+
+/// Compass sectors of a rose, clockwise from north, 22.5 degrees each.
+const SECTORS: [&str; 16] = ["N", "NNE", "NE", "ENE", "E", "ESE", "SE", "SSE", "S", "SSW", "SW", "WSW", "W", "WNW", "NW", "NNW"];
+
+/// A step between consecutive rows longer than this many usual intervals is a gap.
+const GAP_FACTOR: i64 = 2;
+
+/// A station whose latest row is older than this when the report is built is flagged stale.
+const STALE_AFTER: time::Duration = time::Duration::hours(6);
+
+/// What a site report covers.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SiteReportOptions {
+    /// Center of the site, decimal degrees.
+    pub lat: f64,
+    pub lon: f64,
+    /// Stations within this great-circle distance of the center are included (met stations unless
+    /// the [`MetadataFilter`](crate::MetadataFilter) says otherwise).
+    pub radius_km: f64,
+    /// How far back to fetch, rounded to whole days (the window ends today, UTC).
+    pub last: Duration,
+}
+
+/// One file in a site report, as listed in `index.json`.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Artifact {
+    /// Stable identifier, e.g. `stations`, `summary` or `observations`.
+    pub kind: &'static str,
+    /// Path relative to the report directory, with `/` separators.
+    pub path: String,
+    pub format: &'static str,
+    /// The station the file belongs to; `None` for files covering every station.
+    pub station: Option<String>,
+    /// Data rows in the file.
+    pub rows: usize,
+}
+
+/// Contents of `index.json`: the inputs, the stations that were selected and every artifact.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct SiteReportIndex {
+    pub lat: f64,
+    pub lon: f64,
+    pub radius_km: f64,
+    /// First and last day fetched (UTC, `YYYY-MM-DD`).
+    pub start: String,
+    pub end: String,
+    /// Selected stations, nearest first.
+    pub stations: Vec<String>,
+    /// Selected stations that could not be fetched.
+    pub failures: Vec<StationFailure>,
+    /// Every file written, in a fixed order: `stations`, `summary`, `health`, `exceedances`,
+    /// `gaps`, `wind_rose`, `wave_rose`, then `observations` by station.
+    pub artifacts: Vec<Artifact>,
+}
+
+impl NdbcData {
+    /// Build a site report in `out`: the stations within `opts.radius_km` of the center and
+    /// their data for the last `opts.last`, written as
+    ///
+    /// - `stations.csv`: id, name, distance and position of each selected station;
+    /// - `summary.csv`: per station and standard met column, the count of values and nulls and
+    ///   the min, max and mean;
+    /// - `health.csv`: per station, rows, first and last time, usual reporting interval,
+    ///   completeness over the covered span, gap count, sensors with no values at all, and whether
+    ///   the latest row is more than 6 hours old;
+    /// - `exceedances.csv`: per station and [`EXCEEDANCE_THRESHOLDS`] column, how many values
+    ///   reached the threshold, their share of the values present and the first and last time;
+    /// - `gaps.csv`: every step between consecutive rows longer than twice the station's usual
+    ///   interval;
+    /// - `wind_rose.csv` and `wave_rose.csv`: counts and shares of rows per 16-point direction
+    ///   sector and speed (`WDIR`/`WSPD`) or height (`MWD`/`WVHT`) bin, non-empty cells only;
+    /// - `observations/<station>.parquet`: the fetched rows of each station;
+    /// - [`INDEX_FILE`]: the [`SiteReportIndex`] describing the above.
+    ///
+    /// Thresholds and bins follow the configured [`Units`](crate::Units).
+    ///
+    /// Station metadata must be loaded first. Stations that fail are listed in the index and left
+    /// out of the other files; the first error is returned only when none succeed.
+    pub async fn site_report(&self, opts: &SiteReportOptions, out: &Path) -> Result<SiteReportIndex> {
+        let now = OffsetDateTime::now_utc();
+        let end = now.date();
+        let days = opts.last.as_secs() / 86_400;
+        let start = time::Duration::try_from(Duration::from_secs(days * 86_400))
+            .ok()
+            .and_then(|window| end.checked_sub(window))
+            .ok_or_else(|| NdbcError::Parse(format!("site report window of {} days reaches before the earliest date", days)))?;
+        let selected = self.stations_within_km(opts.lat, opts.lon, opts.radius_km);
+
+        let results: Vec<_> = futures::stream::iter(&selected)
+            .map(|(station, _)| async move { self.fetch_range(station, start, end).await })
+            .buffered(self.concurrency)
+            .collect()
+            .await;
+
+        let mut failed = BatchReport::default();
+        let mut frames: Vec<(&str, DataFrame)> = Vec::new();
+        let mut first_err: Option<NdbcError> = None;
+        for ((station, _), result) in selected.iter().zip(results) {
+            failed.record(station, &result);
+            match result {
+                Ok(df) => frames.push((station, df)),
+                Err(e) => {
+                    first_err.get_or_insert(e);
+                }
+            }
+        }
+        if let Some(e) = first_err.filter(|_| frames.is_empty()) {
+            return Err(e);
+        }
+
+        fs::create_dir_all(out.join("observations"))?;
+        let mut artifacts = Vec::new();
+
+        let mut stations_csv = String::from("station_id,name,distance_km,latitude,longitude\n");
+        for (id, km) in &selected {
+            let station = self.station(id);
+            let name = station.and_then(|s| s.name.as_deref()).unwrap_or_default();
+            let coord = |v: Option<f64>| v.map(|v| v.to_string()).unwrap_or_default();
+            let _ = writeln!(
+                stations_csv,
+                "{},{},{:.1},{},{}",
+                id,
+                csv_field(name),
+                km,
+                coord(station.and_then(|s| s.lat)),
+                coord(station.and_then(|s| s.lon))
+            );
+        }
+        fs::write(out.join("stations.csv"), stations_csv)?;
+        artifacts.push(Artifact { kind: "stations", path: "stations.csv".into(), format: "csv", station: None, rows: selected.len() });
+
+        let mut summary_csv = String::from("station_id,column,count,missing,min,max,mean\n");
+        let mut summary_rows = 0;
+        for (station, df) in &frames {
+            for (name, _) in STD_MET_COLUMNS {
+                // Columns left out by a column selection are not summarized.
+                let Ok(values) = df.column(name) else {
+                    continue;
+                };
+                let values = values.f64()?;
+                let number = |v: Option<f64>| v.map(|v| v.to_string()).unwrap_or_default();
+                let _ = writeln!(
+                    summary_csv,
+                    "{},{},{},{},{},{},{}",
+                    station,
+                    name,
+                    values.len() - values.null_count(),
+                    values.null_count(),
+                    number(values.min()),
+                    number(values.max()),
+                    number(values.mean())
+                );
+                summary_rows += 1;
+            }
+        }
+        fs::write(out.join("summary.csv"), summary_csv)?;
+        artifacts.push(Artifact { kind: "summary", path: "summary.csv".into(), format: "csv", station: None, rows: summary_rows });
+
+        let times: Vec<Vec<i64>> = frames.iter().map(|(_, df)| sorted_times(df)).collect::<Result<_>>()?;
+
+        let mut health_csv =
+            String::from("station_id,rows,first_time,last_time,interval_minutes,completeness,gaps,dead_sensors,stale\n");
+        for ((station, df), times) in frames.iter().zip(&times) {
+            let interval = usual_interval(times);
+            // Share of the rows the usual interval would give between the first and last row.
+            let completeness = match (interval, times.first(), times.last()) {
+                (Some(interval), Some(first), Some(last)) => (times.len() as f64 / ((last - first) / interval + 1) as f64).min(1.0),
+                (None, Some(_), _) => 1.0,
+                _ => 0.0,
+            };
+            let stale = times.last().is_none_or(|last| *last < (now - STALE_AFTER).unix_timestamp() * 1000);
+            let _ = writeln!(
+                health_csv,
+                "{},{},{},{},{},{:.3},{},{},{}",
+                station,
+                df.height(),
+                times.first().map(|t| rfc3339(*t)).unwrap_or_default(),
+                times.last().map(|t| rfc3339(*t)).unwrap_or_default(),
+                interval.map(|i| (i / 60_000).to_string()).unwrap_or_default(),
+                completeness,
+                gaps(times, interval).len(),
+                missing_sensors(df).join(";"),
+                stale
+            );
+        }
+        fs::write(out.join("health.csv"), health_csv)?;
+        artifacts.push(Artifact { kind: "health", path: "health.csv".into(), format: "csv", station: None, rows: frames.len() });
+
+        let mut exceedances_csv = String::from("station_id,column,threshold,count,fraction,first_time,last_time\n");
+        let mut exceedance_rows = 0;
+        for (station, df) in &frames {
+            let row_times = time_millis(df)?;
+            for (name, threshold) in EXCEEDANCE_THRESHOLDS {
+                let Ok(values) = df.column(name) else {
+                    continue;
+                };
+                let values = values.f64()?;
+                let threshold = self.units.convert_value(name, threshold);
+                let over: Vec<i64> =
+                    values.into_iter().zip(&row_times).filter_map(|(v, t)| if v? >= threshold { t } else { None }).collect();
+                let present = values.len() - values.null_count();
+                let fraction = if present == 0 { String::new() } else { (over.len() as f64 / present as f64).to_string() };
+                let _ = writeln!(
+                    exceedances_csv,
+                    "{},{},{},{},{},{},{}",
+                    station,
+                    name,
+                    threshold,
+                    over.len(),
+                    fraction,
+                    over.iter().min().map(|t| rfc3339(*t)).unwrap_or_default(),
+                    over.iter().max().map(|t| rfc3339(*t)).unwrap_or_default()
+                );
+                exceedance_rows += 1;
+            }
+        }
+        fs::write(out.join("exceedances.csv"), exceedances_csv)?;
+        artifacts.push(Artifact {
+            kind: "exceedances",
+            path: "exceedances.csv".into(),
+            format: "csv",
+            station: None,
+            rows: exceedance_rows,
+        });
+
+        let mut gaps_csv = String::from("station_id,start,end,minutes\n");
+        let mut gap_rows = 0;
+        for ((station, _), times) in frames.iter().zip(&times) {
+            for (from, to) in gaps(times, usual_interval(times)) {
+                let _ = writeln!(gaps_csv, "{},{},{},{}", station, rfc3339(from), rfc3339(to), (to - from) / 60_000);
+                gap_rows += 1;
+            }
+        }
+        fs::write(out.join("gaps.csv"), gaps_csv)?;
+        artifacts.push(Artifact { kind: "gaps", path: "gaps.csv".into(), format: "csv", station: None, rows: gap_rows });
+
+        let roses = [
+            ("wind_rose", "WDIR", "WSPD", &WIND_ROSE_BINS[..]),
+            ("wave_rose", "MWD", "WVHT", &WAVE_ROSE_BINS[..]),
+        ];
+        for (kind, direction, value, bins) in roses {
+            let bins: Vec<f64> = bins.iter().map(|b| self.units.convert_value(value, *b)).collect();
+            let mut rose_csv = String::from("station_id,sector,direction,lower,upper,count,fraction\n");
+            let mut rose_rows = 0;
+            for (station, df) in &frames {
+                let (Ok(dirs), Ok(values)) = (df.column(direction), df.column(value)) else {
+                    continue;
+                };
+                rose_rows += write_rose(&mut rose_csv, station, dirs.f64()?, values.f64()?, &bins);
+            }
+            let path = format!("{}.csv", kind);
+            fs::write(out.join(&path), rose_csv)?;
+            artifacts.push(Artifact { kind, path, format: "csv", station: None, rows: rose_rows });
+        }
+
+        for (station, df) in &mut frames {
+            let path = format!("observations/{}.parquet", station);
+            let key_values = self.provenance_key_values(Some(station), None);
+            coords::write_parquet(self.parquet.writer(fs::File::create(out.join(&path))?)?, df, key_values, self.parquet.row_group_size)?;
+            artifacts.push(Artifact {
+                kind: "observations",
+                path,
+                format: "parquet",
+                station: Some(station.to_string()),
+                rows: df.height(),
+            });
+        }
+
+        let index = SiteReportIndex {
+            lat: opts.lat,
+            lon: opts.lon,
+            radius_km: opts.radius_km,
+            start: start.to_string(),
+            end: end.to_string(),
+            stations: selected.into_iter().map(|(id, _)| id).collect(),
+            failures: failed.failures,
+            artifacts,
+        };
+        let json = serde_json::to_string_pretty(&index).map_err(|e| NdbcError::Parse(format!("site report index: {}", e)))?;
+        fs::write(out.join(INDEX_FILE), json)?;
+        Ok(index)
+    }
+}
+
+/// Distinct `time_ms` values of a frame, ascending.
+fn sorted_times(df: &DataFrame) -> Result<Vec<i64>> {
+    let mut times: Vec<i64> = time_millis(df)?.into_iter().flatten().collect();
+    times.sort_unstable();
+    times.dedup();
+    Ok(times)
+}
+
+/// Median step between consecutive `times`, taken as the station's reporting interval; `None`
+/// with fewer than two times.
+fn usual_interval(times: &[i64]) -> Option<i64> {
+    let mut steps: Vec<i64> = times.windows(2).map(|w| w[1] - w[0]).collect();
+    steps.sort_unstable();
+    steps.get(steps.len() / 2).copied()
+}
+
+/// Steps between consecutive `times` longer than [`GAP_FACTOR`] times `interval`, as (last row
+/// before, first row after).
+fn gaps(times: &[i64], interval: Option<i64>) -> Vec<(i64, i64)> {
+    let Some(interval) = interval else {
+        return Vec::new();
+    };
+    times.windows(2).filter(|w| w[1] - w[0] > GAP_FACTOR * interval).map(|w| (w[0], w[1])).collect()
+}
+
+/// Index into [`SECTORS`] of a direction in degrees; each sector is centered on its compass point.
+fn sector(degrees: f64) -> usize {
+    ((degrees.rem_euclid(360.0) + 11.25) / 22.5) as usize % SECTORS.len()
+}
+
+/// Append one station's rose to `csv`: rows with both a direction and a value counted per sector
+/// and bin (`bins` are lower bounds), non-empty cells only, in sector then bin order. Returns the
+/// number of lines written.
+fn write_rose(csv: &mut String, station: &str, dirs: &Float64Chunked, values: &Float64Chunked, bins: &[f64]) -> usize {
+    let mut counts = vec![vec![0usize; bins.len()]; SECTORS.len()];
+    let mut total = 0;
+    for (dir, value) in dirs.into_iter().zip(values) {
+        let (Some(dir), Some(value)) = (dir, value) else {
+            continue;
+        };
+        let bin = bins.iter().rposition(|lower| value >= *lower).unwrap_or(0);
+        counts[sector(dir)][bin] += 1;
+        total += 1;
+    }
+    let mut rows = 0;
+    for (s, cells) in counts.iter().enumerate() {
+        for (b, count) in cells.iter().enumerate().filter(|(_, count)| **count > 0) {
+            let upper = bins.get(b + 1).map(|u| u.to_string()).unwrap_or_default();
+            let share = *count as f64 / total as f64;
+            let _ = writeln!(csv, "{},{},{},{},{},{},{}", station, SECTORS[s], s as f64 * 22.5, bins[b], upper, count, share);
+            rows += 1;
+        }
+    }
+    rows
+}
+
+/// RFC 3339 form of a `time_ms` value.
+fn rfc3339(ms: i64) -> String {
+    OffsetDateTime::from_unix_timestamp_nanos(ms as i128 * 1_000_000)
+        .ok()
+        .and_then(|t| t.format(&Rfc3339).ok())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn gaps_are_steps_over_twice_the_usual_interval() {
+        let hour = 3_600_000;
+        let times = [0, hour, 2 * hour, 6 * hour, 7 * hour];
+        assert_eq!(usual_interval(&times), Some(hour));
+        assert_eq!(gaps(&times, usual_interval(&times)), [(2 * hour, 6 * hour)]);
+        assert_eq!(usual_interval(&[0]), None);
+    }
+
+    #[test]
+    fn sectors_are_centered_on_compass_points() {
+        let names: Vec<&str> = [0.0, 11.0, 11.25, 200.0, 348.75, 359.0, 360.0].iter().map(|d| SECTORS[sector(*d)]).collect();
+        assert_eq!(names, ["N", "N", "NNE", "SSW", "N", "N", "N"]);
+    }
+
+    #[tokio::test]
+    async fn window_past_the_calendar_is_an_error() -> Result<()> {
+        let data = NdbcData::in_memory()?;
+        let out = std::env::temp_dir().join(format!("ndbc-data-site-report-window-{}", std::process::id()));
+        for last in [Duration::from_secs(99_999_999 * 86_400), Duration::MAX] {
+            let opts = SiteReportOptions { lat: 0.0, lon: 0.0, radius_km: 1.0, last };
+            assert!(matches!(data.site_report(&opts, &out).await, Err(NdbcError::Parse(_))));
+        }
+        assert!(!out.exists());
+        Ok(())
+    }
+}
@@ -0,0 +1,134 @@
+//! Load parsed observations into SQLite (feature `sqlite`): an in-memory database for ad-hoc
+//! SQL, or a database file as an output target ([`OutputFormat::Sqlite`](crate::OutputFormat::Sqlite)).
+
+use crate::{align_columns, standard_columns, NdbcData, NdbcError, Result, STD_MET_COLUMNS};
+use polars::prelude::*;
+use rusqlite::params_from_iter;
+use rusqlite::types::Value;
+pub use rusqlite::Connection;
+use std::fs;
+use std::path::PathBuf;
+use std::time::Duration;
+use tracing::info;
+
+/// Name of the table created by [`frames_to_sqlite`], and the default table of [`SqliteTarget`].
+const TABLE: &str = "observations";
+
+/// Database file [`OutputFormat::Sqlite`](crate::OutputFormat::Sqlite) writes to when no path is
+/// set, in the output directory.
+pub const SQLITE_FILE: &str = "ndbc.sqlite";
+
+/// How long a write waits for another connection (e.g. a concurrent station) to release the
+/// database.
+const BUSY_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Database and table that [`OutputFormat::Sqlite`](crate::OutputFormat::Sqlite) upserts
+/// observations into.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SqliteTarget {
+    /// Database file, created when missing; `None` uses [`SQLITE_FILE`] in the output directory.
+    pub path: Option<PathBuf>,
+    /// Table name, created when missing (default `observations`).
+    pub table: String,
+}
+
+impl Default for SqliteTarget {
+    fn default() -> Self {
+        Self { path: None, table: TABLE.to_string() }
+    }
+}
+
+/// Create an in-memory SQLite database with an `observations` table holding every row of
+/// `frames` (as returned by [`NdbcData::fetch_station_df`]), keyed by `(station_id, time_ms)`.
+///
+/// Columns are `station_id`, `time_ms` (milliseconds since the Unix epoch, UTC), the standard met
+/// columns in lowercase, `latitude` and `longitude`; missing values are `NULL`. A row repeated
+/// for the same station and time keeps the last one.
+pub fn frames_to_sqlite(frames: &[DataFrame]) -> Result<Connection> {
+    let mut conn = Connection::open_in_memory()?;
+    upsert_frames(&mut conn, TABLE, frames)?;
+    Ok(conn)
+}
+
+/// Create `table` in `conn` if needed (layout as in [`frames_to_sqlite`]) and insert every row
+/// of `frames` in one transaction, replacing rows with the same `(station_id, time_ms)`.
+fn upsert_frames(conn: &mut Connection, table: &str, frames: &[DataFrame]) -> Result<()> {
+    let table = format!("\"{}\"", table.replace('"', "\"\""));
+    let met: Vec<String> = STD_MET_COLUMNS.iter().map(|(name, _)| name.to_lowercase()).collect();
+    let columns: Vec<String> = ["station_id", "time_ms"]
+        .into_iter()
+        .map(String::from)
+        .chain(met.iter().cloned())
+        .chain(["latitude", "longitude"].map(String::from))
+        .collect();
+    conn.execute_batch(&format!(
+        "CREATE TABLE IF NOT EXISTS {table} (station_id TEXT NOT NULL, time_ms INTEGER NOT NULL, {}, latitude REAL, longitude REAL, \
+         PRIMARY KEY (station_id, time_ms))",
+        met.iter().map(|c| format!("{} REAL", c)).collect::<Vec<_>>().join(", "),
+    ))?;
+
+    let tx = conn.transaction()?;
+    {
+        let placeholders = vec!["?"; columns.len()].join(", ");
+        let mut insert =
+            tx.prepare(&format!("INSERT OR REPLACE INTO {table} ({}) VALUES ({})", columns.join(", "), placeholders))?;
+        for df in frames {
+            let df = align_columns(df, &standard_columns())?;
+            let stations = df.column("station_id")?.str()?;
+            let times = crate::time_millis(&df)?;
+            let mut values: Vec<&Float64Chunked> = Vec::new();
+            for (name, _) in STD_MET_COLUMNS {
+                values.push(df.column(name)?.f64()?);
+            }
+            values.push(df.column("latitude")?.f64()?);
+            values.push(df.column("longitude")?.f64()?);
+
+            for i in 0..df.height() {
+                let mut row = vec![
+                    stations.get(i).map_or(Value::Null, |s| Value::Text(s.to_string())),
+                    times.get(i).map_or(Value::Null, Value::Integer),
+                ];
+                row.extend(values.iter().map(|col| col.get(i).map_or(Value::Null, Value::Real)));
+                insert.execute(params_from_iter(row))?;
+            }
+        }
+    }
+    tx.commit()?;
+    Ok(())
+}
+
+impl NdbcData {
+    /// Fetch realtime data for several stations and load it into an in-memory SQLite
+    /// `observations` table (see [`frames_to_sqlite`]), returning the connection for queries.
+    ///
+    /// Nothing is written to disk. Stations that fail are logged and left out; the first error is
+    /// returned only when none succeed.
+    pub async fn fetch_into_sqlite(&self, stations: &[String]) -> Result<Connection> {
+        let frames = self.fetch_station_frames(stations).await?;
+        frames_to_sqlite(&frames)
+    }
+
+    /// Database file [`OutputFormat::Sqlite`](crate::OutputFormat::Sqlite) writes to.
+    pub(crate) fn sqlite_path(&self) -> Result<PathBuf> {
+        match (&self.sqlite, &self.out_dir) {
+            (SqliteTarget { path: Some(path), .. }, _) => Ok(path.clone()),
+            (_, Some(out_dir)) => Ok(out_dir.join(SQLITE_FILE)),
+            (_, None) => Err(NdbcError::NoOutputDir),
+        }
+    }
+
+    /// Upsert `frames` (with station columns) into the configured [`SqliteTarget`], returning
+    /// the database path. Only the standard met columns, coordinates and `time_ms` are stored.
+    pub(crate) fn save_to_sqlite(&self, frames: &[DataFrame]) -> Result<PathBuf> {
+        let path = self.sqlite_path()?;
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            fs::create_dir_all(parent)?;
+        }
+        let rows: usize = frames.iter().map(|df| df.height()).sum();
+        info!(file = %path.display(), table = %self.sqlite.table, rows, "writing output");
+        let mut conn = Connection::open(&path)?;
+        conn.busy_timeout(BUSY_TIMEOUT)?;
+        upsert_frames(&mut conn, &self.sqlite.table, frames)?;
+        Ok(path)
+    }
+}
@@ -0,0 +1,709 @@
+//! Station metadata parsed from NDBC's `stationmetadata.xml`, `activestations.xml` and
+//! `station_table.txt`.
+
+use crate::{NdbcError, Result, StationClass};
+use quick_xml::events::{BytesStart, Event};
+use polars::prelude::{DataFrame, NamedFrom, Series};
+use quick_xml::Reader as XmlReader;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use time::format_description::well_known::Rfc3339;
+use time::macros::format_description;
+use time::{Date, OffsetDateTime};
+use tracing::warn;
+
+/// Which NDBC file the loaded station metadata came from.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "snake_case")]
+pub enum MetadataSource {
+    /// `metadata/stationmetadata.xml`: every station, with full deployment history (~10 MB).
+    #[default]
+    #[value(name = "full")]
+    StationMetadata,
+    /// `activestations.xml`: currently active stations only, without history; much smaller.
+    #[value(name = "active")]
+    ActiveStations,
+    /// Both files, merged per station; see [`merge_sources`].
+    #[value(name = "both")]
+    Merged,
+}
+
+/// Which loaded stations the station queries ([`all_station_ids`](crate::NdbcData::all_station_ids),
+/// the bounding box and distance queries) return. Every station is kept in the metadata map either
+/// way, with its capability flags.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum MetadataFilter {
+    /// Only stations that report standard meteorological data.
+    #[default]
+    MetOnly,
+    /// Every station, including currents-, water-quality- and DART-only stations.
+    All,
+}
+
+impl MetadataFilter {
+    /// True when `station` passes the filter.
+    pub fn includes(&self, station: &Station) -> bool {
+        match self {
+            MetadataFilter::MetOnly => station.met,
+            MetadataFilter::All => true,
+        }
+    }
+}
+
+/// Everything known about a station from the loaded metadata.
+///
+/// Fields that the metadata source does not provide are `None`.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Station {
+    pub id: String,
+    pub name: Option<String>,
+    pub owner: Option<String>,
+    /// Program the station belongs to (`pgm`), e.g. "NDBC Meteorological/Ocean".
+    pub program: Option<String>,
+    /// Station type, e.g. `buoy`, `fixed`, `dart`, `other`.
+    pub station_type: Option<String>,
+    /// Current latitude; the met-reporting deployment is preferred when there is one.
+    pub lat: Option<f64>,
+    /// Current longitude; the met-reporting deployment is preferred when there is one.
+    pub lon: Option<f64>,
+    /// Site elevation in meters above sea level.
+    pub elevation: Option<f64>,
+    /// Anemometer height in meters above the site (`anemom_height`) for the current deployment.
+    pub anemometer_height: Option<f64>,
+    /// Hull type of the current deployment.
+    pub hull: Option<String>,
+    pub payload: Option<String>,
+    pub time_zone: Option<String>,
+    /// Free-text notes from `station_table.txt`.
+    pub notes: Option<String>,
+    /// Reports standard meteorological data.
+    pub met: bool,
+    /// Reports ocean current data.
+    pub currents: bool,
+    /// Reports water quality data.
+    pub water_quality: bool,
+    /// DART tsunami station.
+    pub dart: bool,
+    /// Every `<history>` entry, in file order (most recent first).
+    pub history: Vec<HistoryEntry>,
+}
+
+impl Station {
+    /// Current `(latitude, longitude)` when both are known.
+    pub fn coords(&self) -> Option<(f64, f64)> {
+        Some((self.lat?, self.lon?))
+    }
+
+    /// The data types this station reports.
+    pub fn capabilities(&self) -> Capabilities {
+        let buoy = self.station_type.as_deref().and_then(StationClass::from_station_type) == Some(StationClass::Buoy);
+        Capabilities {
+            met: self.met,
+            waves: self.met && buoy,
+            currents: self.currents,
+            water_quality: self.water_quality,
+            dart: self.dart,
+        }
+    }
+
+    /// Fill fields this station does not have yet from `other`; existing values win.
+    pub(crate) fn merge_missing(&mut self, other: Station) {
+        fn fill<T>(field: &mut Option<T>, value: Option<T>) {
+            if field.is_none() {
+                *field = value;
+            }
+        }
+        fill(&mut self.name, other.name);
+        fill(&mut self.owner, other.owner);
+        fill(&mut self.program, other.program);
+        fill(&mut self.station_type, other.station_type);
+        if self.coords().is_none() {
+            self.lat = other.lat;
+            self.lon = other.lon;
+        }
+        fill(&mut self.elevation, other.elevation);
+        fill(&mut self.anemometer_height, other.anemometer_height);
+        fill(&mut self.hull, other.hull);
+        fill(&mut self.payload, other.payload);
+        fill(&mut self.time_zone, other.time_zone);
+        fill(&mut self.notes, other.notes);
+    }
+}
+
+/// Data types a station reports, used as a query with
+/// [`NdbcData::station_ids_with`](crate::NdbcData::station_ids_with): a station matches when it
+/// has every capability set to `true` here, e.g. `Capabilities { met: true, waves: true, ..Default::default() }`.
+///
+/// The metadata has no wave flag, so `waves` is inferred: met stations whose type is a buoy
+/// (see [`StationClass`]) report wave data; fixed C-MAN stations do not.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Capabilities {
+    pub met: bool,
+    pub waves: bool,
+    pub currents: bool,
+    pub water_quality: bool,
+    pub dart: bool,
+}
+
+impl Capabilities {
+    /// True when every capability set in `required` is also set here.
+    pub fn contains(&self, required: Capabilities) -> bool {
+        (!required.met || self.met)
+            && (!required.waves || self.waves)
+            && (!required.currents || self.currents)
+            && (!required.water_quality || self.water_quality)
+            && (!required.dart || self.dart)
+    }
+}
+
+/// One `<history>` entry from the station metadata XML: a deployment period and position.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    /// First day of the deployment.
+    pub start: Option<Date>,
+    /// Last day of the deployment; `None` for the current deployment.
+    pub stop: Option<Date>,
+    pub lat: Option<f64>,
+    pub lng: Option<f64>,
+    /// Site elevation in meters above sea level (`elev`).
+    #[serde(default)]
+    pub elevation: Option<f64>,
+    /// Anemometer height in meters above the site (`anemom_height`).
+    #[serde(default)]
+    pub anemometer_height: Option<f64>,
+    /// Whether standard meteorological data were reported (`met="y"`).
+    pub met: bool,
+    /// Whether ocean current data were reported (`currents="y"`).
+    #[serde(default)]
+    pub currents: bool,
+    /// Whether water quality data were reported (`waterquality="y"`).
+    #[serde(default)]
+    pub water_quality: bool,
+    /// Whether the deployment was a DART tsunami station (`dart="y"`).
+    #[serde(default)]
+    pub dart: bool,
+}
+
+/// A station deployment: one [`HistoryEntry`] with its dates, position and capability flags.
+pub type Deployment = HistoryEntry;
+
+impl HistoryEntry {
+    /// True when this is the ongoing deployment (no stop date).
+    pub fn is_current(&self) -> bool {
+        self.stop.is_none()
+    }
+}
+
+/// Parse the station metadata XML into a map of station id -> [`Station`].
+pub(crate) fn parse_station_metadata(xml: &[u8]) -> Result<HashMap<String, Station>> {
+    let mut stations = HashMap::new();
+    let mut reader = XmlReader::from_reader(xml);
+    reader.trim_text(true);
+    let mut buf = Vec::new();
+    let mut in_stations = false;
+    let mut current: Option<Station> = None;
+    let mut picked_lat_lon: Option<(f64, f64)> = None;
+    let mut picked_hull: Option<String> = None;
+    let mut picked_elev: Option<f64> = None;
+    let mut picked_anemom: Option<f64> = None;
+
+    loop {
+        match reader.read_event_into(&mut buf) {
+            // <history .../> entries are self-closing, so they arrive as Empty events
+            Ok(Event::Start(e)) | Ok(Event::Empty(e)) => {
+                let name = e.name();
+                if name.as_ref() == b"stations" {
+                    in_stations = true;
+                } else if in_stations && name.as_ref() == b"station" {
+                    // Start new station
+                    let attrs = attributes(&e)?;
+                    current = attrs.get("id").map(|id| Station {
+                        id: id.clone(),
+                        name: attrs.get("name").cloned(),
+                        owner: attrs.get("owner").cloned(),
+                        program: attrs.get("pgm").cloned(),
+                        dart: attrs.get("type").is_some_and(|t| t == "dart"),
+                        station_type: attrs.get("type").cloned(),
+                        ..Default::default()
+                    });
+                    picked_lat_lon = None;
+                    picked_hull = None;
+                    picked_elev = None;
+                    picked_anemom = None;
+                } else if in_stations && name.as_ref() == b"history" {
+                    let attrs = attributes(&e)?;
+                    let lat = attrs.get("lat").and_then(|v| v.parse().ok());
+                    let lng = attrs.get("lng").and_then(|v| v.parse().ok());
+                    let elevation = attrs.get("elev").and_then(|v| v.parse().ok());
+                    let anemometer_height = attrs.get("anemom_height").and_then(|v| v.parse().ok());
+                    let flag = |key: &str| attrs.get(key).is_some_and(|v| v == "y");
+                    // Consider this history as a candidate for current position if met="y"
+                    let met_yes = flag("met");
+                    let is_current = attrs.get("stop").is_none_or(|s| s.is_empty());
+                    if let (true, Some(la), Some(lo)) = (met_yes, lat, lng) {
+                        // Prefer the current entry (stop empty). If not set yet, set. If we already set and current is false, keep existing.
+                        if picked_lat_lon.is_none() || is_current {
+                            picked_lat_lon = Some((la, lo));
+                        }
+                    }
+                    if is_current || picked_hull.is_none() {
+                        picked_hull = attrs.get("hull").filter(|h| !h.is_empty()).cloned().or(picked_hull);
+                        picked_elev = elevation.or(picked_elev);
+                        picked_anemom = anemometer_height.or(picked_anemom);
+                    }
+                    if let Some(station) = current.as_mut() {
+                        station.history.push(HistoryEntry {
+                            start: attrs.get("start").and_then(|v| parse_meta_date(v)),
+                            stop: attrs.get("stop").and_then(|v| parse_meta_date(v)),
+                            lat,
+                            lng,
+                            elevation,
+                            anemometer_height,
+                            met: met_yes,
+                            currents: flag("currents"),
+                            water_quality: flag("waterquality"),
+                            dart: flag("dart"),
+                        });
+                    }
+                }
+            }
+            Ok(Event::End(e)) => {
+                if e.name().as_ref() == b"station" {
+                    if let Some(mut station) = current.take() {
+                        // Met stations use the met deployment's position; others the current one.
+                        let fallback = station
+                            .history
+                            .iter()
+                            .find(|h| h.is_current())
+                            .or(station.history.first())
+                            .and_then(|h| Some((h.lat?, h.lng?)));
+                        // Like `met`, a capability counts if any deployment reported it.
+                        station.met = picked_lat_lon.is_some();
+                        station.currents = station.history.iter().any(|h| h.currents);
+                        station.water_quality = station.history.iter().any(|h| h.water_quality);
+                        station.dart |= station.history.iter().any(|h| h.dart);
+                        if let Some((la, lo)) = picked_lat_lon.take().or(fallback) {
+                            station.lat = Some(la);
+                            station.lon = Some(lo);
+                        }
+                        station.hull = picked_hull.take();
+                        station.elevation = picked_elev.take();
+                        station.anemometer_height = picked_anemom.take();
+                        stations.insert(station.id.clone(), station);
+                    }
+                } else if e.name().as_ref() == b"stations" {
+                    // finished
+                    break;
+                }
+            }
+            Ok(Event::Eof) => break,
+            Err(e) => return Err(NdbcError::Parse(format!("station metadata: {}", e))),
+            _ => {}
+        }
+        buf.clear();
+    }
+    Ok(stations)
+}
+
+/// Parse NDBC's compact `activestations.xml` (one `<station .../>` element per active station,
+/// with position and capability flags as attributes) into a map of station id -> [`Station`].
+///
+/// Ids are upper-cased to match `stationmetadata.xml`. No deployment history is available.
+pub(crate) fn parse_active_stations(xml: &[u8]) -> Result<HashMap<String, Station>> {
+    let mut stations = HashMap::new();
+    let mut reader = XmlReader::from_reader(xml);
+    reader.trim_text(true);
+    let mut buf = Vec::new();
+    loop {
+        match reader.read_event_into(&mut buf) {
+            Ok(Event::Start(e)) | Ok(Event::Empty(e)) if e.name().as_ref() == b"station" => {
+                let attrs = attributes(&e)?;
+                let Some(id) = attrs.get("id").map(|id| id.to_uppercase()) else {
+                    continue;
+                };
+                let flag = |key: &str| attrs.get(key).is_some_and(|v| v == "y");
+                let station = Station {
+                    id: id.clone(),
+                    name: attrs.get("name").cloned(),
+                    owner: attrs.get("owner").cloned(),
+                    program: attrs.get("pgm").cloned(),
+                    station_type: attrs.get("type").cloned(),
+                    lat: attrs.get("lat").and_then(|v| v.parse().ok()),
+                    lon: attrs.get("lon").and_then(|v| v.parse().ok()),
+                    elevation: attrs.get("elev").and_then(|v| v.parse().ok()),
+                    met: flag("met"),
+                    currents: flag("currents"),
+                    water_quality: flag("waterquality"),
+                    dart: flag("dart"),
+                    ..Default::default()
+                };
+                stations.insert(id, station);
+            }
+            Ok(Event::Eof) => break,
+            Err(e) => return Err(NdbcError::Parse(format!("active stations: {}", e))),
+            _ => {}
+        }
+        buf.clear();
+    }
+    Ok(stations)
+}
+
+/// Parse NDBC's pipe-delimited `station_table.txt` into a map of station id -> [`Station`].
+///
+/// Columns are `STATION_ID | OWNER | TTYPE | HULL | NAME | PAYLOAD | LOCATION | TIMEZONE |
+/// FORECAST | NOTE`; `#` lines are headers. `OWNER` is NDBC's owner code and `TTYPE` the station
+/// type. Ids are upper-cased to match the XML sources. Capability flags are left unset. Lines
+/// that stop before `LOCATION` are logged and skipped.
+pub(crate) fn parse_station_table(text: &str) -> Result<HashMap<String, Station>> {
+    let mut stations = HashMap::new();
+    for line in text.lines().filter(|l| !l.trim().is_empty() && !l.starts_with('#')) {
+        let cols: Vec<&str> = line.split('|').map(str::trim).collect();
+        // The location is the 7th column; later ones (time zone, forecast, notes) may be missing.
+        if cols.len() < 7 {
+            warn!(columns = cols.len(), %line, "station table line has fewer than 7 columns; skipping");
+            continue;
+        }
+        let col = |i: usize| cols.get(i).map(|c| c.trim_matches('"').trim()).filter(|c| !c.is_empty()).map(str::to_string);
+        let Some(id) = col(0).map(|id| id.to_uppercase()) else {
+            continue;
+        };
+        // The decimal position comes first, followed by a degrees/minutes/seconds form in parentheses
+        let location = cols[6].trim_matches('"');
+        let decimal = location.split_once('(').map_or(location, |(decimal, _)| decimal);
+        let (lat, lon) = parse_location(decimal).unzip();
+        let station = Station {
+            id: id.clone(),
+            owner: col(1),
+            station_type: col(2),
+            hull: col(3),
+            name: col(4),
+            payload: col(5),
+            lat,
+            lon,
+            time_zone: col(7),
+            notes: col(9),
+            ..Default::default()
+        };
+        stations.insert(id, station);
+    }
+    Ok(stations)
+}
+
+/// Parse a decimal location such as `30.000 N 90.000 W` into signed `(lat, lon)`.
+fn parse_location(s: &str) -> Option<(f64, f64)> {
+    let parts: Vec<&str> = s.split_whitespace().collect();
+    let [lat, ns, lon, ew] = parts[..] else {
+        return None;
+    };
+    let lat: f64 = lat.parse().ok()?;
+    let lon: f64 = lon.parse().ok()?;
+    let lat = match ns {
+        "N" | "n" => lat,
+        "S" | "s" => -lat,
+        _ => return None,
+    };
+    let lon = match ew {
+        "E" | "e" => lon,
+        "W" | "w" => -lon,
+        _ => return None,
+    };
+    Some((lat, lon))
+}
+
+/// Default distance above which differing coordinates for a station are logged as a conflict
+/// when merging metadata sources.
+pub const COORD_CONFLICT_KM: f64 = 1.0;
+
+/// `created` timestamp on the root element of an NDBC metadata XML file, if present.
+///
+/// `stationmetadata.xml` writes RFC 3339 (`2025-01-10T06:00:02Z`); `activestations.xml` uses a
+/// `UTC` suffix instead of `Z`.
+pub(crate) fn document_created(xml: &[u8]) -> Option<OffsetDateTime> {
+    let mut reader = XmlReader::from_reader(xml);
+    let mut buf = Vec::new();
+    loop {
+        match reader.read_event_into(&mut buf).ok()? {
+            Event::Start(e) | Event::Empty(e) => {
+                let created = attributes(&e).ok()?.remove("created")?;
+                let created = created.strip_suffix("UTC").map_or(created.clone(), |t| format!("{}Z", t));
+                return OffsetDateTime::parse(&created, &Rfc3339).ok();
+            }
+            Event::Eof => return None,
+            _ => {}
+        }
+        buf.clear();
+    }
+}
+
+/// Merge station maps from two sources into one, deterministically.
+///
+/// For stations listed in both, the source with the later `created` timestamp wins and the other
+/// only fills fields it lacks (including deployment history); when the timestamps are equal or
+/// unknown, `secondary` wins since it is expected to be the current-only list. Coordinates that
+/// differ by more than `conflict_km` are logged.
+pub(crate) fn merge_sources(
+    primary: HashMap<String, Station>,
+    primary_created: Option<OffsetDateTime>,
+    secondary: HashMap<String, Station>,
+    secondary_created: Option<OffsetDateTime>,
+    conflict_km: f64,
+) -> HashMap<String, Station> {
+    let primary_newer = matches!((primary_created, secondary_created), (Some(p), Some(s)) if p > s);
+    let mut merged = primary;
+    for (id, other) in secondary {
+        let Some(existing) = merged.remove(&id) else {
+            merged.insert(id, other);
+            continue;
+        };
+        if let (Some(a), Some(b)) = (existing.coords(), other.coords()) {
+            let km = haversine_km(a, b);
+            if km > conflict_km {
+                warn!(station = %id, distance_km = km, primary = ?a, secondary = ?b, "metadata sources disagree on station position");
+            }
+        }
+        let (mut winner, mut loser) = if primary_newer { (existing, other) } else { (other, existing) };
+        if winner.history.is_empty() {
+            winner.history = std::mem::take(&mut loser.history);
+        }
+        winner.merge_missing(loser);
+        merged.insert(id, winner);
+    }
+    merged
+}
+
+/// Criteria for selecting stations from the loaded metadata, combined with AND; see
+/// [`NdbcData::filter_stations`](crate::NdbcData::filter_stations).
+///
+/// Text criteria match case-insensitively anywhere in the field, so `owner("cdip")` matches
+/// "Scripps Institution of Oceanography - CDIP". A station without the field never matches.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct StationFilter {
+    owner: Option<String>,
+    program: Option<String>,
+    station_type: Option<String>,
+    met_only: bool,
+    capabilities: Capabilities,
+    bbox: Option<[f64; 4]>,
+}
+
+impl StationFilter {
+    /// A filter that matches every station.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Owner/operator containing `owner`, e.g. `NDBC`, `CDIP`, `TABS`.
+    pub fn owner(mut self, owner: impl Into<String>) -> Self {
+        self.owner = Some(owner.into().to_lowercase());
+        self
+    }
+
+    /// Program (`pgm`) containing `program`, e.g. `IOOS Partners`.
+    pub fn program(mut self, program: impl Into<String>) -> Self {
+        self.program = Some(program.into().to_lowercase());
+        self
+    }
+
+    /// Station type containing `station_type`, e.g. `buoy` or `fixed`.
+    pub fn station_type(mut self, station_type: impl Into<String>) -> Self {
+        self.station_type = Some(station_type.into().to_lowercase());
+        self
+    }
+
+    /// Only stations that report standard meteorological data.
+    pub fn met_only(mut self) -> Self {
+        self.met_only = true;
+        self
+    }
+
+    /// Only stations with every capability set in `required`.
+    pub fn capabilities(mut self, required: Capabilities) -> Self {
+        self.capabilities = required;
+        self
+    }
+
+    /// Only stations whose current coordinates fall inside the box (bounds inclusive;
+    /// `min_lon > max_lon` crosses the antimeridian).
+    pub fn bbox(mut self, min_lat: f64, min_lon: f64, max_lat: f64, max_lon: f64) -> Self {
+        self.bbox = Some([min_lat, min_lon, max_lat, max_lon]);
+        self
+    }
+
+    /// True when `station` meets every criterion.
+    pub fn matches(&self, station: &Station) -> bool {
+        let contains = |field: &Option<String>, wanted: &Option<String>| match wanted {
+            None => true,
+            Some(wanted) => field.as_ref().is_some_and(|f| f.to_lowercase().contains(wanted.as_str())),
+        };
+        contains(&station.owner, &self.owner)
+            && contains(&station.program, &self.program)
+            && contains(&station.station_type, &self.station_type)
+            && (!self.met_only || station.met)
+            && station.capabilities().contains(self.capabilities)
+            && self.bbox.is_none_or(|bbox| station.coords().is_some_and(|coords| in_bbox(coords, bbox)))
+    }
+}
+
+/// True when `(lat, lon)` lies inside `[min_lat, min_lon, max_lat, max_lon]` (bounds inclusive);
+/// `min_lon > max_lon` is taken to cross the antimeridian.
+pub(crate) fn in_bbox((lat, lon): (f64, f64), [min_lat, min_lon, max_lat, max_lon]: [f64; 4]) -> bool {
+    let in_lon = if min_lon <= max_lon { min_lon <= lon && lon <= max_lon } else { lon >= min_lon || lon <= max_lon };
+    min_lat <= lat && lat <= max_lat && in_lon
+}
+
+/// Format written by the `metadata` CLI subcommand.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum MetadataFormat {
+    /// GeoJSON FeatureCollection of station points; see
+    /// [`NdbcData::metadata_geojson`](crate::NdbcData::metadata_geojson).
+    #[default]
+    Geojson,
+    /// Station table with a header row, every station included; see
+    /// [`NdbcData::station_metadata_df`](crate::NdbcData::station_metadata_df).
+    Csv,
+    /// The same station table as Parquet.
+    Parquet,
+}
+
+/// Format written by the `coords` CLI subcommand; see
+/// [`NdbcData::write_station_coords`](crate::NdbcData::write_station_coords).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum CoordsFormat {
+    /// Aligned `id lat lon name` columns, with a `not found` line for unknown ids
+    #[default]
+    Table,
+    /// `id,lat,lon,name` with a header row
+    Csv,
+    /// Array of `{"id", "lat", "lon", "name"}` objects
+    Json,
+}
+
+/// GeoJSON FeatureCollection with one Point feature per station that has coordinates, sorted by
+/// id. Properties carry the descriptive fields and capability flags; unknown values are `null`.
+pub(crate) fn stations_geojson<'a>(stations: impl IntoIterator<Item = &'a Station>) -> Value {
+    let mut stations: Vec<&Station> = stations.into_iter().filter(|s| s.coords().is_some()).collect();
+    stations.sort_by(|a, b| a.id.cmp(&b.id));
+    let features: Vec<Value> = stations
+        .into_iter()
+        .filter_map(|s| {
+            let (lat, lon) = s.coords()?;
+            Some(json!({
+                "type": "Feature",
+                "id": s.id,
+                "geometry": { "type": "Point", "coordinates": [lon, lat] },
+                "properties": {
+                    "id": s.id,
+                    "name": s.name,
+                    "owner": s.owner,
+                    "program": s.program,
+                    "station_type": s.station_type,
+                    "elevation": s.elevation,
+                    "anemometer_height": s.anemometer_height,
+                    "hull": s.hull,
+                    "met": s.met,
+                    "currents": s.currents,
+                    "water_quality": s.water_quality,
+                    "dart": s.dart,
+                },
+            }))
+        })
+        .collect();
+    json!({ "type": "FeatureCollection", "features": features })
+}
+
+/// One row per station, sorted by id: `station_id`, descriptive fields, `latitude`, `longitude`,
+/// `elevation`, `anemometer_height` and the capability flags. Unknown values are null.
+pub(crate) fn stations_frame<'a>(stations: impl IntoIterator<Item = &'a Station>) -> Result<DataFrame> {
+    let mut stations: Vec<&Station> = stations.into_iter().collect();
+    stations.sort_by(|a, b| a.id.cmp(&b.id));
+    let text = |name: &str, f: fn(&Station) -> Option<&str>| Series::new(name.into(), stations.iter().map(|s| f(s)).collect::<Vec<_>>());
+    let number = |name: &str, f: fn(&Station) -> Option<f64>| Series::new(name.into(), stations.iter().map(|s| f(s)).collect::<Vec<_>>());
+    let flag = |name: &str, f: fn(&Station) -> bool| Series::new(name.into(), stations.iter().map(|s| f(s)).collect::<Vec<_>>());
+    Ok(DataFrame::new(vec![
+        Series::new("station_id".into(), stations.iter().map(|s| s.id.as_str()).collect::<Vec<_>>()),
+        text("name", |s| s.name.as_deref()),
+        text("owner", |s| s.owner.as_deref()),
+        text("program", |s| s.program.as_deref()),
+        text("station_type", |s| s.station_type.as_deref()),
+        number("latitude", |s| s.lat),
+        number("longitude", |s| s.lon),
+        number("elevation", |s| s.elevation),
+        number("anemometer_height", |s| s.anemometer_height),
+        text("hull", |s| s.hull.as_deref()),
+        text("payload", |s| s.payload.as_deref()),
+        text("time_zone", |s| s.time_zone.as_deref()),
+        flag("met", |s| s.met),
+        flag("currents", |s| s.currents),
+        flag("water_quality", |s| s.water_quality),
+        flag("dart", |s| s.dart),
+    ])?)
+}
+
+/// Great-circle distance in kilometers between two `(lat, lon)` points in decimal degrees.
+pub(crate) fn haversine_km((lat1, lon1): (f64, f64), (lat2, lon2): (f64, f64)) -> f64 {
+    const EARTH_RADIUS_KM: f64 = 6371.0;
+    let (phi1, phi2) = (lat1.to_radians(), lat2.to_radians());
+    let dphi = (lat2 - lat1).to_radians();
+    let dlambda = (lon2 - lon1).to_radians();
+    let a = (dphi / 2.0).sin().powi(2) + phi1.cos() * phi2.cos() * (dlambda / 2.0).sin().powi(2);
+    2.0 * EARTH_RADIUS_KM * a.sqrt().asin()
+}
+
+/// Attributes of an element as owned name -> value pairs, with entity and character references
+/// (`&amp;`, `&#39;`) in the values resolved.
+fn attributes(e: &BytesStart) -> Result<HashMap<String, String>> {
+    e.attributes()
+        .filter_map(|a| a.ok())
+        .map(|a| {
+            let value = a.unescape_value().map_err(|err| NdbcError::Parse(format!("station metadata attribute: {}", err)))?;
+            Ok((String::from_utf8_lossy(a.key.as_ref()).into_owned(), value.into_owned()))
+        })
+        .collect()
+}
+
+/// Parse a `YYYY-MM-DD` metadata date; empty or malformed values yield `None`.
+fn parse_meta_date(s: &str) -> Option<Date> {
+    Date::parse(s, format_description!("[year]-[month]-[day]")).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn station_table_skips_short_lines() -> Result<()> {
+        let text = "# STATION_ID | OWNER | TTYPE | HULL | NAME | PAYLOAD | LOCATION | TIMEZONE | FORECAST | NOTE\n\
+                    0Y2W3|CG|USCG MARINE RADIO|||||\n\
+                    41001|N|Weather Buoy|6N|EAST HATTERAS|AMPS|34.675 N 72.698 W (34&#176;40'31\" N 72&#176;41'54\" W)|E||\n\
+                    42002|N|Weather Buoy\n";
+        let stations = parse_station_table(text)?;
+        let mut ids: Vec<&str> = stations.keys().map(String::as_str).collect();
+        ids.sort();
+        assert_eq!(ids, ["0Y2W3", "41001"]);
+        assert_eq!(stations.get("41001").and_then(|s| s.lat), Some(34.675));
+        Ok(())
+    }
+
+    #[test]
+    fn haversine_matches_known_distances() {
+        let close = |a: f64, b: f64| (a - b).abs() < 0.01;
+        // One degree along a meridian on a 6371 km sphere.
+        assert!(close(haversine_km((0.0, 0.0), (1.0, 0.0)), 111.195));
+        // JFK to LAX.
+        assert!(close(haversine_km((40.6413, -73.7781), (33.9416, -118.4085)), 3974.336));
+        // Longitude wraps: 179.9°E to 179.9°W is 0.2° apart, either way round.
+        assert!(close(haversine_km((0.0, 179.9), (0.0, -179.9)), 22.239));
+        assert!(close(haversine_km((0.0, -179.9), (0.0, 179.9)), 22.239));
+        assert!(close(haversine_km((0.0, 180.0), (0.0, -180.0)), 0.0));
+        assert!(close(haversine_km((90.0, 0.0), (90.0, 123.0)), 0.0));
+    }
+
+    #[test]
+    fn metadata_attributes_are_unescaped() -> Result<()> {
+        let stations = parse_station_metadata(include_bytes!("../data-samples/stationmetadata.xml"))?;
+        let owner = stations.get("42092").and_then(|s| s.owner.as_deref());
+        assert_eq!(owner, Some("Conrad Blucher Institute (CBI) for Surveying and Science, Texas A&M University-Corpus Christi"));
+        Ok(())
+    }
+}
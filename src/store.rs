@@ -0,0 +1,52 @@
+//! Saving station files to an object store (S3, GCS, Azure, ...) instead of the local output
+//! directory (feature `object-store`).
+
+use crate::{coords, CoordsAs, IndexEntry, NdbcData, NdbcError, OutputFormat, Result};
+use polars::prelude::*;
+pub use object_store::path::Path as StorePath;
+pub use object_store::ObjectStore;
+use std::path::PathBuf;
+use std::sync::Arc;
+use tracing::info;
+
+/// Object store and key prefix that station files are `put` under.
+#[derive(Clone, Debug)]
+pub(crate) struct OutputStore {
+    pub(crate) store: Arc<dyn ObjectStore>,
+    pub(crate) prefix: StorePath,
+}
+
+impl NdbcData {
+    /// Create an instance that saves station files to `store` under `prefix` (e.g. an S3 store
+    /// and `ndbc/realtime`) instead of a local directory. Nothing is created on the local
+    /// filesystem. Use [`NdbcDataBuilder::store`](crate::NdbcDataBuilder::store) to combine a
+    /// store with other options.
+    pub fn new_with_store(store: Arc<dyn ObjectStore>, prefix: StorePath) -> Result<Self> {
+        Self::builder().in_memory().store(store, prefix).build()
+    }
+
+    /// Encode a parsed station frame in the configured format and `put` it as
+    /// `<prefix>/<station>.<ext>`, replacing any object already there. Parquet objects record
+    /// `source_url` in their footer like local files. The whole file is
+    /// buffered in memory; merging ([`Revision`](crate::Revision)) and partitioning apply to local
+    /// output only.
+    pub(crate) async fn put_station_frame(&self, output: &OutputStore, station: &str, df: DataFrame, source_url: &str) -> Result<IndexEntry> {
+        let df = self.with_station_columns(station, df)?;
+        let location = output.prefix.child(format!("{}.{}", station, self.format.extension()));
+        let entry = self.index_entry(station, &df, PathBuf::from(location.as_ref()))?;
+        let (mut df, mut key_values) = match (self.format, self.coords_as) {
+            (OutputFormat::Parquet, CoordsAs::Metadata) => coords::coords_to_metadata(df)?,
+            _ => (df, Vec::new()),
+        };
+        key_values.extend(self.provenance_key_values(Some(station), Some(source_url)));
+        let mut buf = Vec::new();
+        self.encode_frame(&mut df, &mut buf, key_values)?;
+        info!(store = %output.store, location = %location, rows = df.height(), bytes = buf.len(), format = ?self.format, "writing output");
+        output
+            .store
+            .put(&location, buf.into())
+            .await
+            .map_err(|source| NdbcError::Store { station: station.to_string(), source })?;
+        Ok(entry)
+    }
+}
@@ -0,0 +1,136 @@
+//! Parse anomaly counts, the limits that turn them into failures in strict mode, and the
+//! per-file sidecar listing them.
+
+use crate::{NdbcData, NdbcError, Result};
+use serde::Serialize;
+use std::fs;
+use tracing::info;
+
+/// Why a line was flagged while parsing.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ParseIssueKind {
+    /// Fewer than five fields; the line was skipped.
+    TooFewFields,
+    /// The date or time fields do not form a valid time; the line was skipped.
+    InvalidTime,
+    /// The time is too far from the epoch to store; the line was skipped.
+    TimeOutOfRange,
+    /// The field count differs from the header's; values were matched to columns by position.
+    FieldCountMismatch,
+    /// The timestamp repeats an earlier row's; the row was kept.
+    DuplicateTime,
+}
+
+/// One flagged line of a parsed file.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct ParseIssue {
+    /// 1-based line number in the file.
+    pub line: usize,
+    pub kind: ParseIssueKind,
+    /// The line as it appears in the file, trimmed.
+    pub text: String,
+}
+
+/// Anomalies seen while parsing one standard met file with
+/// [`parse_std_met_with_stats`](crate::parse_std_met_with_stats).
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct ParseStats {
+    /// Data rows kept.
+    pub rows: usize,
+    /// Lines skipped for having too few fields or an unparseable date or time.
+    pub malformed_lines: usize,
+    /// Rows whose field count differs from the header's.
+    pub token_mismatches: usize,
+    /// Rows repeating the timestamp of an earlier row.
+    pub duplicate_times: usize,
+    /// Every line behind the counts above, in file order.
+    pub issues: Vec<ParseIssue>,
+}
+
+impl ParseStats {
+    /// Count an anomaly of `kind` on 1-based `line` with content `text`.
+    pub(crate) fn record(&mut self, kind: ParseIssueKind, line: usize, text: &str) {
+        match kind {
+            ParseIssueKind::TooFewFields | ParseIssueKind::InvalidTime | ParseIssueKind::TimeOutOfRange => {
+                self.malformed_lines += 1
+            }
+            ParseIssueKind::FieldCountMismatch => self.token_mismatches += 1,
+            ParseIssueKind::DuplicateTime => self.duplicate_times += 1,
+        }
+        self.issues.push(ParseIssue { line, kind, text: text.to_string() });
+    }
+
+    /// True when nothing unusual was seen.
+    pub fn is_clean(&self) -> bool {
+        self.malformed_lines == 0 && self.token_mismatches == 0 && self.duplicate_times == 0
+    }
+}
+
+/// Per-file limits on [`ParseStats`] counts; a file over any limit fails its station with
+/// [`NdbcError::ParseAnomalies`]. The default tolerates nothing.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct StrictLimits {
+    pub max_malformed_lines: usize,
+    pub max_token_mismatches: usize,
+    pub max_duplicate_times: usize,
+}
+
+impl StrictLimits {
+    /// Fail when `stats` exceed any limit, naming every count that does.
+    pub fn check(&self, stats: &ParseStats) -> Result<()> {
+        let over: Vec<String> = [
+            ("malformed lines", stats.malformed_lines, self.max_malformed_lines),
+            ("token count mismatches", stats.token_mismatches, self.max_token_mismatches),
+            ("duplicate timestamps", stats.duplicate_times, self.max_duplicate_times),
+        ]
+        .into_iter()
+        .filter(|(_, count, max)| count > max)
+        .map(|(what, count, max)| format!("{} {} (max {})", count, what, max))
+        .collect();
+        if over.is_empty() {
+            Ok(())
+        } else {
+            Err(NdbcError::ParseAnomalies(over.join(", ")))
+        }
+    }
+}
+
+/// File suffix of the sidecar written by [`NdbcData::save_parse_issues`].
+pub const PARSE_ISSUES_SUFFIX: &str = "parse_issues.json";
+
+/// JSON shape of a parse issues sidecar.
+#[derive(Serialize)]
+struct Sidecar<'a> {
+    station: &'a str,
+    #[serde(flatten)]
+    stats: &'a ParseStats,
+}
+
+impl NdbcData {
+    /// Write `stats` for `station` to `<stem>.parse_issues.json` in the output directory,
+    /// replacing it atomically: the counts plus one entry per flagged line (`line`, `kind`,
+    /// `text`). A clean file removes any sidecar left by an earlier run instead.
+    pub fn save_parse_issues(&self, station: &str, stem: &str, stats: &ParseStats) -> Result<()> {
+        let out_dir = self.out_dir.as_ref().ok_or(NdbcError::NoOutputDir)?;
+        let out_path = out_dir.join(format!("{}.{}", stem, PARSE_ISSUES_SUFFIX));
+        if stats.is_clean() {
+            return match fs::remove_file(&out_path) {
+                Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+                _ => Ok(()),
+            };
+        }
+        let json = serde_json::to_vec_pretty(&Sidecar { station, stats })
+            .map_err(|e| NdbcError::Parse(format!("parse issues: {}", e)))?;
+        if let Some(parent) = out_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let tmp_path = out_path.with_extension("json.part");
+        info!(file = %out_path.display(), issues = stats.issues.len(), "writing parse issues");
+        let written = fs::write(&tmp_path, json).and_then(|_| fs::rename(&tmp_path, &out_path));
+        if written.is_err() {
+            let _ = fs::remove_file(&tmp_path);
+        }
+        Ok(written?)
+    }
+}
@@ -0,0 +1,109 @@
+//! Realtime spectral wave summary (`realtime2/<station>.spec`): wave height, swell and wind wave
+//! parameters derived by NDBC from the wave spectrum.
+
+use crate::{find_std_met_header, http, Delimiter, parse_value, row_time, FetchOptions, NdbcData, NdbcError, Result};
+use polars::prelude::*;
+use reqwest::StatusCode;
+use tracing::{info, warn};
+
+/// Columns kept as text; the rest are parsed as `f64`.
+const TEXT: [&str; 3] = ["SwD", "WWD", "STEEPNESS"];
+/// Frame columns after `time_ms`, in file order.
+const COLUMNS: [&str; 10] = ["WVHT", "SwH", "SwP", "WWH", "WWP", "SwD", "WWD", "STEEPNESS", "APD", "MWD"];
+
+/// Parse a realtime spectral wave summary file into a DataFrame with `time_ms` (milliseconds
+/// since the Unix epoch, UTC) followed by:
+///
+/// - `WVHT`: significant wave height (m)
+/// - `SwH`: swell height (m)
+/// - `SwP`: swell period (s)
+/// - `WWH`: wind wave height (m)
+/// - `WWP`: wind wave period (s)
+/// - `SwD`: swell direction, the compass point waves come from (`ENE`), as text
+/// - `WWD`: wind wave direction, likewise text
+/// - `STEEPNESS`: NDBC's steepness class (`SWELL`, `AVERAGE`, `STEEP`, `VERY_STEEP`), as text
+/// - `APD`: average wave period (s)
+/// - `MWD`: direction of the waves at the dominant period (degrees true)
+///
+/// `MM` and `N/A` become null; columns the header lacks are null throughout. Rows whose time does
+/// not parse are skipped with a warning. Returns an empty frame when no header is found.
+pub fn parse_wave_summary_to_df(text: &str) -> Result<DataFrame> {
+    let mut lines = text.lines().peekable();
+    let Some(header) = find_std_met_header(&mut lines, Delimiter::Whitespace) else {
+        return Ok(DataFrame::empty());
+    };
+    let idx = |name: &str| header.cols.iter().position(|c| c == name);
+    let minute_idx = idx("mm");
+    let col_idx: Vec<Option<usize>> = COLUMNS.iter().map(|c| idx(c)).collect();
+
+    let mut times: Vec<i64> = Vec::new();
+    let mut numeric: Vec<Vec<Option<f64>>> = vec![Vec::new(); COLUMNS.len()];
+    let mut text_cols: Vec<Vec<Option<String>>> = vec![Vec::new(); COLUMNS.len()];
+    for line in lines {
+        let l = line.trim();
+        if l.is_empty() {
+            continue;
+        }
+        if l.starts_with('#') {
+            break;
+        }
+        let toks: Vec<&str> = l.split_whitespace().collect();
+        let Some(ts_ms) = row_time(&toks, minute_idx, None).and_then(|dt| dt.unix_timestamp().checked_mul(1000)) else {
+            warn!(line = l, "invalid wave summary time; skipping row");
+            continue;
+        };
+        times.push(ts_ms);
+        for (i, name) in COLUMNS.iter().enumerate() {
+            let tok = col_idx[i].and_then(|c| toks.get(c)).copied().filter(|t| !matches!(*t, "MM" | "N/A"));
+            if TEXT.contains(name) {
+                text_cols[i].push(tok.map(String::from));
+            } else {
+                numeric[i].push(tok.and_then(parse_value));
+            }
+        }
+    }
+
+    let mut series = vec![Series::new("time_ms".into(), times)];
+    for (i, name) in COLUMNS.iter().enumerate() {
+        series.push(if TEXT.contains(name) {
+            Series::new((*name).into(), std::mem::take(&mut text_cols[i]))
+        } else {
+            Series::new((*name).into(), std::mem::take(&mut numeric[i]))
+        });
+    }
+    Ok(DataFrame::new(series)?)
+}
+
+impl NdbcData {
+    /// Fetch the realtime spectral wave summary (`data/realtime2/<station>.spec`, the last ~45
+    /// days) for a station and return it as parsed by [`parse_wave_summary_to_df`], with a
+    /// `station_id` column added. Stations without a wave sensor fail with
+    /// [`NdbcError::StationNotFound`].
+    pub async fn fetch_wave_summary_df(&self, station: &str) -> Result<DataFrame> {
+        self.fetch_wave_summary_df_with(station, &FetchOptions::default()).await
+    }
+
+    /// [`fetch_wave_summary_df`](Self::fetch_wave_summary_df) bounded by `opts`.
+    pub async fn fetch_wave_summary_df_with(&self, station: &str, opts: &FetchOptions) -> Result<DataFrame> {
+        let url = self.ndbc_url(&format!("/data/realtime2/{}.spec", station));
+        info!(station = %station, %url, "downloading wave summary");
+        let text = opts
+            .guard(async {
+                let resp = http::get(&self.client, &url, &self.retry, &self.headers).await?;
+                if resp.status() == StatusCode::NOT_FOUND {
+                    return Err(NdbcError::StationNotFound(station.to_string()));
+                }
+                crate::decode_text(&resp.error_for_status()?.bytes().await?)
+            })
+            .await?;
+
+        opts.check()?;
+        let mut df = parse_wave_summary_to_df(&text)?;
+        if df.height() == 0 {
+            return Err(NdbcError::EmptyData);
+        }
+        let station_id = Series::new("station_id".into(), vec![station; df.height()]);
+        df.with_column(station_id)?;
+        Ok(df)
+    }
+}
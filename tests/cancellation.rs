@@ -0,0 +1,61 @@
+//! A fetch cancelled while its download is still arriving returns `Cancelled` and leaves no
+//! partial (`.part`) or final file behind.
+
+mod common;
+
+use common::{sample, scratch_dir, MockServer, Route};
+use ndbc_data::{CancellationToken, FetchOptions, NdbcData, NdbcError};
+use std::fs;
+use std::path::Path;
+use std::time::{Duration, Instant};
+use time::macros::date;
+
+const STALL: Duration = Duration::from_secs(30);
+
+/// Names of the files left in `dir`.
+fn files(dir: &Path) -> Vec<String> {
+    fs::read_dir(dir).map(|entries| entries.flatten().map(|e| e.file_name().to_string_lossy().into_owned()).collect()).unwrap_or_default()
+}
+
+/// Options cancelled `after` from now.
+fn cancel_after(after: Duration) -> FetchOptions {
+    let token = CancellationToken::new();
+    let cancel = token.clone();
+    tokio::spawn(async move {
+        tokio::time::sleep(after).await;
+        cancel.cancel();
+    });
+    FetchOptions::default().with_cancel(token)
+}
+
+#[tokio::test]
+async fn cancelling_a_station_mid_download_leaves_no_file() {
+    let text = fs::read(sample("realtimedata.txt")).expect("fixture");
+    let server = MockServer::start([("/data/realtime2/41001.txt", Route::ok(text.clone()).stall_after(text.len() / 2, STALL))]);
+    let dir = scratch_dir("cancel-station");
+    let data = NdbcData::builder().base_url(&server.url).out_dir(&dir).max_retries(0).build().expect("build");
+
+    let started = Instant::now();
+    let result = data.fetch_and_save_station_with("41001", &cancel_after(Duration::from_millis(300))).await;
+    assert!(matches!(result, Err(NdbcError::Cancelled)), "{:?}", result);
+    assert!(started.elapsed() < STALL);
+    assert_eq!(files(&dir), Vec::<String>::new());
+}
+
+#[tokio::test]
+async fn cancelling_a_range_mid_download_removes_the_part_file() {
+    // 2024 arrives whole and is written; 2025 hangs halfway, after the `.part` file has rows.
+    let text = fs::read_to_string(sample("realtimedata.txt")).expect("fixture");
+    let year_2024 = text.lines().map(|line| line.replacen("2025 ", "2024 ", 1)).collect::<Vec<_>>().join("\n");
+    let server = MockServer::start([
+        ("/data/historical/stdmet/41001h2024.txt.gz", Route::ok(year_2024)),
+        ("/data/historical/stdmet/41001h2025.txt.gz", Route::ok(text.clone()).stall_after(text.len() / 2, STALL)),
+    ]);
+    let dir = scratch_dir("cancel-range");
+    let data = NdbcData::builder().base_url(&server.url).out_dir(&dir).max_retries(0).build().expect("build");
+
+    let opts = cancel_after(Duration::from_millis(500));
+    let result = data.save_range_with("41001", date!(2024 - 01 - 01), date!(2025 - 12 - 31), &opts).await;
+    assert!(matches!(result, Err(NdbcError::Cancelled)), "{:?}", result);
+    assert_eq!(files(&dir), Vec::<String>::new());
+}
@@ -0,0 +1,46 @@
+//! The `ndbc-data` binary is a thin front end over the library: for the same download it must
+//! write exactly the file [`NdbcData`] writes.
+
+mod common;
+
+use common::{sample, scratch_dir, MockServer, Route};
+use ndbc_data::{NdbcData, OutputFormat};
+use std::fs;
+use std::process::Command;
+
+fn serve_fixture() -> MockServer {
+    MockServer::start([
+        ("/metadata/stationmetadata.xml", Route::ok(fs::read(sample("stationmetadata.xml")).expect("metadata fixture"))),
+        ("/data/realtime2/41001.txt", Route::ok(fs::read(sample("realtimedata.txt")).expect("realtime fixture"))),
+    ])
+}
+
+#[tokio::test]
+async fn cli_writes_the_same_csv_as_the_library() {
+    let server = serve_fixture();
+
+    let cli_dir = scratch_dir("cli-out");
+    let output = Command::new(env!("CARGO_BIN_EXE_ndbc-data"))
+        .args(["--base-url", &server.url, "--format", "csv", "--max-retries", "0", "--out-dir"])
+        .arg(&cli_dir)
+        .arg("41001")
+        .output()
+        .expect("run ndbc-data");
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+
+    let lib_dir = scratch_dir("lib-out");
+    let mut data = NdbcData::builder()
+        .base_url(&server.url)
+        .out_dir(&lib_dir)
+        .format(OutputFormat::Csv)
+        .max_retries(0)
+        .build()
+        .expect("build");
+    data.fetch_station_metadata().await.expect("metadata");
+    data.fetch_and_save_station("41001").await.expect("save");
+
+    let cli = fs::read(cli_dir.join("41001.csv")).expect("CLI output");
+    let lib = fs::read(lib_dir.join("41001.csv")).expect("library output");
+    assert!(!lib.is_empty());
+    assert_eq!(cli, lib);
+}
@@ -0,0 +1,171 @@
+//! Minimal HTTP server for integration tests: serves fixed responses by path from a background
+//! thread, one connection per request.
+#![allow(dead_code)]
+
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+/// Response served for one path.
+#[derive(Clone, Debug)]
+pub struct Route {
+    status: u16,
+    headers: Vec<(String, String)>,
+    body: Vec<u8>,
+    delay: Duration,
+    stall: Option<(usize, Duration)>,
+}
+
+impl Route {
+    /// `200 OK` with `body`.
+    pub fn ok(body: impl Into<Vec<u8>>) -> Self {
+        Self { status: 200, headers: Vec::new(), body: body.into(), delay: Duration::ZERO, stall: None }
+    }
+
+    /// An empty response with `status`.
+    pub fn status(status: u16) -> Self {
+        Self { status, ..Self::ok(Vec::new()) }
+    }
+
+    /// Add a response header.
+    pub fn header(mut self, name: &str, value: &str) -> Self {
+        self.headers.push((name.to_string(), value.to_string()));
+        self
+    }
+
+    /// Wait this long before answering.
+    pub fn delay(mut self, delay: Duration) -> Self {
+        self.delay = delay;
+        self
+    }
+
+    /// Send the headers and the first `bytes` of the body, then hang for `duration` before the
+    /// rest: a download cut off mid-body.
+    pub fn stall_after(mut self, bytes: usize, duration: Duration) -> Self {
+        self.stall = Some((bytes, duration));
+        self
+    }
+}
+
+/// A running server; lives until the test process exits.
+pub struct MockServer {
+    /// `http://127.0.0.1:<port>`, for `base_url`.
+    pub url: String,
+    requests: Arc<Mutex<Vec<Request>>>,
+}
+
+/// A request the server received.
+#[derive(Clone, Debug)]
+pub struct Request {
+    pub path: String,
+    /// Header lines as sent, names lowercased.
+    pub headers: Vec<(String, String)>,
+}
+
+impl MockServer {
+    /// Serve `routes` (path -> response); any other path gets `404`.
+    pub fn start<'a>(routes: impl IntoIterator<Item = (&'a str, Route)>) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").expect("bind mock server");
+        let url = format!("http://{}", listener.local_addr().expect("mock server address"));
+        let routes: Arc<HashMap<String, Route>> = Arc::new(routes.into_iter().map(|(path, route)| (path.to_string(), route)).collect());
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let log = Arc::clone(&requests);
+        thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                let routes = Arc::clone(&routes);
+                let log = Arc::clone(&log);
+                thread::spawn(move || serve(stream, &routes, &log));
+            }
+        });
+        Self { url, requests }
+    }
+
+    /// Every request received so far, in arrival order.
+    pub fn requests(&self) -> Vec<Request> {
+        self.requests.lock().map(|r| r.clone()).unwrap_or_default()
+    }
+}
+
+fn serve(mut stream: TcpStream, routes: &HashMap<String, Route>, log: &Mutex<Vec<Request>>) {
+    let mut reader = BufReader::new(match stream.try_clone() {
+        Ok(s) => s,
+        Err(_) => return,
+    });
+    let mut request_line = String::new();
+    if reader.read_line(&mut request_line).is_err() {
+        return;
+    }
+    let mut headers = Vec::new();
+    let mut line = String::new();
+    while reader.read_line(&mut line).is_ok_and(|n| n > 0) && line != "\r\n" {
+        if let Some((name, value)) = line.trim_end().split_once(':') {
+            headers.push((name.to_lowercase(), value.trim().to_string()));
+        }
+        line.clear();
+    }
+    let path = request_line.split_whitespace().nth(1).unwrap_or("/");
+    if let Ok(mut log) = log.lock() {
+        log.push(Request { path: path.to_string(), headers });
+    }
+    let not_found = Route::status(404);
+    let route = routes.get(path).unwrap_or(&not_found);
+    thread::sleep(route.delay);
+    let mut head = format!("HTTP/1.1 {} X\r\nContent-Length: {}\r\nConnection: close\r\n", route.status, route.body.len());
+    for (name, value) in &route.headers {
+        head.push_str(&format!("{}: {}\r\n", name, value));
+    }
+    head.push_str("\r\n");
+    let (first, rest) = match route.stall {
+        Some((bytes, _)) => route.body.split_at(bytes.min(route.body.len())),
+        None => (&route.body[..], &[][..]),
+    };
+    if stream.write_all(head.as_bytes()).and_then(|_| stream.write_all(first)).and_then(|_| stream.flush()).is_err() {
+        return;
+    }
+    if let Some((_, duration)) = route.stall {
+        thread::sleep(duration);
+    }
+    let _ = stream.write_all(rest);
+}
+
+/// Path of a file under `data-samples/`.
+pub fn sample(name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("data-samples").join(name)
+}
+
+/// Empty scratch directory for one test.
+pub fn scratch_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("ndbc-data-it-{}-{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    dir
+}
+
+/// Peak resident memory growth from a starting point, read from `/proc/self/status` (Linux).
+pub struct PeakRss {
+    baseline_kb: u64,
+}
+
+impl PeakRss {
+    /// Reset the process's high-water mark to its current resident size and start measuring;
+    /// `None` where `/proc` does not support that.
+    pub fn start() -> Option<Self> {
+        std::fs::write("/proc/self/clear_refs", "5").ok()?;
+        Some(Self { baseline_kb: status_kb("VmRSS:")? })
+    }
+
+    /// How far the high-water mark rose above the resident size at [`start`](Self::start), in
+    /// bytes.
+    pub fn growth(&self) -> u64 {
+        status_kb("VmHWM:").unwrap_or_default().saturating_sub(self.baseline_kb) * 1024
+    }
+}
+
+fn status_kb(key: &str) -> Option<u64> {
+    let status = std::fs::read_to_string("/proc/self/status").ok()?;
+    let line = status.lines().find(|l| l.starts_with(key))?;
+    line[key.len()..].trim().trim_end_matches("kB").trim().parse().ok()
+}
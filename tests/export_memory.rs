@@ -0,0 +1,56 @@
+//! `export` streams: a 1M-row archive goes out as CSV without ever being held in memory whole.
+
+mod common;
+
+use common::{scratch_dir, PeakRss};
+use ndbc_data::{ExportFormat, NdbcData, STD_MET_COLUMNS};
+use polars::prelude::*;
+use std::fs;
+use std::io;
+
+const STATIONS: usize = 20;
+const ROWS_PER_STATION: usize = 50_000;
+
+/// Write `<station>.parquet` files holding `ROWS_PER_STATION` 10-minute rows each, every met
+/// column populated.
+fn write_archive(dir: &std::path::Path) -> PolarsResult<()> {
+    fs::create_dir_all(dir)?;
+    for n in 0..STATIONS {
+        let station = format!("{:05}", 41000 + n);
+        let start_ms = 1_577_836_800_000i64; // 2020-01-01T00:00:00Z
+        let times: Vec<i64> = (0..ROWS_PER_STATION as i64).map(|i| start_ms + i * 600_000).collect();
+        let mut columns = vec![Series::new("time_ms".into(), times)
+            .cast(&DataType::Datetime(TimeUnit::Milliseconds, Some("UTC".into())))?];
+        for (c, (name, _)) in STD_MET_COLUMNS.iter().enumerate() {
+            let values: Vec<f64> = (0..ROWS_PER_STATION).map(|i| (c * 10 + i % 10) as f64 + 0.25).collect();
+            columns.push(Series::new((*name).into(), values));
+        }
+        columns.push(Series::new("station_id".into(), vec![station.clone(); ROWS_PER_STATION]));
+        columns.push(Series::new("latitude".into(), vec![30.0 + n as f64; ROWS_PER_STATION]));
+        columns.push(Series::new("longitude".into(), vec![-70.0 - n as f64; ROWS_PER_STATION]));
+        let mut df = DataFrame::new(columns)?;
+        ParquetWriter::new(fs::File::create(dir.join(format!("{}.parquet", station)))?).finish(&mut df)?;
+    }
+    Ok(())
+}
+
+#[test]
+fn million_row_export_stays_within_one_station_of_memory() {
+    let dir = scratch_dir("export-memory");
+    write_archive(&dir).expect("synthetic archive");
+    let data = NdbcData::builder().out_dir(&dir).build().expect("build");
+
+    let Some(peak) = PeakRss::start() else {
+        eprintln!("skipping the memory assertion: /proc/self/clear_refs is unavailable");
+        return;
+    };
+    let rows = data.export(ExportFormat::Csv, io::sink()).expect("export");
+    let growth = peak.growth();
+
+    assert_eq!(rows, STATIONS * ROWS_PER_STATION);
+    // Holding the whole archive would take at least its 17 f64 columns: 136 MB for 1M rows.
+    // One station of 50k rows is ~7 MB; a third of the whole archive leaves room for allocator
+    // slack and polars' read buffers (about 27 MB in practice).
+    let whole_archive = (STATIONS * ROWS_PER_STATION * (STD_MET_COLUMNS.len() + 3) * 8) as u64;
+    assert!(growth < whole_archive / 3, "peak memory grew by {} MB", growth / (1024 * 1024));
+}
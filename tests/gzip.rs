@@ -0,0 +1,44 @@
+//! Gzip bodies are recognised by their magic bytes, whatever the headers or URL say.
+
+mod common;
+
+use common::{sample, MockServer, Route};
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use ndbc_data::NdbcData;
+use std::fs;
+use std::io::Write;
+
+fn gzip(bytes: &[u8]) -> Vec<u8> {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(bytes).expect("compress");
+    encoder.finish().expect("compress")
+}
+
+fn client(server: &MockServer) -> NdbcData {
+    NdbcData::builder().base_url(&server.url).in_memory().max_retries(0).build().expect("build")
+}
+
+#[tokio::test]
+async fn gzip_body_with_content_encoding() {
+    let text = fs::read_to_string(sample("realtimedata.txt")).expect("fixture");
+    let server = MockServer::start([("/data/realtime2/41001.txt", Route::ok(gzip(text.as_bytes())).header("Content-Encoding", "gzip"))]);
+    assert_eq!(client(&server).fetch_station_raw("41001").await.expect("fetch"), text);
+}
+
+#[tokio::test]
+async fn gzip_body_without_content_encoding() {
+    let text = fs::read_to_string(sample("realtimedata.txt")).expect("fixture");
+    let server = MockServer::start([("/data/realtime2/41001.txt", Route::ok(gzip(text.as_bytes())))]);
+    assert_eq!(client(&server).fetch_station_raw("41001").await.expect("fetch"), text);
+}
+
+#[tokio::test]
+async fn plain_text_behind_a_gz_url() {
+    let text = fs::read_to_string(sample("realtimedata.txt")).expect("fixture");
+    let server = MockServer::start([("/data/historical/stdmet/41001h2025.txt.gz", Route::ok(text.clone()))]);
+    let data = client(&server);
+    let fetched = data.fetch_historical_stdmet("41001", 2025).await.expect("fetch");
+    assert!(fetched.height() > 0);
+    assert!(fetched.equals_missing(&data.frame_from_text("41001", &text).expect("parse")));
+}
@@ -0,0 +1,95 @@
+//! Custom request headers reach the server on every fetch path but their secrets never reach the
+//! logs.
+
+mod common;
+
+use common::{sample, MockServer, Route};
+use ndbc_data::NdbcData;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue, AUTHORIZATION};
+use std::fs;
+use std::io::{self, Write};
+use std::sync::{Arc, Mutex};
+
+const API_KEY: &str = "sk-live-0123456789";
+const BEARER: &str = "Bearer eyJhbGciOiJIUzI1NiJ9.secret";
+const SESSION: &str = "session-abcdef";
+const MARKED: &str = "marked-sensitive-value";
+
+/// Log output collected in memory.
+#[derive(Clone, Default)]
+struct Captured(Arc<Mutex<Vec<u8>>>);
+
+impl Write for Captured {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.lock().map_err(|_| io::Error::other("poisoned"))?.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[tokio::test]
+async fn headers_are_sent_and_secrets_redacted_in_logs() {
+    let server = MockServer::start([
+        ("/metadata/stationmetadata.xml", Route::ok(fs::read(sample("stationmetadata.xml")).expect("metadata fixture"))),
+        ("/data/realtime2/41001.txt", Route::ok(fs::read(sample("realtimedata.txt")).expect("realtime fixture"))),
+    ]);
+
+    let mut marked = HeaderValue::from_static(MARKED);
+    marked.set_sensitive(true);
+    let mut data = NdbcData::builder()
+        .base_url(&server.url)
+        .in_memory()
+        .max_retries(0)
+        .default_header(HeaderName::from_static("x-api-key"), HeaderValue::from_static(API_KEY))
+        .default_header(AUTHORIZATION, HeaderValue::from_static(BEARER))
+        .default_header(HeaderName::from_static("x-tenant"), HeaderValue::from_static("tenant-7"))
+        .default_header(HeaderName::from_static("x-signature"), marked)
+        .request_headers(|url| {
+            let mut headers = HeaderMap::new();
+            headers.insert(HeaderName::from_static("x-session-token"), HeaderValue::from_static(SESSION));
+            if let Ok(path) = HeaderValue::from_str(url.path()) {
+                headers.insert(HeaderName::from_static("x-request-path"), path);
+            }
+            headers
+        })
+        .build()
+        .expect("build");
+
+    let logs = Captured::default();
+    let writer = logs.clone();
+    let subscriber = tracing_subscriber::fmt()
+        .with_max_level(tracing::Level::DEBUG)
+        .with_ansi(false)
+        .with_writer(move || writer.clone())
+        .finish();
+    {
+        let _guard = tracing::subscriber::set_default(subscriber);
+        data.fetch_station_metadata().await.expect("metadata");
+        data.fetch_station_raw("41001").await.expect("realtime");
+    }
+
+    // Every request carried the default and per-request headers.
+    let requests = server.requests();
+    assert_eq!(requests.len(), 2);
+    for request in &requests {
+        let header = |name: &str| request.headers.iter().find(|(n, _)| n == name).map(|(_, v)| v.as_str());
+        assert_eq!(header("x-api-key"), Some(API_KEY));
+        assert_eq!(header("authorization"), Some(BEARER));
+        assert_eq!(header("x-tenant"), Some("tenant-7"));
+        assert_eq!(header("x-signature"), Some(MARKED));
+        assert_eq!(header("x-session-token"), Some(SESSION));
+        assert_eq!(header("x-request-path"), Some(request.path.as_str()));
+    }
+
+    let logs = String::from_utf8(logs.0.lock().expect("log buffer").clone()).expect("UTF-8 logs");
+    assert!(logs.contains("adding request headers"), "{}", logs);
+    assert!(logs.contains("tenant-7"), "{}", logs);
+    assert!(logs.contains("/data/realtime2/41001.txt"), "{}", logs);
+    assert!(logs.contains("<redacted>"), "{}", logs);
+    for secret in [API_KEY, BEARER, SESSION, MARKED] {
+        assert!(!logs.contains(secret), "{} leaked into\n{}", secret, logs);
+    }
+}
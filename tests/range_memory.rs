@@ -0,0 +1,75 @@
+//! `save_range` writes Parquet a piece at a time: a 1M-row range never sits in memory whole.
+
+mod common;
+
+use common::{scratch_dir, MockServer, PeakRss, Route};
+use ndbc_data::NdbcData;
+use std::fmt::Write as _;
+use time::{Date, Month, PrimitiveDateTime, Time};
+
+const FIRST_YEAR: i32 = 2000;
+const YEARS: i32 = 20;
+const ROWS_PER_YEAR: i64 = 50_000;
+
+/// A yearly stdmet archive of `ROWS_PER_YEAR` 10-minute rows from January 1st.
+fn year_text(year: i32) -> String {
+    let mut text = String::from(
+        "#YY  MM DD hh mm WDIR WSPD GST  WVHT   DPD   APD MWD   PRES  ATMP  WTMP  DEWP  VIS PTDY  TIDE\n\
+         #yr  mo dy hr mn degT m/s  m/s     m   sec   sec degT   hPa  degC  degC  degC  nmi  hPa    ft\n",
+    );
+    let start = PrimitiveDateTime::new(Date::from_calendar_date(year, Month::January, 1).expect("January 1st"), Time::MIDNIGHT);
+    for i in 0..ROWS_PER_YEAR {
+        let t = start + time::Duration::minutes(10 * i);
+        let _ = writeln!(
+            text,
+            "{} {:02} {:02} {:02} {:02} {:3} {:4.1}  5.0   1.2     8   6.1 190 1012.{} 20.1  21.0  10.7 99.0 -0.4  99.0",
+            t.year(),
+            t.month() as u8,
+            t.day(),
+            t.hour(),
+            t.minute(),
+            i % 360,
+            (i % 200) as f64 / 10.0,
+            i % 10
+        );
+    }
+    text
+}
+
+#[tokio::test]
+async fn million_row_range_is_written_in_bounded_memory() {
+    let paths: Vec<String> = (FIRST_YEAR..FIRST_YEAR + YEARS).map(|y| format!("/data/historical/stdmet/41001h{}.txt.gz", y)).collect();
+    let server = MockServer::start(paths.iter().zip(FIRST_YEAR..).map(|(path, year)| (path.as_str(), Route::ok(year_text(year)))));
+    let dir = scratch_dir("range-memory");
+    let data = NdbcData::builder().base_url(&server.url).out_dir(&dir).max_retries(0).build().expect("build");
+    let start = Date::from_calendar_date(FIRST_YEAR, Month::January, 1).expect("start");
+    let end = Date::from_calendar_date(FIRST_YEAR + YEARS - 1, Month::December, 31).expect("end");
+
+    let Some(peak) = PeakRss::start() else {
+        eprintln!("skipping the memory assertion: /proc/self/clear_refs is unavailable");
+        return;
+    };
+    let path = data.save_range("41001", start, end).await.expect("save range");
+    let chunked = peak.growth();
+
+    let rows = YEARS as i64 * ROWS_PER_YEAR;
+    let file = std::fs::File::open(&path).expect("range file");
+    let metadata = polars_parquet::read::read_metadata(&mut std::io::BufReader::new(file)).expect("footer");
+    assert_eq!(metadata.num_rows as i64, rows);
+    assert_eq!(metadata.row_groups.len(), YEARS as usize);
+
+    // The same range merged in memory, for comparison: every downloaded file plus the full frame.
+    let peak = PeakRss::start().expect("clear_refs worked above");
+    let merged = data.fetch_range("41001", start, end).await.expect("fetch range");
+    let materialized = peak.growth();
+    assert_eq!(merged.height() as i64, rows);
+    drop(merged);
+
+    // About 90 MB against 465 MB when measured; allow for allocator and thread count differences.
+    assert!(
+        chunked < materialized / 3,
+        "save_range peak grew by {} MB, fetch_range by {} MB",
+        chunked / (1024 * 1024),
+        materialized / (1024 * 1024)
+    );
+}
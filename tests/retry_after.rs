@@ -0,0 +1,28 @@
+//! A `429` asking for a long `Retry-After` waits no longer than the retry policy's cap.
+
+mod common;
+
+use common::{MockServer, Route};
+use ndbc_data::NdbcData;
+use std::time::{Duration, Instant};
+
+#[tokio::test]
+async fn retry_after_is_capped_at_max_delay() {
+    let server = MockServer::start([("/data/realtime2/41001.txt", Route::status(429).header("Retry-After", "3600"))]);
+    let data = NdbcData::builder()
+        .base_url(&server.url)
+        .in_memory()
+        .max_retries(2)
+        .retry_max_delay(Duration::from_millis(200))
+        .build()
+        .expect("build");
+
+    let started = Instant::now();
+    let result = data.fetch_station_raw("41001").await;
+    let elapsed = started.elapsed();
+
+    assert!(result.is_err());
+    assert_eq!(server.requests().len(), 3);
+    // Two capped waits of 200 ms, not two hours.
+    assert!(elapsed >= Duration::from_millis(400) && elapsed < Duration::from_secs(5), "{:?}", elapsed);
+}
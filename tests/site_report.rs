@@ -0,0 +1,186 @@
+//! `site_report` end to end against the mock server: the same inputs give the same report tree.
+
+mod common;
+
+use common::{scratch_dir, MockServer, Route};
+use ndbc_data::{NdbcData, SiteReportIndex, SiteReportOptions, EXCEEDANCE_THRESHOLDS, INDEX_FILE, STD_MET_COLUMNS};
+use std::fs;
+use std::path::Path;
+use std::time::Duration;
+use time::OffsetDateTime;
+
+const METADATA: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<stations created="2025-01-10T06:00:02Z">
+ <station id="ALPHA" name="Alpha Buoy" owner="NDBC" pgm="NDBC Meteorological/Ocean" type="buoy">
+  <history start="2020-01-01" stop="" lat="36.8" lng="-121.9" met="y" hull="3D" anemom_height="4.1"/>
+ </station>
+ <station id="BRAVO" name="Bravo, Point" owner="NDBC" pgm="NDBC Meteorological/Ocean" type="fixed">
+  <history start="2020-01-01" stop="" lat="37.3" lng="-121.9" met="y" hull="" anemom_height="10"/>
+ </station>
+ <station id="CHARL" name="Charlie Buoy" owner="NDBC" pgm="NDBC Meteorological/Ocean" type="buoy">
+  <history start="2020-01-01" stop="" lat="36.3" lng="-121.9" met="y" hull="3D" anemom_height="4.1"/>
+ </station>
+ <station id="DELTA" name="Delta Buoy" owner="NDBC" pgm="NDBC Meteorological/Ocean" type="buoy">
+  <history start="2020-01-01" stop="" lat="38.8" lng="-121.9" met="y" hull="3D" anemom_height="4.1"/>
+ </station>
+</stations>
+"#;
+
+/// A realtime file with one row per `(hours ago, WSPD, ATMP)`, timed relative to now so the rows
+/// fall inside the report window whenever the test runs; every other value is fixed.
+fn realtime(rows: &[(i64, f64, &str)]) -> String {
+    let hour = OffsetDateTime::now_utc().replace_minute(0).and_then(|t| t.replace_second(0)).expect("top of the hour");
+    let mut text = String::from(
+        "#YY  MM DD hh mm WDIR WSPD GST  WVHT   DPD   APD MWD   PRES  ATMP  WTMP  DEWP  VIS PTDY  TIDE\n\
+         #yr  mo dy hr mn degT m/s  m/s     m   sec   sec degT   hPa  degC  degC  degC  nmi  hPa    ft\n",
+    );
+    for (hours_ago, wspd, atmp) in rows {
+        let t = hour - time::Duration::hours(*hours_ago);
+        text.push_str(&format!(
+            "{} {:02} {:02} {:02} 00 200 {:4.1}  5.0    MM    MM    MM  MM 1012.0 {:>5}    MM  10.0   MM   MM    MM\n",
+            t.year(),
+            t.month() as u8,
+            t.day(),
+            t.hour(),
+            wspd,
+            atmp
+        ));
+    }
+    text
+}
+
+fn serve() -> MockServer {
+    MockServer::start([
+        ("/metadata/stationmetadata.xml", Route::ok(METADATA)),
+        // ALPHA reports hourly with a four-hour gap and one gale-force hour.
+        ("/data/realtime2/ALPHA.txt", Route::ok(realtime(&[(1, 3.0, "14.0"), (2, 5.0, "15.0"), (3, 12.0, "14.0"), (7, 3.0, "13.0")]))),
+        ("/data/realtime2/BRAVO.txt", Route::ok(realtime(&[(1, 8.0, "MM")]))),
+        ("/data/realtime2/DELTA.txt", Route::ok(realtime(&[(1, 1.0, "9.0")]))),
+        // CHARL has no data file at all.
+    ])
+}
+
+async fn report(server: &MockServer, out: &Path) -> SiteReportIndex {
+    let mut data = NdbcData::builder().base_url(&server.url).in_memory().max_retries(0).build().expect("build");
+    data.fetch_station_metadata().await.expect("metadata");
+    let opts = SiteReportOptions { lat: 36.8, lon: -121.9, radius_km: 75.0, last: Duration::from_secs(2 * 86_400) };
+    data.site_report(&opts, out).await.expect("site report")
+}
+
+/// Every file under `dir`, relative, sorted.
+fn tree(dir: &Path) -> Vec<String> {
+    let mut files = Vec::new();
+    for entry in fs::read_dir(dir).expect("read report dir").flatten() {
+        let path = entry.path();
+        let name = entry.file_name().to_string_lossy().into_owned();
+        if path.is_dir() {
+            files.extend(tree(&path).into_iter().map(|f| format!("{}/{}", name, f)));
+        } else {
+            files.push(name);
+        }
+    }
+    files.sort();
+    files
+}
+
+#[tokio::test]
+async fn site_report_writes_a_deterministic_tree() {
+    let server = serve();
+    let out = scratch_dir("site-report");
+    let index = report(&server, &out).await;
+
+    // DELTA is 222 km out; CHARL is selected but fails.
+    assert_eq!(index.stations, ["ALPHA", "BRAVO", "CHARL"]);
+    let failed: Vec<&str> = index.failures.iter().map(|f| f.station.as_str()).collect();
+    assert_eq!(failed, ["CHARL"]);
+    assert_eq!(
+        tree(&out),
+        [
+            "exceedances.csv",
+            "gaps.csv",
+            "health.csv",
+            "index.json",
+            "observations/ALPHA.parquet",
+            "observations/BRAVO.parquet",
+            "stations.csv",
+            "summary.csv",
+            "wave_rose.csv",
+            "wind_rose.csv"
+        ]
+    );
+
+    let artifacts: Vec<(&str, &str, Option<&str>, usize)> =
+        index.artifacts.iter().map(|a| (a.kind, a.path.as_str(), a.station.as_deref(), a.rows)).collect();
+    assert_eq!(
+        artifacts,
+        [
+            ("stations", "stations.csv", None, 3),
+            ("summary", "summary.csv", None, 2 * STD_MET_COLUMNS.len()),
+            ("health", "health.csv", None, 2),
+            ("exceedances", "exceedances.csv", None, 2 * EXCEEDANCE_THRESHOLDS.len()),
+            ("gaps", "gaps.csv", None, 1),
+            ("wind_rose", "wind_rose.csv", None, 4),
+            ("wave_rose", "wave_rose.csv", None, 0),
+            ("observations", "observations/ALPHA.parquet", Some("ALPHA"), 4),
+            ("observations", "observations/BRAVO.parquet", Some("BRAVO"), 1),
+        ]
+    );
+
+    assert_eq!(
+        fs::read_to_string(out.join("stations.csv")).expect("stations.csv"),
+        "station_id,name,distance_km,latitude,longitude\n\
+         ALPHA,Alpha Buoy,0.0,36.8,-121.9\n\
+         BRAVO,\"Bravo, Point\",55.6,37.3,-121.9\n\
+         CHARL,Charlie Buoy,55.6,36.3,-121.9\n"
+    );
+    let summary = fs::read_to_string(out.join("summary.csv")).expect("summary.csv");
+    for line in ["ALPHA,WSPD,4,0,3,12,5.75", "ALPHA,ATMP,4,0,13,15,14", "BRAVO,WSPD,1,0,8,8,8", "BRAVO,ATMP,0,1,,,", "BRAVO,WVHT,0,1,,,"] {
+        assert!(summary.lines().any(|l| l == line), "missing {:?} in\n{}", line, summary);
+    }
+
+    // Times depend on when the test runs, so they are left out of the comparisons.
+    let without_times = |file: &str, time_fields: &[usize]| -> Vec<String> {
+        let text = fs::read_to_string(out.join(file)).expect(file);
+        text.lines()
+            .skip(1)
+            .map(|l| l.split(',').enumerate().filter(|(i, _)| !time_fields.contains(i)).map(|(_, f)| f).collect::<Vec<_>>().join(","))
+            .collect()
+    };
+    assert_eq!(
+        without_times("health.csv", &[2, 3]),
+        [
+            "ALPHA,4,60,0.571,1,WVHT;DPD;APD;MWD;WTMP;VIS;PTDY;TIDE,false",
+            "BRAVO,1,,1.000,0,WVHT;DPD;APD;MWD;ATMP;WTMP;VIS;PTDY;TIDE,false"
+        ]
+    );
+    assert_eq!(
+        without_times("exceedances.csv", &[5, 6]),
+        [
+            "ALPHA,WSPD,10.8,1,0.25",
+            "ALPHA,GST,17.2,0,0",
+            "ALPHA,WVHT,4,0,",
+            "BRAVO,WSPD,10.8,0,0",
+            "BRAVO,GST,17.2,0,0",
+            "BRAVO,WVHT,4,0,"
+        ]
+    );
+    assert_eq!(without_times("gaps.csv", &[1, 2]), ["ALPHA,240"]);
+    assert_eq!(
+        fs::read_to_string(out.join("wind_rose.csv")).expect("wind_rose.csv"),
+        "station_id,sector,direction,lower,upper,count,fraction\n\
+         ALPHA,SSW,202.5,2,4,2,0.5\n\
+         ALPHA,SSW,202.5,4,6,1,0.25\n\
+         ALPHA,SSW,202.5,10,,1,0.25\n\
+         BRAVO,SSW,202.5,8,10,1,1\n"
+    );
+    let written: serde_json::Value = serde_json::from_slice(&fs::read(out.join(INDEX_FILE)).expect("index.json")).expect("index JSON");
+    assert_eq!(written, serde_json::to_value(&index).expect("index value"));
+
+    // A second run over the same data gives byte-identical CSV and index files.
+    let again = scratch_dir("site-report-again");
+    report(&server, &again).await;
+    assert_eq!(tree(&again), tree(&out));
+    for file in ["stations.csv", "summary.csv", "health.csv", "exceedances.csv", "gaps.csv", "wind_rose.csv", "wave_rose.csv", INDEX_FILE] {
+        assert_eq!(fs::read(again.join(file)).expect("rerun file"), fs::read(out.join(file)).expect("first run file"), "{}", file);
+    }
+}
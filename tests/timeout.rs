@@ -0,0 +1,25 @@
+//! The per-request timeout bounds a server that never answers.
+
+mod common;
+
+use common::{MockServer, Route};
+use ndbc_data::{NdbcData, NdbcError};
+use std::time::{Duration, Instant};
+
+#[tokio::test]
+async fn slow_server_times_out_within_the_configured_window() {
+    let server = MockServer::start([("/data/realtime2/41001.txt", Route::ok("late").delay(Duration::from_secs(30)))]);
+    let data = NdbcData::builder()
+        .base_url(&server.url)
+        .in_memory()
+        .timeout(Duration::from_millis(500))
+        .max_retries(0)
+        .build()
+        .expect("build");
+
+    let started = Instant::now();
+    let result = data.fetch_station_raw("41001").await;
+    let elapsed = started.elapsed();
+    assert!(matches!(&result, Err(NdbcError::Http(e)) if e.is_timeout()), "{:?}", result);
+    assert!(elapsed >= Duration::from_millis(500) && elapsed < Duration::from_secs(5), "{:?}", elapsed);
+}